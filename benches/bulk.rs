@@ -0,0 +1,96 @@
+//! Compares parallel bulk parsing with parsing one file at a time.
+//!
+//! Run with `cargo bench --bench bulk [directories or jars...]`. Without arguments it
+//! uses the class files at the root of the repository.
+
+use std::{
+    hint::black_box,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use rust_java::classfile::{
+    archive::Archive,
+    bulk::{BulkParser, ClassInput},
+    ClassFile,
+};
+
+/// Minimum time spent measuring each approach.
+const MEASURE_FOR: Duration = Duration::from_secs(2);
+
+fn main() {
+    let mut roots: Vec<PathBuf> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .map(PathBuf::from)
+        .collect();
+    if roots.is_empty() {
+        roots.push(PathBuf::from(env!("CARGO_MANIFEST_DIR")));
+    }
+
+    for root in &roots {
+        if root.is_dir() {
+            let paths = ClassInput::from_directory(root, false).expect("readable directory");
+            let mapped = ClassInput::from_directory(root, true).expect("readable directory");
+            println!("{}: {} classes", root.display(), paths.len());
+            let serial = measure("serial ClassFile::parse", || {
+                for input in &paths {
+                    let ClassInput::Path(path) = input else {
+                        unreachable!()
+                    };
+                    let path = path.to_str().expect("UTF-8 path");
+                    black_box(ClassFile::parse(path).is_ok());
+                }
+            });
+            let parallel = measure("BulkParser, read", || bulk(&paths));
+            let parallel_mapped = measure("BulkParser, mapped", || bulk(&mapped));
+            println!(
+                "speedup: {:.1}x read, {:.1}x mapped\n",
+                serial / parallel,
+                serial / parallel_mapped
+            );
+        } else {
+            bench_archive(root);
+        }
+    }
+}
+
+fn bench_archive(path: &Path) {
+    let archive = Arc::new(Archive::open(path).expect("readable archive"));
+    let inputs = ClassInput::from_archive(Arc::clone(&archive));
+    println!("{}: {} classes", path.display(), inputs.len());
+    let serial = measure("serial", || {
+        for input in &inputs {
+            let ClassInput::ArchiveEntry { index, .. } = input else {
+                unreachable!()
+            };
+            let bytes = archive
+                .read(&archive.entries()[*index])
+                .expect("readable entry");
+            black_box(ClassFile::from_bytes(&bytes).is_ok());
+        }
+    });
+    let parallel = measure("BulkParser", || bulk(&inputs));
+    println!("speedup: {:.1}x\n", serial / parallel);
+}
+
+fn bulk(inputs: &[ClassInput]) {
+    BulkParser::default().for_each(inputs, |name, result| {
+        black_box((name, result.is_ok()));
+    });
+}
+
+/// Runs `parse_all` repeatedly and prints the mean time per run, which it returns in
+/// milliseconds.
+fn measure(name: &str, mut parse_all: impl FnMut()) -> f64 {
+    let mut rounds = 0u32;
+    let start = Instant::now();
+    while start.elapsed() < MEASURE_FOR {
+        parse_all();
+        rounds += 1;
+    }
+    let per_round = start.elapsed().as_secs_f64() * 1000.0 / rounds as f64;
+    println!("{name:<24} {per_round:>10.2} ms");
+    per_round
+}
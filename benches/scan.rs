@@ -0,0 +1,89 @@
+//! Compares the header-only scan with a full parse.
+//!
+//! Run with `cargo bench --bench scan [class files or directories...]`. Without arguments
+//! it uses the class files at the root of the repository.
+
+use std::{
+    fs,
+    hint::black_box,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use rust_java::classfile::{borrowed::ClassFileRef, scan::ClassSummary, ClassFile};
+
+/// Minimum time spent measuring each parser.
+const MEASURE_FOR: Duration = Duration::from_secs(2);
+
+fn main() {
+    let roots: Vec<PathBuf> = std::env::args()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .map(PathBuf::from)
+        .collect();
+    let mut classes: Vec<Vec<u8>> = vec![];
+    if roots.is_empty() {
+        collect_classes(Path::new(env!("CARGO_MANIFEST_DIR")), false, &mut classes);
+    }
+    for root in &roots {
+        collect_classes(root, true, &mut classes);
+    }
+    if classes.is_empty() {
+        eprintln!("no class files found");
+        std::process::exit(1);
+    }
+    let total_bytes: usize = classes.iter().map(Vec::len).sum();
+    println!("{} classes, {total_bytes} bytes", classes.len());
+
+    let full = measure("ClassFile::from_bytes", &classes, |bytes| {
+        ClassFile::from_bytes(bytes).is_ok()
+    });
+    let borrowed = measure("ClassFileRef::parse", &classes, |bytes| {
+        ClassFileRef::parse(bytes).is_ok()
+    });
+    let scan = measure("ClassSummary::scan", &classes, |bytes| {
+        ClassSummary::scan(bytes).is_ok()
+    });
+    println!(
+        "scan is {:.1}x faster than a full parse and {:.1}x faster than a borrowed parse",
+        full / scan,
+        borrowed / scan
+    );
+}
+
+/// Parses every class repeatedly and prints the mean time per class, which it returns in
+/// nanoseconds.
+fn measure(name: &str, classes: &[Vec<u8>], parse: impl Fn(&[u8]) -> bool) -> f64 {
+    let mut rounds = 0u32;
+    let start = Instant::now();
+    while start.elapsed() < MEASURE_FOR {
+        for bytes in classes {
+            assert!(black_box(parse(black_box(bytes))), "{name} failed");
+        }
+        rounds += 1;
+    }
+    let per_class = start.elapsed().as_nanos() as f64 / (rounds as f64 * classes.len() as f64);
+    println!("{name:<24} {per_class:>10.0} ns/class");
+    per_class
+}
+
+fn collect_classes(path: &Path, recursive: bool, classes: &mut Vec<Vec<u8>>) {
+    if path.is_dir() {
+        let Ok(entries) = fs::read_dir(path) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let entry_path = entry.path();
+            if recursive || entry_path.is_file() {
+                collect_classes(&entry_path, recursive, classes);
+            }
+        }
+    } else if path
+        .extension()
+        .is_some_and(|extension| extension == "class")
+    {
+        if let Ok(bytes) = fs::read(path) {
+            classes.push(bytes);
+        }
+    }
+}
@@ -0,0 +1,359 @@
+//! Access and property flags, one type per context they appear in (JVMS Tables 4.1-B,
+//! 4.5-A, 4.6-A, 4.7.6-A and §§4.7.24-4.7.25). Several bits mean different things in different
+//! contexts, e.g. 0x0020 is `ACC_SUPER` on a class but `ACC_SYNCHRONIZED` on a method,
+//! so a flag type only answers the queries that make sense where it was read.
+
+use std::fmt;
+
+pub const ACC_PUBLIC: u16 = 0x0001;
+pub const ACC_PRIVATE: u16 = 0x0002;
+pub const ACC_PROTECTED: u16 = 0x0004;
+pub const ACC_STATIC: u16 = 0x0008;
+pub const ACC_FINAL: u16 = 0x0010;
+pub const ACC_SUPER: u16 = 0x0020;
+pub const ACC_SYNCHRONIZED: u16 = 0x0020;
+pub const ACC_OPEN: u16 = 0x0020;
+pub const ACC_TRANSITIVE: u16 = 0x0020;
+pub const ACC_VOLATILE: u16 = 0x0040;
+pub const ACC_BRIDGE: u16 = 0x0040;
+pub const ACC_STATIC_PHASE: u16 = 0x0040;
+pub const ACC_TRANSIENT: u16 = 0x0080;
+pub const ACC_VARARGS: u16 = 0x0080;
+pub const ACC_NATIVE: u16 = 0x0100;
+pub const ACC_INTERFACE: u16 = 0x0200;
+pub const ACC_ABSTRACT: u16 = 0x0400;
+pub const ACC_STRICT: u16 = 0x0800;
+pub const ACC_SYNTHETIC: u16 = 0x1000;
+pub const ACC_ANNOTATION: u16 = 0x2000;
+pub const ACC_ENUM: u16 = 0x4000;
+pub const ACC_MODULE: u16 = 0x8000;
+pub const ACC_MANDATED: u16 = 0x8000;
+
+pub const ACC_VISIBILITY: u16 = ACC_PUBLIC | ACC_PRIVATE | ACC_PROTECTED;
+
+/// Declares a flag type for one context. `flags` lists every bit defined there with
+/// its query and its JVMS name, and `modifiers` lists the bits that have a Java keyword,
+/// in the order the JLS recommends writing them.
+macro_rules! access_flags {
+    (
+        $(#[$meta:meta])*
+        $name:ident {
+            flags: { $($query:ident => $flag:ident,)* }
+            modifiers: [$($modifier:ident => $keyword:literal,)*]
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize), serde(transparent))]
+        pub struct $name(u16);
+
+        impl $name {
+            /// Every bit this context defines.
+            pub const DEFINED: u16 = 0 $(| $flag)*;
+
+            pub const fn from_bits(bits: u16) -> $name {
+                $name(bits)
+            }
+
+            pub const fn bits(&self) -> u16 {
+                self.0
+            }
+
+            /// True if every bit of `flags` is set.
+            pub const fn contains(&self, flags: u16) -> bool {
+                self.0 & flags == flags
+            }
+
+            /// True if any bit of `flags` is set.
+            pub const fn intersects(&self, flags: u16) -> bool {
+                self.0 & flags != 0
+            }
+
+            $(
+                pub const fn $query(&self) -> bool {
+                    self.0 & $flag != 0
+                }
+            )*
+
+            /// The bits set that this context does not define. The JVM ignores them, but
+            /// they are reserved for future use and should be zero.
+            pub const fn illegal_bits(&self) -> u16 {
+                self.0 & !Self::DEFINED
+            }
+
+            pub const fn has_illegal_bits(&self) -> bool {
+                self.illegal_bits() != 0
+            }
+
+            /// The JVMS names of the defined bits that are set, lowest bit first.
+            pub fn names(&self) -> Vec<&'static str> {
+                self.select(&[$(($flag, stringify!($flag))),*])
+            }
+
+            /// The Java keywords for the set bits, in source order. Flags without a
+            /// keyword, such as `ACC_SYNTHETIC`, are left out.
+            pub fn modifiers(&self) -> Vec<&'static str> {
+                self.select(&[$(($modifier, $keyword)),*])
+            }
+
+            fn select(&self, table: &[(u16, &'static str)]) -> Vec<&'static str> {
+                table
+                    .iter()
+                    .filter(|&&(flag, _)| self.0 & flag != 0)
+                    .map(|&(_, name)| name)
+                    .collect()
+            }
+        }
+
+        impl From<u16> for $name {
+            fn from(bits: u16) -> $name {
+                $name(bits)
+            }
+        }
+
+        /// Writes the modifiers separated by spaces, e.g. `public static final`.
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.modifiers().join(" "))
+            }
+        }
+
+        /// Writes the bits along with the names of the flags they hold, e.g.
+        /// `0x0021 (ACC_PUBLIC | ACC_SUPER)`; undefined bits are shown as a hex mask.
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{:#06x} (", self.0)?;
+                let mut names = self.names().join(" | ");
+                if self.has_illegal_bits() {
+                    if !names.is_empty() {
+                        names.push_str(" | ");
+                    }
+                    names.push_str(&format!("{:#06x}", self.illegal_bits()));
+                }
+                write!(f, "{names})")
+            }
+        }
+    };
+}
+
+access_flags! {
+    /// `access_flags` of a `ClassFile`.
+    ClassAccessFlags {
+        flags: {
+            is_public => ACC_PUBLIC,
+            is_final => ACC_FINAL,
+            is_super => ACC_SUPER,
+            is_interface => ACC_INTERFACE,
+            is_abstract => ACC_ABSTRACT,
+            is_synthetic => ACC_SYNTHETIC,
+            is_annotation => ACC_ANNOTATION,
+            is_enum => ACC_ENUM,
+            is_module => ACC_MODULE,
+        }
+        modifiers: [
+            ACC_PUBLIC => "public",
+            ACC_ABSTRACT => "abstract",
+            ACC_FINAL => "final",
+        ]
+    }
+}
+
+access_flags! {
+    /// `access_flags` of a `field_info`.
+    FieldAccessFlags {
+        flags: {
+            is_public => ACC_PUBLIC,
+            is_private => ACC_PRIVATE,
+            is_protected => ACC_PROTECTED,
+            is_static => ACC_STATIC,
+            is_final => ACC_FINAL,
+            is_volatile => ACC_VOLATILE,
+            is_transient => ACC_TRANSIENT,
+            is_synthetic => ACC_SYNTHETIC,
+            is_enum => ACC_ENUM,
+        }
+        modifiers: [
+            ACC_PUBLIC => "public",
+            ACC_PROTECTED => "protected",
+            ACC_PRIVATE => "private",
+            ACC_STATIC => "static",
+            ACC_FINAL => "final",
+            ACC_TRANSIENT => "transient",
+            ACC_VOLATILE => "volatile",
+        ]
+    }
+}
+
+access_flags! {
+    /// `access_flags` of a `method_info`.
+    MethodAccessFlags {
+        flags: {
+            is_public => ACC_PUBLIC,
+            is_private => ACC_PRIVATE,
+            is_protected => ACC_PROTECTED,
+            is_static => ACC_STATIC,
+            is_final => ACC_FINAL,
+            is_synchronized => ACC_SYNCHRONIZED,
+            is_bridge => ACC_BRIDGE,
+            is_varargs => ACC_VARARGS,
+            is_native => ACC_NATIVE,
+            is_abstract => ACC_ABSTRACT,
+            is_strict => ACC_STRICT,
+            is_synthetic => ACC_SYNTHETIC,
+        }
+        modifiers: [
+            ACC_PUBLIC => "public",
+            ACC_PROTECTED => "protected",
+            ACC_PRIVATE => "private",
+            ACC_ABSTRACT => "abstract",
+            ACC_STATIC => "static",
+            ACC_FINAL => "final",
+            ACC_SYNCHRONIZED => "synchronized",
+            ACC_NATIVE => "native",
+            ACC_STRICT => "strictfp",
+        ]
+    }
+}
+
+access_flags! {
+    /// `inner_class_access_flags` of an InnerClasses entry: the flags of the nested
+    /// class as declared in source, which the class's own `access_flags` cannot hold.
+    InnerClassAccessFlags {
+        flags: {
+            is_public => ACC_PUBLIC,
+            is_private => ACC_PRIVATE,
+            is_protected => ACC_PROTECTED,
+            is_static => ACC_STATIC,
+            is_final => ACC_FINAL,
+            is_interface => ACC_INTERFACE,
+            is_abstract => ACC_ABSTRACT,
+            is_synthetic => ACC_SYNTHETIC,
+            is_annotation => ACC_ANNOTATION,
+            is_enum => ACC_ENUM,
+        }
+        modifiers: [
+            ACC_PUBLIC => "public",
+            ACC_PROTECTED => "protected",
+            ACC_PRIVATE => "private",
+            ACC_ABSTRACT => "abstract",
+            ACC_STATIC => "static",
+            ACC_FINAL => "final",
+        ]
+    }
+}
+
+access_flags! {
+    /// `access_flags` of a MethodParameters attribute `parameters` entry.
+    ParameterFlags {
+        flags: {
+            is_final => ACC_FINAL,
+            is_synthetic => ACC_SYNTHETIC,
+            is_mandated => ACC_MANDATED,
+        }
+        modifiers: [
+            ACC_FINAL => "final",
+        ]
+    }
+}
+
+access_flags! {
+    /// `module_flags` of the Module attribute.
+    ModuleFlags {
+        flags: {
+            is_open => ACC_OPEN,
+            is_synthetic => ACC_SYNTHETIC,
+            is_mandated => ACC_MANDATED,
+        }
+        modifiers: [
+            ACC_OPEN => "open",
+        ]
+    }
+}
+
+access_flags! {
+    /// `requires_flags` of a Module attribute `requires` entry.
+    RequiresFlags {
+        flags: {
+            is_transitive => ACC_TRANSITIVE,
+            is_static_phase => ACC_STATIC_PHASE,
+            is_synthetic => ACC_SYNTHETIC,
+            is_mandated => ACC_MANDATED,
+        }
+        modifiers: [
+            ACC_TRANSITIVE => "transitive",
+            ACC_STATIC_PHASE => "static",
+        ]
+    }
+}
+
+access_flags! {
+    /// `exports_flags` of a Module attribute `exports` entry. `opens_flags` define the
+    /// same bits and use this type too.
+    ExportsFlags {
+        flags: {
+            is_synthetic => ACC_SYNTHETIC,
+            is_mandated => ACC_MANDATED,
+        }
+        modifiers: []
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modifiers_follow_the_jls_order() {
+        let flags = MethodAccessFlags::from_bits(
+            ACC_FINAL | ACC_SYNCHRONIZED | ACC_STATIC | ACC_PUBLIC | ACC_SYNTHETIC,
+        );
+        assert_eq!(
+            flags.modifiers(),
+            ["public", "static", "final", "synchronized"]
+        );
+        assert_eq!(flags.to_string(), "public static final synchronized");
+
+        let flags = FieldAccessFlags::from_bits(ACC_VOLATILE | ACC_TRANSIENT | ACC_PROTECTED);
+        assert_eq!(flags.to_string(), "protected transient volatile");
+        let flags = ClassAccessFlags::from_bits(ACC_FINAL | ACC_SUPER | ACC_PUBLIC);
+        assert_eq!(flags.to_string(), "public final");
+        let flags = InnerClassAccessFlags::from_bits(ACC_STATIC | ACC_ABSTRACT | ACC_PRIVATE);
+        assert_eq!(flags.to_string(), "private abstract static");
+        assert_eq!(ParameterFlags::from_bits(ACC_MANDATED).to_string(), "");
+        assert_eq!(ParameterFlags::from_bits(ACC_FINAL).to_string(), "final");
+    }
+
+    #[test]
+    fn shared_bits_are_read_per_context() {
+        assert!(ClassAccessFlags::from_bits(0x0020).is_super());
+        assert!(MethodAccessFlags::from_bits(0x0020).is_synchronized());
+        assert!(MethodAccessFlags::from_bits(0x0040).is_bridge());
+        assert!(FieldAccessFlags::from_bits(0x0040).is_volatile());
+        assert!(RequiresFlags::from_bits(0x0040).is_static_phase());
+        assert!(ParameterFlags::from_bits(0x8000).is_mandated());
+        assert!(ClassAccessFlags::from_bits(0x8000).is_module());
+    }
+
+    #[test]
+    fn illegal_bits_are_the_undefined_ones() {
+        let flags = FieldAccessFlags::from_bits(ACC_PUBLIC | ACC_NATIVE | ACC_ABSTRACT);
+        assert_eq!(flags.illegal_bits(), ACC_NATIVE | ACC_ABSTRACT);
+        assert!(flags.has_illegal_bits());
+        assert!(!MethodAccessFlags::from_bits(ACC_PUBLIC | ACC_NATIVE).has_illegal_bits());
+        assert_eq!(ParameterFlags::from_bits(0x0011).illegal_bits(), ACC_PUBLIC);
+        assert_eq!(ExportsFlags::from_bits(0xFFFF).illegal_bits(), 0x6FFF);
+    }
+
+    #[test]
+    fn debug_names_every_bit() {
+        let flags = ClassAccessFlags::from_bits(ACC_PUBLIC | ACC_SUPER);
+        assert_eq!(format!("{flags:?}"), "0x0021 (ACC_PUBLIC | ACC_SUPER)");
+        let flags = FieldAccessFlags::from_bits(ACC_PRIVATE | ACC_NATIVE);
+        assert_eq!(format!("{flags:?}"), "0x0102 (ACC_PRIVATE | 0x0100)");
+        assert_eq!(format!("{:?}", ParameterFlags::default()), "0x0000 ()");
+        assert_eq!(
+            MethodAccessFlags::from_bits(ACC_VARARGS | ACC_SYNTHETIC).names(),
+            ["ACC_VARARGS", "ACC_SYNTHETIC"]
+        );
+    }
+}
@@ -1,4 +1,12 @@
+use std::io::Read;
+
+use crate::classfile::{
+    error::{ClassParseError, ParseErrorKind},
+    reader::ClassReader,
+};
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Annotation {
     pub type_index: u16,
     pub num_element_value_pairs: u16,
@@ -6,18 +14,21 @@ pub struct Annotation {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ElementValuePair {
     pub element_name_index: u16,
     pub value: ElementValue,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ElementValue {
     pub tag: u8,
     pub value: ElementValueEnum,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum ElementValueEnum {
     ConstantValueIndex(ConstValueElement),
     EnumConstValue(EnumConstValueElement),
@@ -27,23 +38,628 @@ pub enum ElementValueEnum {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ConstValueElement {
     pub const_value_index: u16,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct EnumConstValueElement {
     pub type_name_index: u16,
     pub const_name_index: u16,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ClassInfoIndexElement {
     pub class_info_index: u16,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ArrayValueElement {
-    num_values: u16,
-    values: Vec<ElementValue>,
+    pub num_values: u16,
+    pub values: Vec<ElementValue>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ParameterAnnotations {
+    pub num_annotations: u16,
+    pub annotations: Vec<Annotation>,
+}
+
+/// A type_annotation (JVMS §4.7.20): where in a type the annotation applies, followed
+/// by the annotation itself.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TypeAnnotation {
+    pub target_type: u8,
+    pub target_info: TargetInfo,
+    pub target_path: TypePath,
+    pub annotation: Annotation,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum TargetInfo {
+    TypeParameter {
+        type_parameter_index: u8,
+    },
+    Supertype {
+        supertype_index: u16,
+    },
+    TypeParameterBound {
+        type_parameter_index: u8,
+        bound_index: u8,
+    },
+    Empty,
+    FormalParameter {
+        formal_parameter_index: u8,
+    },
+    Throws {
+        throws_type_index: u16,
+    },
+    Localvar {
+        table_length: u16,
+        table: Vec<LocalvarTargetEntry>,
+    },
+    Catch {
+        exception_table_index: u16,
+    },
+    Offset {
+        offset: u16,
+    },
+    TypeArgument {
+        offset: u16,
+        type_argument_index: u8,
+    },
+}
+
+/// The bytecode range `start_pc..start_pc + length` in which local variable `index`
+/// has the annotated type.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LocalvarTargetEntry {
+    pub start_pc: u16,
+    pub length: u16,
+    pub index: u16,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TypePath {
+    pub path_length: u8,
+    pub path: Vec<TypePathEntry>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TypePathEntry {
+    pub type_path_kind: u8,
+    pub type_argument_index: u8,
+}
+
+/// How many annotations and arrays an element_value may be nested in.
+pub const MAX_ELEMENT_VALUE_DEPTH: usize = 64;
+
+pub const TYPE_PATH_ARRAY: u8 = 0;
+pub const TYPE_PATH_NESTED: u8 = 1;
+pub const TYPE_PATH_WILDCARD_BOUND: u8 = 2;
+pub const TYPE_PATH_TYPE_ARGUMENT: u8 = 3;
+
+const TARGET_CLASS_TYPE_PARAMETER: u8 = 0x00;
+const TARGET_METHOD_TYPE_PARAMETER: u8 = 0x01;
+const TARGET_SUPERTYPE: u8 = 0x10;
+const TARGET_CLASS_TYPE_PARAMETER_BOUND: u8 = 0x11;
+const TARGET_METHOD_TYPE_PARAMETER_BOUND: u8 = 0x12;
+const TARGET_FIELD: u8 = 0x13;
+const TARGET_METHOD_RETURN: u8 = 0x14;
+const TARGET_METHOD_RECEIVER: u8 = 0x15;
+const TARGET_METHOD_FORMAL_PARAMETER: u8 = 0x16;
+const TARGET_THROWS: u8 = 0x17;
+const TARGET_LOCAL_VARIABLE: u8 = 0x40;
+const TARGET_RESOURCE_VARIABLE: u8 = 0x41;
+const TARGET_EXCEPTION_PARAMETER: u8 = 0x42;
+const TARGET_INSTANCEOF: u8 = 0x43;
+const TARGET_METHOD_REFERENCE: u8 = 0x46;
+const TARGET_CAST: u8 = 0x47;
+const TARGET_METHOD_REFERENCE_TYPE_ARGUMENT: u8 = 0x4B;
+
+pub fn generate_type_annotations<R: Read>(
+    num_annotations: u16,
+    reader: &mut ClassReader<R>,
+) -> Result<Vec<TypeAnnotation>, ClassParseError> {
+    let mut annotations: Vec<TypeAnnotation> = vec![];
+    for annotation_index in 0..num_annotations {
+        annotations.push(reader.scoped(
+            format!("annotations[{annotation_index}]"),
+            generate_type_annotation,
+        )?);
+    }
+    Ok(annotations)
+}
+
+fn generate_type_annotation<R: Read>(
+    reader: &mut ClassReader<R>,
+) -> Result<TypeAnnotation, ClassParseError> {
+    let target_type_offset = reader.offset();
+    let target_type: u8 = reader.read_u8()?;
+    let target_info = match target_type {
+        TARGET_CLASS_TYPE_PARAMETER | TARGET_METHOD_TYPE_PARAMETER => TargetInfo::TypeParameter {
+            type_parameter_index: reader.read_u8()?,
+        },
+        TARGET_SUPERTYPE => TargetInfo::Supertype {
+            supertype_index: reader.read_u16()?,
+        },
+        TARGET_CLASS_TYPE_PARAMETER_BOUND | TARGET_METHOD_TYPE_PARAMETER_BOUND => {
+            let type_parameter_index: u8 = reader.read_u8()?;
+            let bound_index: u8 = reader.read_u8()?;
+            TargetInfo::TypeParameterBound {
+                type_parameter_index,
+                bound_index,
+            }
+        }
+        TARGET_FIELD | TARGET_METHOD_RETURN | TARGET_METHOD_RECEIVER => TargetInfo::Empty,
+        TARGET_METHOD_FORMAL_PARAMETER => TargetInfo::FormalParameter {
+            formal_parameter_index: reader.read_u8()?,
+        },
+        TARGET_THROWS => TargetInfo::Throws {
+            throws_type_index: reader.read_u16()?,
+        },
+        TARGET_LOCAL_VARIABLE | TARGET_RESOURCE_VARIABLE => {
+            let table_length: u16 = reader.read_u16()?;
+            let mut table: Vec<LocalvarTargetEntry> = vec![];
+            for entry_index in 0..table_length {
+                let entry = reader.scoped(format!("table[{entry_index}]"), |reader| {
+                    let start_pc: u16 = reader.read_u16()?;
+                    let length: u16 = reader.read_u16()?;
+                    let index: u16 = reader.read_u16()?;
+                    Ok(LocalvarTargetEntry {
+                        start_pc,
+                        length,
+                        index,
+                    })
+                })?;
+                table.push(entry);
+            }
+            TargetInfo::Localvar {
+                table_length,
+                table,
+            }
+        }
+        TARGET_EXCEPTION_PARAMETER => TargetInfo::Catch {
+            exception_table_index: reader.read_u16()?,
+        },
+        TARGET_INSTANCEOF..=TARGET_METHOD_REFERENCE => TargetInfo::Offset {
+            offset: reader.read_u16()?,
+        },
+        TARGET_CAST..=TARGET_METHOD_REFERENCE_TYPE_ARGUMENT => {
+            let offset: u16 = reader.read_u16()?;
+            let type_argument_index: u8 = reader.read_u8()?;
+            TargetInfo::TypeArgument {
+                offset,
+                type_argument_index,
+            }
+        }
+        _ => {
+            return Err(reader.error_at(
+                target_type_offset,
+                ParseErrorKind::UnknownTypeAnnotationTarget(target_type),
+            ));
+        }
+    };
+
+    let target_path = reader.scoped("target_path", |reader| {
+        let path_length: u8 = reader.read_u8()?;
+        let mut path: Vec<TypePathEntry> = vec![];
+        for _ in 0..path_length {
+            let type_path_kind: u8 = reader.read_u8()?;
+            let type_argument_index: u8 = reader.read_u8()?;
+            path.push(TypePathEntry {
+                type_path_kind,
+                type_argument_index,
+            });
+        }
+        Ok(TypePath { path_length, path })
+    })?;
+    let annotation = generate_annotation(reader)?;
+
+    Ok(TypeAnnotation {
+        target_type,
+        target_info,
+        target_path,
+        annotation,
+    })
+}
+
+pub fn generate_annotations<R: Read>(
+    num_annotations: u16,
+    reader: &mut ClassReader<R>,
+) -> Result<Vec<Annotation>, ClassParseError> {
+    let mut annotations: Vec<Annotation> = vec![];
+    for annotation_index in 0..num_annotations {
+        annotations.push(reader.scoped(
+            format!("annotations[{annotation_index}]"),
+            generate_annotation,
+        )?);
+    }
+    Ok(annotations)
+}
+
+pub fn generate_parameter_annotations<R: Read>(
+    num_parameters: u8,
+    reader: &mut ClassReader<R>,
+) -> Result<Vec<ParameterAnnotations>, ClassParseError> {
+    let mut parameter_annotations: Vec<ParameterAnnotations> = vec![];
+    for parameter_index in 0..num_parameters {
+        let parameter = reader.scoped(
+            format!("parameter_annotations[{parameter_index}]"),
+            |reader| {
+                let num_annotations: u16 = reader.read_u16()?;
+                let annotations = generate_annotations(num_annotations, reader)?;
+                Ok(ParameterAnnotations {
+                    num_annotations,
+                    annotations,
+                })
+            },
+        )?;
+        parameter_annotations.push(parameter);
+    }
+    Ok(parameter_annotations)
+}
+
+pub fn generate_annotation<R: Read>(
+    reader: &mut ClassReader<R>,
+) -> Result<Annotation, ClassParseError> {
+    generate_nested_annotation(reader, 0)
+}
+
+/// Reads an annotation whose element values sit `depth` levels of nested annotations and
+/// arrays below the attribute.
+fn generate_nested_annotation<R: Read>(
+    reader: &mut ClassReader<R>,
+    depth: usize,
+) -> Result<Annotation, ClassParseError> {
+    let type_index: u16 = reader.read_u16()?;
+    let num_element_value_pairs: u16 = reader.read_u16()?;
+
+    let mut element_value_pairs: Vec<ElementValuePair> = vec![];
+    for pair_index in 0..num_element_value_pairs {
+        let element_value_pair =
+            reader.scoped(format!("element_value_pairs[{pair_index}]"), |reader| {
+                let element_name_index: u16 = reader.read_u16()?;
+                let value = generate_nested_element_value(reader, depth)?;
+                Ok(ElementValuePair {
+                    element_name_index,
+                    value,
+                })
+            })?;
+        element_value_pairs.push(element_value_pair);
+    }
+    Ok(Annotation {
+        type_index,
+        num_element_value_pairs,
+        element_value_pairs,
+    })
+}
+
+/// Reads one element_value (JVMS §4.7.16.1), recursing into nested annotations and
+/// arrays. Values nested more than [`MAX_ELEMENT_VALUE_DEPTH`] deep are rejected, as each
+/// level costs only a few bytes of input but a stack frame to parse.
+pub fn generate_element_value<R: Read>(
+    reader: &mut ClassReader<R>,
+) -> Result<ElementValue, ClassParseError> {
+    generate_nested_element_value(reader, 0)
+}
+
+fn generate_nested_element_value<R: Read>(
+    reader: &mut ClassReader<R>,
+    depth: usize,
+) -> Result<ElementValue, ClassParseError> {
+    let tag_offset = reader.offset();
+    let tag: u8 = reader.read_u8()?;
+    if matches!(tag, b'@' | b'[') && depth >= MAX_ELEMENT_VALUE_DEPTH {
+        return Err(reader.error_at(
+            tag_offset,
+            ParseErrorKind::ElementValueTooDeep {
+                limit: MAX_ELEMENT_VALUE_DEPTH,
+            },
+        ));
+    }
+
+    let value = match tag as char {
+        's' | 'B' | 'C' | 'D' | 'F' | 'I' | 'J' | 'S' | 'Z' => {
+            let const_value_index: u16 = reader.read_u16()?;
+            ElementValueEnum::ConstantValueIndex(ConstValueElement { const_value_index })
+        }
+        'e' => {
+            let type_name_index: u16 = reader.read_u16()?;
+            let const_name_index: u16 = reader.read_u16()?;
+            ElementValueEnum::EnumConstValue(EnumConstValueElement {
+                type_name_index,
+                const_name_index,
+            })
+        }
+        'c' => {
+            let class_info_index: u16 = reader.read_u16()?;
+            ElementValueEnum::ClassInfoIndex(ClassInfoIndexElement { class_info_index })
+        }
+        '@' => ElementValueEnum::AnnotationValue(reader.scoped("annotation", |reader| {
+            generate_nested_annotation(reader, depth + 1)
+        })?),
+        '[' => {
+            let num_values: u16 = reader.read_u16()?;
+            let mut values: Vec<ElementValue> = vec![];
+            for value_index in 0..num_values {
+                values.push(reader.scoped(format!("values[{value_index}]"), |reader| {
+                    generate_nested_element_value(reader, depth + 1)
+                })?);
+            }
+            ElementValueEnum::ArrayValue(ArrayValueElement { num_values, values })
+        }
+        _ => {
+            return Err(reader.error_at(tag_offset, ParseErrorKind::UnknownElementValueTag(tag)));
+        }
+    };
+    Ok(ElementValue { tag, value })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classfile::{
+        attributes::AttributeType,
+        testing::{u16_bytes, ClassBuilder},
+        ClassFile,
+    };
+
+    /// An array value nested `depth` deep around an int constant.
+    fn nested_arrays(depth: usize) -> Vec<u8> {
+        let mut bytes = [b'[', 0, 1].repeat(depth);
+        bytes.extend([b'I', 0, 1]);
+        bytes
+    }
+
+    fn element_value(bytes: &[u8]) -> Result<ElementValue, ClassParseError> {
+        generate_element_value(&mut ClassReader::new(bytes))
+    }
+
+    fn depth(value: &ElementValue) -> usize {
+        match &value.value {
+            ElementValueEnum::ArrayValue(array) => {
+                1 + array.values.iter().map(depth).max().unwrap_or(0)
+            }
+            ElementValueEnum::AnnotationValue(annotation) => {
+                1 + annotation
+                    .element_value_pairs
+                    .iter()
+                    .map(|pair| depth(&pair.value))
+                    .max()
+                    .unwrap_or(0)
+            }
+            _ => 0,
+        }
+    }
+
+    #[test]
+    fn reads_every_kind_of_element_value() {
+        let bytes = [
+            &[b'[', 0, 5][..],
+            &[b's', 0, 1],
+            &[b'J', 0, 2],
+            &[b'e', 0, 3, 0, 4],
+            &[b'c', 0, 5],
+            &[b'@', 0, 6, 0, 1, 0, 7, b'Z', 0, 8],
+        ]
+        .concat();
+        let value = element_value(&bytes).unwrap();
+        let ElementValueEnum::ArrayValue(array) = &value.value else {
+            panic!("expected an array, got {value:?}");
+        };
+        let tags: Vec<u8> = array.values.iter().map(|value| value.tag).collect();
+        assert_eq!(tags, b"sJec@");
+        assert!(matches!(
+            array.values[2].value,
+            ElementValueEnum::EnumConstValue(EnumConstValueElement {
+                type_name_index: 3,
+                const_name_index: 4
+            })
+        ));
+        let ElementValueEnum::AnnotationValue(annotation) = &array.values[4].value else {
+            panic!("expected an annotation, got {:?}", array.values[4]);
+        };
+        assert_eq!(annotation.type_index, 6);
+        assert_eq!(annotation.element_value_pairs[0].element_name_index, 7);
+    }
+
+    #[test]
+    fn nesting_up_to_the_limit_is_accepted() {
+        let value = element_value(&nested_arrays(MAX_ELEMENT_VALUE_DEPTH)).unwrap();
+        assert_eq!(depth(&value), MAX_ELEMENT_VALUE_DEPTH);
+    }
+
+    #[test]
+    fn deeper_nesting_is_an_error() {
+        let err = element_value(&nested_arrays(MAX_ELEMENT_VALUE_DEPTH + 1)).unwrap_err();
+        assert!(matches!(
+            err.kind,
+            ParseErrorKind::ElementValueTooDeep {
+                limit: MAX_ELEMENT_VALUE_DEPTH
+            }
+        ));
+        assert_eq!(err.offset, 3 * MAX_ELEMENT_VALUE_DEPTH);
+    }
+
+    #[test]
+    fn nested_annotations_count_towards_the_limit() {
+        // @A(v = @A(v = @A(...))), seven bytes a level.
+        let mut bytes = [b'@', 0, 1, 0, 1, 0, 2].repeat(MAX_ELEMENT_VALUE_DEPTH + 1);
+        bytes.extend([b'I', 0, 1]);
+        let err = element_value(&bytes).unwrap_err();
+        assert!(matches!(
+            err.kind,
+            ParseErrorKind::ElementValueTooDeep { .. }
+        ));
+    }
+
+    #[test]
+    fn a_hostile_class_file_fails_without_overflowing_the_stack() {
+        let mut builder = ClassBuilder::new("Sample");
+        let type_index = builder.utf8("LA;");
+        let name_index = builder.utf8("value");
+        // One annotation with a single element nested about a million levels deep.
+        let mut body = u16_bytes(&[1, type_index, 1, name_index]);
+        body.extend(nested_arrays(1_000_000));
+        let attribute = builder.attribute("RuntimeVisibleAnnotations", &body);
+        builder.class_attribute(attribute);
+        let bytes = builder.build();
+
+        let err = ClassFile::from_bytes(&bytes).unwrap_err();
+        assert!(matches!(
+            err.kind,
+            ParseErrorKind::ElementValueTooDeep { .. }
+        ));
+        assert!(err.path.starts_with(
+            "attributes[0].RuntimeVisibleAnnotations.annotations[0].element_value_pairs[0].values[0]"
+        ));
+
+        let (class_file, diagnostics) = ClassFile::from_bytes_lenient(&bytes).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert!(matches!(
+            class_file.attributes[0],
+            AttributeType::Invalid { .. }
+        ));
+    }
+
+    const TYPE_ANNOTATIONS: &[u8] = include_bytes!("../../testdata/TypeAnnotations.class");
+
+    fn type_annotations(attributes: &[AttributeType]) -> &[TypeAnnotation] {
+        attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                AttributeType::RuntimeVisibleTypeAnnotations(attribute) => {
+                    Some(&attribute.annotations[..])
+                }
+                _ => None,
+            })
+            .expect("no RuntimeVisibleTypeAnnotations attribute")
+    }
+
+    /// Each annotation's target_type and target_info, as `javap -v` would list them.
+    fn targets(annotations: &[TypeAnnotation]) -> Vec<String> {
+        annotations
+            .iter()
+            .map(|annotation| {
+                format!(
+                    "{:#04x} {:?}",
+                    annotation.target_type, annotation.target_info
+                )
+            })
+            .collect()
+    }
+
+    fn path(annotation: &TypeAnnotation) -> Vec<(u8, u8)> {
+        assert_eq!(
+            annotation.target_path.path_length as usize,
+            annotation.target_path.path.len()
+        );
+        annotation
+            .target_path
+            .path
+            .iter()
+            .map(|entry| (entry.type_path_kind, entry.type_argument_index))
+            .collect()
+    }
+
+    #[test]
+    fn reads_the_type_annotations_on_a_class_declaration() {
+        let class_file = ClassFile::from_bytes(TYPE_ANNOTATIONS).unwrap();
+        let annotations = type_annotations(&class_file.attributes);
+        assert_eq!(
+            targets(annotations),
+            [
+                "0x10 Supertype { supertype_index: 0 }",
+                "0x00 TypeParameter { type_parameter_index: 0 }",
+                "0x11 TypeParameterBound { type_parameter_index: 0, bound_index: 0 }",
+            ]
+        );
+        assert!(annotations
+            .iter()
+            .all(|annotation| path(annotation).is_empty()));
+    }
+
+    #[test]
+    fn follows_a_type_path_into_nested_type_arguments() {
+        // Map<String, @Tag List<@Tag String @Tag []>>
+        let class_file = ClassFile::from_bytes(TYPE_ANNOTATIONS).unwrap();
+        let annotations = type_annotations(&class_file.fields[0].attributes);
+        assert_eq!(targets(annotations), ["0x13 Empty"; 3]);
+        let paths: Vec<_> = annotations.iter().map(path).collect();
+        assert_eq!(
+            paths,
+            [
+                vec![(TYPE_PATH_TYPE_ARGUMENT, 1)],
+                vec![(TYPE_PATH_TYPE_ARGUMENT, 1), (TYPE_PATH_TYPE_ARGUMENT, 0)],
+                vec![
+                    (TYPE_PATH_TYPE_ARGUMENT, 1),
+                    (TYPE_PATH_TYPE_ARGUMENT, 0),
+                    (TYPE_PATH_ARRAY, 0)
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_the_type_annotations_on_a_method_and_its_code() {
+        let class_file = ClassFile::from_bytes(TYPE_ANNOTATIONS).unwrap();
+        let convert = &class_file.methods[1];
+        assert_eq!(
+            targets(type_annotations(&convert.attributes)),
+            [
+                "0x17 Throws { throws_type_index: 0 }",
+                "0x14 Empty",
+                "0x16 FormalParameter { formal_parameter_index: 0 }",
+            ]
+        );
+
+        let code = convert
+            .attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                AttributeType::Code(code) => Some(code),
+                _ => None,
+            })
+            .unwrap();
+        let annotations = type_annotations(&code.attribute_info);
+        assert_eq!(
+            targets(annotations),
+            [
+                "0x44 Offset { offset: 0 }",
+                "0x43 Offset { offset: 9 }",
+                "0x47 TypeArgument { offset: 31, type_argument_index: 0 }",
+                "0x49 TypeArgument { offset: 47, type_argument_index: 0 }",
+                "0x40 Localvar { table_length: 1, table: [LocalvarTargetEntry { start_pc: 8, length: 55, index: 2 }] }",
+                "0x42 Catch { exception_table_index: 0 }",
+            ]
+        );
+        // Every one of them is a @Tag, whatever it is attached to.
+        let tag = annotations[0].annotation.type_index;
+        assert!(annotations
+            .iter()
+            .all(|annotation| annotation.annotation.type_index == tag));
+    }
+
+    #[test]
+    fn an_unknown_target_type_is_reported_at_the_target() {
+        // A field annotation with an empty path, then one with target_type 0x30.
+        let bytes = [&[TARGET_FIELD, 0, 0, 1, 0, 0][..], &[0x30, 0, 0, 1, 0, 0]].concat();
+        let err = generate_type_annotations(2, &mut ClassReader::new(&bytes[..])).unwrap_err();
+        assert!(matches!(
+            err.kind,
+            ParseErrorKind::UnknownTypeAnnotationTarget(0x30)
+        ));
+        assert_eq!(err.offset, 6);
+    }
 }
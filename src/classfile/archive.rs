@@ -0,0 +1,478 @@
+//! Just enough of the ZIP format to read class files out of jars: the central directory
+//! (including the ZIP64 extensions needed past 65535 entries or 4 GiB) and stored or
+//! deflated entries. The archive is memory mapped, so stored entries are never copied.
+
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use flate2::{read::DeflateDecoder, Crc};
+use memmap2::Mmap;
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x04034B50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x02014B50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06054B50;
+const ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06064B50;
+const ZIP64_LOCATOR_SIGNATURE: u32 = 0x07064B50;
+const ZIP64_EXTRA_FIELD: u16 = 0x0001;
+
+const END_OF_CENTRAL_DIRECTORY_LENGTH: usize = 22;
+const ZIP64_LOCATOR_LENGTH: usize = 20;
+const LOCAL_HEADER_LENGTH: usize = 30;
+const CENTRAL_HEADER_LENGTH: usize = 46;
+
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATED: u16 = 8;
+
+const MAX_PREALLOCATION: u64 = 1 << 24;
+
+pub struct Archive {
+    path: PathBuf,
+    data: ArchiveData,
+    entries: Vec<ArchiveEntry>,
+}
+
+enum ArchiveData {
+    Mapped(Mmap),
+    /// Mapping an empty file fails on some platforms, and there is nothing to map.
+    Empty,
+}
+
+#[derive(Debug, Clone)]
+pub struct ArchiveEntry {
+    pub name: String,
+    pub method: u16,
+    pub crc32: u32,
+    pub compressed_size: u64,
+    pub size: u64,
+    header_offset: u64,
+}
+
+impl ArchiveEntry {
+    pub fn is_class(&self) -> bool {
+        self.name.ends_with(".class")
+    }
+}
+
+impl Archive {
+    /// Opens a ZIP archive such as a jar and reads its central directory. The file is
+    /// memory mapped and must not be modified while the archive is open.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Archive> {
+        let path = path.as_ref().to_path_buf();
+        let file = File::open(&path)?;
+        let data = if file.metadata()?.len() == 0 {
+            ArchiveData::Empty
+        } else {
+            // SAFETY: the map is only read, and the caller is told not to modify the file
+            // while the archive is open; a concurrent truncation is the usual mmap caveat.
+            ArchiveData::Mapped(unsafe { Mmap::map(&file)? })
+        };
+        let mut archive = Archive {
+            path,
+            data,
+            entries: vec![],
+        };
+        archive.entries = read_central_directory(archive.bytes())?;
+        Ok(archive)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn entries(&self) -> &[ArchiveEntry] {
+        &self.entries
+    }
+
+    fn bytes(&self) -> &[u8] {
+        match &self.data {
+            ArchiveData::Mapped(map) => map,
+            ArchiveData::Empty => &[],
+        }
+    }
+
+    /// The uncompressed contents of `entry`, borrowed from the mapping when it is stored.
+    pub fn read(&self, entry: &ArchiveEntry) -> io::Result<Cow<'_, [u8]>> {
+        let bytes = self.bytes();
+        let header = slice(bytes, entry.header_offset, LOCAL_HEADER_LENGTH as u64)?;
+        if u32_at(header, 0) != LOCAL_HEADER_SIGNATURE {
+            return Err(invalid(format!("{}: bad local header", entry.name)));
+        }
+        let data_offset = entry.header_offset
+            + LOCAL_HEADER_LENGTH as u64
+            + u16_at(header, 26) as u64
+            + u16_at(header, 28) as u64;
+        let compressed = slice(bytes, data_offset, entry.compressed_size)?;
+        let contents: Cow<[u8]> = match entry.method {
+            METHOD_STORED => Cow::Borrowed(compressed),
+            METHOD_DEFLATED => {
+                // The declared size is only trusted as far as a plausible allocation.
+                let capacity = entry.size.min(MAX_PREALLOCATION) as usize;
+                let mut contents: Vec<u8> = Vec::with_capacity(capacity);
+                DeflateDecoder::new(compressed)
+                    .take(entry.size)
+                    .read_to_end(&mut contents)?;
+                Cow::Owned(contents)
+            }
+            method => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("{}: compression method {method}", entry.name),
+                ));
+            }
+        };
+        let mut crc = Crc::new();
+        crc.update(&contents);
+        if contents.len() as u64 != entry.size || crc.sum() != entry.crc32 {
+            return Err(invalid(format!(
+                "{}: contents do not match CRC or size",
+                entry.name
+            )));
+        }
+        Ok(contents)
+    }
+}
+
+fn read_central_directory(bytes: &[u8]) -> io::Result<Vec<ArchiveEntry>> {
+    let end = find_end_of_central_directory(bytes)?;
+    let mut entry_count = u16_at(bytes, end + 10) as u64;
+    let mut directory_size = u32_at(bytes, end + 12) as u64;
+    let mut directory_offset = u32_at(bytes, end + 16) as u64;
+
+    let has_locator = end >= ZIP64_LOCATOR_LENGTH
+        && u32_at(bytes, end - ZIP64_LOCATOR_LENGTH) == ZIP64_LOCATOR_SIGNATURE;
+    if has_locator {
+        let zip64_end = u64_at(bytes, end - ZIP64_LOCATOR_LENGTH + 8);
+        let record = slice(bytes, zip64_end, 56)?;
+        if u32_at(record, 0) != ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE {
+            return Err(invalid(
+                "bad ZIP64 end of central directory record".to_string(),
+            ));
+        }
+        entry_count = u64_at(record, 32);
+        directory_size = u64_at(record, 40);
+        directory_offset = u64_at(record, 48);
+    }
+
+    let directory = slice(bytes, directory_offset, directory_size)?;
+    let mut entries: Vec<ArchiveEntry> = Vec::with_capacity(entry_count.min(1 << 20) as usize);
+    let mut position = 0;
+    for _ in 0..entry_count {
+        let header = directory
+            .get(position..position + CENTRAL_HEADER_LENGTH)
+            .filter(|header| u32_at(header, 0) == CENTRAL_HEADER_SIGNATURE)
+            .ok_or_else(|| invalid("truncated central directory".to_string()))?;
+        let name_length = u16_at(header, 28) as usize;
+        let extra_length = u16_at(header, 30) as usize;
+        let comment_length = u16_at(header, 32) as usize;
+        let name_start = position + CENTRAL_HEADER_LENGTH;
+        let extra_start = name_start + name_length;
+        let next = extra_start + extra_length + comment_length;
+        if next > directory.len() {
+            return Err(invalid("truncated central directory".to_string()));
+        }
+        let mut entry = ArchiveEntry {
+            name: String::from_utf8_lossy(&directory[name_start..extra_start]).into_owned(),
+            method: u16_at(header, 10),
+            crc32: u32_at(header, 16),
+            compressed_size: u32_at(header, 20) as u64,
+            size: u32_at(header, 24) as u64,
+            header_offset: u32_at(header, 42) as u64,
+        };
+        apply_zip64_extra(
+            &mut entry,
+            &directory[extra_start..extra_start + extra_length],
+        );
+        entries.push(entry);
+        position = next;
+    }
+    Ok(entries)
+}
+
+/// The end of central directory record is the last thing in the file, followed only by
+/// a comment of up to 65535 bytes.
+fn find_end_of_central_directory(bytes: &[u8]) -> io::Result<usize> {
+    let last = bytes
+        .len()
+        .checked_sub(END_OF_CENTRAL_DIRECTORY_LENGTH)
+        .ok_or_else(|| invalid("not a ZIP archive".to_string()))?;
+    let first = last.saturating_sub(u16::MAX as usize);
+    (first..=last)
+        .rev()
+        .find(|&position| u32_at(bytes, position) == END_OF_CENTRAL_DIRECTORY_SIGNATURE)
+        .ok_or_else(|| invalid("not a ZIP archive".to_string()))
+}
+
+/// Replaces the sizes and offset that did not fit in 32 bits with their values from the
+/// ZIP64 extra field, which lists only those, in this order.
+fn apply_zip64_extra(entry: &mut ArchiveEntry, mut extra: &[u8]) {
+    while extra.len() >= 4 {
+        let id = u16_at(extra, 0);
+        let length = (u16_at(extra, 2) as usize).min(extra.len() - 4);
+        let mut data = &extra[4..4 + length];
+        if id == ZIP64_EXTRA_FIELD {
+            for value in [
+                &mut entry.size,
+                &mut entry.compressed_size,
+                &mut entry.header_offset,
+            ] {
+                if *value == u32::MAX as u64 && data.len() >= 8 {
+                    *value = u64_at(data, 0);
+                    data = &data[8..];
+                }
+            }
+            return;
+        }
+        extra = &extra[4 + length..];
+    }
+}
+
+fn slice(bytes: &[u8], offset: u64, length: u64) -> io::Result<&[u8]> {
+    offset
+        .checked_add(length)
+        .and_then(|end| bytes.get(usize::try_from(offset).ok()?..usize::try_from(end).ok()?))
+        .ok_or_else(|| invalid("entry extends past the end of the archive".to_string()))
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().expect("four bytes"))
+}
+
+fn u64_at(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().expect("eight bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Write};
+
+    use flate2::{write::DeflateEncoder, Compression};
+
+    use super::*;
+
+    struct TestEntry<'a> {
+        name: &'a str,
+        method: u16,
+        contents: &'a [u8],
+    }
+
+    /// Lays out a ZIP archive with a local header and data for each entry, then the
+    /// central directory and its end record.
+    fn zip(entries: &[TestEntry]) -> Vec<u8> {
+        let mut bytes: Vec<u8> = vec![];
+        let mut directory: Vec<u8> = vec![];
+        for entry in entries {
+            let data = match entry.method {
+                METHOD_DEFLATED => {
+                    let mut encoder = DeflateEncoder::new(vec![], Compression::default());
+                    encoder.write_all(entry.contents).unwrap();
+                    encoder.finish().unwrap()
+                }
+                _ => entry.contents.to_vec(),
+            };
+            let mut crc = Crc::new();
+            crc.update(entry.contents);
+            let header_offset = bytes.len() as u32;
+            let name = entry.name.as_bytes();
+
+            let mut common: Vec<u8> = vec![];
+            common.extend(20u16.to_le_bytes()); // version needed
+            common.extend(0u16.to_le_bytes()); // flags
+            common.extend(entry.method.to_le_bytes());
+            common.extend([0; 4]); // time and date
+            common.extend(crc.sum().to_le_bytes());
+            common.extend((data.len() as u32).to_le_bytes());
+            common.extend((entry.contents.len() as u32).to_le_bytes());
+            common.extend((name.len() as u16).to_le_bytes());
+            common.extend(0u16.to_le_bytes()); // extra length
+
+            bytes.extend(LOCAL_HEADER_SIGNATURE.to_le_bytes());
+            bytes.extend(&common);
+            bytes.extend(name);
+            bytes.extend(&data);
+
+            directory.extend(CENTRAL_HEADER_SIGNATURE.to_le_bytes());
+            directory.extend(20u16.to_le_bytes()); // version made by
+            directory.extend(&common);
+            directory.extend([0; 10]); // comment length, disk, attributes
+            directory.extend(header_offset.to_le_bytes());
+            directory.extend(name);
+        }
+        let directory_offset = bytes.len() as u32;
+        bytes.extend(&directory);
+        bytes.extend(END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+        bytes.extend([0; 4]); // disk numbers
+        bytes.extend((entries.len() as u16).to_le_bytes());
+        bytes.extend((entries.len() as u16).to_le_bytes());
+        bytes.extend((directory.len() as u32).to_le_bytes());
+        bytes.extend(directory_offset.to_le_bytes());
+        bytes.extend(0u16.to_le_bytes()); // comment length
+        bytes
+    }
+
+    fn sample() -> Vec<u8> {
+        zip(&[
+            TestEntry {
+                name: "META-INF/MANIFEST.MF",
+                method: METHOD_STORED,
+                contents: b"Manifest-Version: 1.0\r\n",
+            },
+            TestEntry {
+                name: "a/B.class",
+                method: METHOD_DEFLATED,
+                contents: &[0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 61].repeat(20),
+            },
+        ])
+    }
+
+    /// Writes `bytes` to a file of its own and opens it.
+    fn open(name: &str, bytes: &[u8]) -> io::Result<Archive> {
+        let path = std::env::temp_dir().join(format!(
+            "rust-java-archive-{}-{name}.zip",
+            std::process::id()
+        ));
+        fs::write(&path, bytes).unwrap();
+        let archive = Archive::open(&path);
+        fs::remove_file(&path).unwrap();
+        archive
+    }
+
+    /// Opens the archive and reads every entry, collecting the first error.
+    fn read_all(name: &str, bytes: &[u8]) -> io::Result<Vec<Vec<u8>>> {
+        let archive = open(name, bytes)?;
+        archive
+            .entries()
+            .iter()
+            .map(|entry| archive.read(entry).map(Cow::into_owned))
+            .collect()
+    }
+
+    #[test]
+    fn reads_stored_and_deflated_entries() {
+        let archive = open("valid", &sample()).unwrap();
+        let entries = archive.entries();
+        assert_eq!(entries.len(), 2);
+
+        assert_eq!(entries[0].name, "META-INF/MANIFEST.MF");
+        assert!(!entries[0].is_class());
+        let manifest = archive.read(&entries[0]).unwrap();
+        assert!(matches!(manifest, Cow::Borrowed(_)));
+        assert_eq!(&manifest[..], b"Manifest-Version: 1.0\r\n");
+
+        assert_eq!(entries[1].name, "a/B.class");
+        assert!(entries[1].is_class());
+        assert_eq!(entries[1].method, METHOD_DEFLATED);
+        assert!(entries[1].compressed_size < entries[1].size);
+        let class = archive.read(&entries[1]).unwrap();
+        assert_eq!(class.len(), 160);
+        assert_eq!(&class[..4], &[0xCA, 0xFE, 0xBA, 0xBE]);
+    }
+
+    #[test]
+    fn crc_mismatch_is_an_error() {
+        let mut bytes = sample();
+        // Flip a byte of the stored manifest's contents.
+        let contents = LOCAL_HEADER_LENGTH + "META-INF/MANIFEST.MF".len();
+        bytes[contents] ^= 0x20;
+        let archive = open("crc", &bytes).unwrap();
+        let err = archive.read(&archive.entries()[0]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            "META-INF/MANIFEST.MF: contents do not match CRC or size"
+        );
+        assert!(archive.read(&archive.entries()[1]).is_ok());
+    }
+
+    #[test]
+    fn truncated_central_directory_is_an_error() {
+        let mut bytes = sample();
+        let end = bytes.len() - END_OF_CENTRAL_DIRECTORY_LENGTH;
+        // Claim one more entry than the directory holds.
+        bytes[end + 10] = 3;
+        let err = open("directory", &bytes).err().unwrap();
+        assert_eq!(err.to_string(), "truncated central directory");
+
+        // Claim a directory that runs past the end record.
+        let mut bytes = sample();
+        let size = u32_at(&bytes, end + 12) + 100;
+        bytes[end + 12..end + 16].copy_from_slice(&size.to_le_bytes());
+        let err = open("directory-size", &bytes).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn non_archives_are_rejected() {
+        assert_eq!(
+            open("empty", &[]).err().unwrap().to_string(),
+            "not a ZIP archive"
+        );
+        let class = [0xCA, 0xFE, 0xBA, 0xBE].repeat(10);
+        assert_eq!(
+            open("class", &class).err().unwrap().to_string(),
+            "not a ZIP archive"
+        );
+    }
+
+    #[test]
+    fn unsupported_methods_are_an_error() {
+        let bytes = zip(&[TestEntry {
+            name: "a.bin",
+            method: 12,
+            contents: b"bzip2",
+        }]);
+        let archive = open("method", &bytes).unwrap();
+        let err = archive.read(&archive.entries()[0]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn damaged_archives_fail_without_panicking() {
+        let bytes = sample();
+        for length in 0..bytes.len() {
+            assert!(read_all("truncated", &bytes[..length]).is_err(), "{length}");
+        }
+        for position in 0..bytes.len() {
+            let mut damaged = bytes.clone();
+            damaged[position] ^= 0xFF;
+            // Only checks that nothing panics: some bytes, like the version fields, are
+            // not looked at.
+            let _ = read_all("damaged", &damaged);
+        }
+    }
+
+    #[test]
+    fn zip64_fields_replace_saturated_values() {
+        let mut entry = ArchiveEntry {
+            name: "big.class".to_string(),
+            method: METHOD_STORED,
+            crc32: 0,
+            compressed_size: 10,
+            size: u32::MAX as u64,
+            header_offset: u32::MAX as u64,
+        };
+        let mut extra: Vec<u8> = vec![];
+        extra.extend(0x5455u16.to_le_bytes()); // an unrelated extended timestamp field
+        extra.extend(1u16.to_le_bytes());
+        extra.push(0);
+        extra.extend(ZIP64_EXTRA_FIELD.to_le_bytes());
+        extra.extend(16u16.to_le_bytes());
+        extra.extend((5u64 << 32).to_le_bytes());
+        extra.extend((6u64 << 32).to_le_bytes());
+        apply_zip64_extra(&mut entry, &extra);
+        assert_eq!(entry.size, 5 << 32);
+        assert_eq!(entry.compressed_size, 10);
+        assert_eq!(entry.header_offset, 6 << 32);
+    }
+}
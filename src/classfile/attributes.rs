@@ -1,444 +1,704 @@
-use crate::{classfile::BYTE_LENGTH_UNAVAILABLE_ERROR, classfile::constant_pool::*};
-use byteorder::{BigEndian, ReadBytesExt};
-use std::{
-    fs::File,
-    io::{BufReader, Read},
-    str,
+use crate::classfile::{
+    access_flags::{
+        ExportsFlags, InnerClassAccessFlags, ModuleFlags, ParameterFlags, RequiresFlags,
+    },
+    constant_pool::*,
+    error::{ClassParseError, ParseErrorKind},
+    mutf8,
+    options::CustomAttribute,
+    reader::ClassReader,
+    smap::{Smap, SmapError},
+    version,
 };
+use std::{io::Read, sync::Arc};
+
+use super::stack_map::{generate_stack_map_frames, StackMapFrame};
 
 use super::annotations::{
-    Annotation, ClassInfoIndexElement, ConstValueElement, ElementValue, ElementValueEnum,
-    ElementValuePair, EnumConstValueElement,
+    generate_annotations, generate_element_value, generate_parameter_annotations,
+    generate_type_annotations, Annotation, ElementValue, ParameterAnnotations, TypeAnnotation,
 };
 
-pub fn generate_attributes(
+pub fn generate_attributes<R: Read>(
     attribute_count: u16,
-    constant_pool: &Vec<Option<Constant>>,
-    reader: &mut BufReader<File>,
-) -> Vec<AttributeType> {
+    constant_pool: &ConstantPool,
+    reader: &mut ClassReader<R>,
+) -> Result<Vec<AttributeType>, ClassParseError> {
     let mut attributes: Vec<AttributeType> = vec![];
 
     for attribute_index in 0..attribute_count {
-        let attribute_name_index: usize = reader
-            .read_u16::<BigEndian>()
-            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR)
-            .into();
-        let attribute_length: u32 = reader
-            .read_u32::<BigEndian>()
-            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-        let attribute_constant_entry = constant_pool
-            .get(attribute_name_index - 1)
-            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR)
-            .as_ref()
-            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-        println!(
-            "attribute_index: {attribute_index}, attribute length: {:?}, attribute_count: {:?}",
-            attribute_length, attribute_count
-        );
-        println!("attribute_constant_entry: {:?}", attribute_constant_entry);
-        match attribute_constant_entry {
-            Constant::Utf8Info(utf8_constant) => {
-                let attribute_name_index: u16 = attribute_name_index as u16;
-                println!("attribute name as string: {:?}", utf8_constant.utf_str);
-                match utf8_constant.utf_str.as_str() {
-                    CONSTANT_VALUE_STR => {
-                        let constantvalue_index: u16 = reader
-                            .read_u16::<BigEndian>()
-                            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                        let constant_value_attribute = AttributeConstantValue {
-                            attribute_length,
-                            attribute_name_index,
-                            constantvalue_index,
-                        };
-                        attributes.push(AttributeType::ConstantValue(constant_value_attribute));
-                    }
-                    CODE_STR => {
-                        let max_stack: u16 = reader
-                            .read_u16::<BigEndian>()
-                            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                        let max_locals: u16 = reader
-                            .read_u16::<BigEndian>()
-                            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                        let code_length: u32 = reader
-                            .read_u32::<BigEndian>()
-                            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-
-                        let mut code: Vec<u8> = vec![];
-                        for _ in 0..code_length {
-                            code.push(reader.read_u8().expect(BYTE_LENGTH_UNAVAILABLE_ERROR));
-                        }
-                        println!("code attribute inner code: {:?}", code);
-
-                        let exception_table_length: u16 = reader
-                            .read_u16::<BigEndian>()
-                            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                        let mut exception_table: Vec<ExceptionTableEntry> = vec![];
-                        for _ in 0..exception_table_length {
-                            let start_pc: u16 = reader
-                                .read_u16::<BigEndian>()
-                                .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                            let end_pc: u16 = reader
-                                .read_u16::<BigEndian>()
-                                .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                            let handler_pc: u16 = reader
-                                .read_u16::<BigEndian>()
-                                .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                            let catch_type: u16 = reader
-                                .read_u16::<BigEndian>()
-                                .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                            let exception: ExceptionTableEntry = ExceptionTableEntry {
-                                start_pc,
-                                end_pc,
-                                handler_pc,
-                                catch_type,
-                            };
-                            exception_table.push(exception);
-                        }
-                        let attributes_count: u16 = reader
-                            .read_u16::<BigEndian>()
-                            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                        let attribute_info =
-                            generate_attributes(attribute_count, constant_pool, reader);
-                        let code = AttributeCode {
-                            attribute_name_index,
-                            attribute_length,
-                            max_stack,
-                            max_locals,
-                            code_length,
-                            code,
-                            exception_table_length,
-                            exception_table,
-                            attributes_count,
-                            attribute_info,
-                        };
-                        attributes.push(AttributeType::Code(code));
-                    }
-                    STACKMAPTABLE_STR => {
-                        println!("attribute index: {:?}", attribute_index);
-                        println!("match stack map table");
-                    }
-                    EXCEPTIONS_STR => {
-                        println!("attribute index: {:?}", attribute_index);
-                        println!("match exceptions attribute string");
-                    }
-                    INNERCLASSES_STR => {
-                        let number_of_classes: u16 = reader
-                            .read_u16::<BigEndian>()
-                            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                        let mut classes: Vec<InnerClass> = vec![];
-                        for _ in 0..number_of_classes {
-                            let inner_class_info_index: u16 = reader
-                                .read_u16::<BigEndian>()
-                                .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                            let outer_class_info_index: u16 = reader
-                                .read_u16::<BigEndian>()
-                                .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                            let inner_name_index: u16 = reader
-                                .read_u16::<BigEndian>()
-                                .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                            let inner_class_access_flags: u16 = reader
-                                .read_u16::<BigEndian>()
-                                .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                            let inner_class: InnerClass = InnerClass {
-                                inner_class_info_index,
-                                outer_class_info_index,
-                                inner_name_index,
-                                inner_class_access_flags,
-                            };
-                            classes.push(inner_class);
-                        }
-                        let inner_class_attr: AttributeInnerClasses = AttributeInnerClasses {
-                            attribute_name_index,
-                            attribute_length,
-                            number_of_classes,
-                            classes,
-                        };
-                        attributes.push(AttributeType::InnerClasses(inner_class_attr));
-                        println!("match inner classes string");
+        let attribute = generate_attribute_at(attribute_index, constant_pool, reader)?;
+        attributes.push(attribute);
+    }
+    Ok(attributes)
+}
+
+/// Reads the entry at `attribute_index` of an attributes table, including its name and
+/// length.
+pub(crate) fn generate_attribute_at<R: Read>(
+    attribute_index: u16,
+    constant_pool: &ConstantPool,
+    reader: &mut ClassReader<R>,
+) -> Result<AttributeType, ClassParseError> {
+    reader.scoped(format!("attributes[{attribute_index}]"), |reader| {
+        let name_offset = reader.offset();
+        let attribute_name_index: u16 = reader.read_u16()?;
+        let attribute_length: u32 = reader.read_u32()?;
+        let attribute_name = match constant_pool.utf8(attribute_name_index) {
+            Ok(attribute_name) => attribute_name,
+            Err(err) => {
+                let kind = match err {
+                    ConstantPoolError::InvalidIndex(index) => {
+                        ParseErrorKind::InvalidConstantIndex(index)
                     }
-                    ENCLOSINGMETHODS_STR => {
-                        println!("match enclosing methods string");
+                    ConstantPoolError::UnexpectedKind { index, .. } => {
+                        ParseErrorKind::ExpectedUtf8(index)
                     }
-                    SYNTHETIC_STR => {
-                        println!("attribute index: {:?}", attribute_index);
-                        let attribute_length: u32 = reader
-                            .read_u32::<BigEndian>()
-                            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR); //this should be a fixed value of 0
-                        let synthetic_attribute = AttributeSynthetic {
-                            attribute_name_index,
-                            attribute_length,
-                        };
-                        attributes.push(AttributeType::Synthetic(synthetic_attribute));
+                };
+                let err = reader.error_at(name_offset, kind);
+                return generate_invalid_attribute(err, attribute_length, reader);
+            }
+        };
+        reader.describe(|| format!("{attribute_name} attribute_length={attribute_length}"));
+        let recognized = match version::attribute_since(attribute_name) {
+            Some(since) => reader.recognizes(since, || format!("the {attribute_name} attribute")),
+            None => true,
+        };
+        reader.scoped(attribute_name, |reader| {
+            reader.bounded(
+                attribute_length as usize,
+                |reader| {
+                    if !recognized {
+                        return Ok(AttributeType::Unknown {
+                            name: attribute_name.to_string(),
+                            bytes: reader.read_bytes(attribute_length as usize)?,
+                        });
                     }
-                    SIGNATURE_STR => {
-                        println!("attribute index: {:?}", attribute_index);
-                        let signature_index: u16 = reader
-                            .read_u16::<BigEndian>()
-                            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                        let signature_attribute: AttributeSignature = AttributeSignature {
-                            attribute_name_index,
-                            attribute_length,
+                    generate_attribute(
+                        attribute_name,
+                        attribute_name_index,
+                        attribute_length,
+                        constant_pool,
+                        reader,
+                    )
+                },
+                |bytes| AttributeType::Invalid {
+                    name: Some(attribute_name.to_string()),
+                    bytes,
+                },
+            )
+        })
+    })
+}
+
+pub(crate) fn generate_exception_table<R: Read>(
+    exception_table_length: u16,
+    reader: &mut ClassReader<R>,
+) -> Result<Vec<ExceptionTableEntry>, ClassParseError> {
+    let mut exception_table: Vec<ExceptionTableEntry> = vec![];
+    for exception_index in 0..exception_table_length {
+        let exception = reader.scoped(format!("exception_table[{exception_index}]"), |reader| {
+            let start_pc: u16 = reader.read_u16()?;
+            let end_pc: u16 = reader.read_u16()?;
+            let handler_pc: u16 = reader.read_u16()?;
+            let catch_type: u16 = reader.read_u16()?;
+            Ok(ExceptionTableEntry {
+                start_pc,
+                end_pc,
+                handler_pc,
+                catch_type,
+            })
+        })?;
+        exception_table.push(exception);
+    }
+    Ok(exception_table)
+}
+
+/// Handles an attribute whose name cannot be resolved: a lenient parse keeps its body as
+/// raw bytes, a strict one fails with `err`.
+fn generate_invalid_attribute<R: Read>(
+    err: ClassParseError,
+    attribute_length: u32,
+    reader: &mut ClassReader<R>,
+) -> Result<AttributeType, ClassParseError> {
+    reader.recover(err)?;
+    let bytes = reader.read_bytes(attribute_length as usize)?;
+    Ok(AttributeType::Invalid { name: None, bytes })
+}
+
+fn generate_attribute(
+    attribute_name: &str,
+    attribute_name_index: u16,
+    attribute_length: u32,
+    constant_pool: &ConstantPool,
+    reader: &mut ClassReader<&[u8]>,
+) -> Result<AttributeType, ClassParseError> {
+    let attribute = match attribute_name {
+        CONSTANT_VALUE_STR => {
+            let constantvalue_index: u16 = reader.read_u16()?;
+            let constant_value_attribute = AttributeConstantValue {
+                attribute_length,
+                attribute_name_index,
+                constantvalue_index,
+            };
+            AttributeType::ConstantValue(constant_value_attribute)
+        }
+        CODE_STR => {
+            let max_stack: u16 = reader.read_u16()?;
+            let max_locals: u16 = reader.read_u16()?;
+            let code_length: u32 = reader.read_u32()?;
+            let code: Vec<u8> =
+                reader.scoped("code", |reader| reader.read_bytes(code_length as usize))?;
+
+            let exception_table_length: u16 = reader.read_u16()?;
+            let exception_table = generate_exception_table(exception_table_length, reader)?;
+            let attributes_count: u16 = reader.read_u16()?;
+            let attribute_info = generate_attributes(attributes_count, constant_pool, reader)?;
+            let code = AttributeCode {
+                attribute_name_index,
+                attribute_length,
+                max_stack,
+                max_locals,
+                code_length,
+                code,
+                exception_table_length,
+                exception_table,
+                attributes_count,
+                attribute_info,
+            };
+            AttributeType::Code(code)
+        }
+        STACKMAPTABLE_STR => {
+            let number_of_entries: u16 = reader.read_u16()?;
+            let entries = generate_stack_map_frames(number_of_entries, reader)?;
+            let stack_map_table_attr = AttributeStackMapTable {
+                attribute_name_index,
+                attribute_length,
+                number_of_entries,
+                entries,
+            };
+            AttributeType::StackMapTable(stack_map_table_attr)
+        }
+        EXCEPTIONS_STR => {
+            let number_of_exceptions: u16 = reader.read_u16()?;
+            let exception_index_table = generate_index_table(number_of_exceptions, reader)?;
+            let exceptions_attr = AttributeExceptions {
+                attribute_name_index,
+                attribute_length,
+                number_of_exceptions,
+                exception_index_table,
+            };
+            AttributeType::Exceptions(exceptions_attr)
+        }
+        INNERCLASSES_STR => {
+            let number_of_classes: u16 = reader.read_u16()?;
+            let mut classes: Vec<InnerClass> = vec![];
+            for class_index in 0..number_of_classes {
+                let inner_class = reader.scoped(format!("classes[{class_index}]"), |reader| {
+                    let inner_class_info_index: u16 = reader.read_u16()?;
+                    let outer_class_info_index: u16 = reader.read_u16()?;
+                    let inner_name_index: u16 = reader.read_u16()?;
+                    let inner_class_access_flags =
+                        InnerClassAccessFlags::from_bits(reader.read_u16()?);
+                    Ok(InnerClass {
+                        inner_class_info_index,
+                        outer_class_info_index,
+                        inner_name_index,
+                        inner_class_access_flags,
+                    })
+                })?;
+                classes.push(inner_class);
+            }
+            let inner_class_attr: AttributeInnerClasses = AttributeInnerClasses {
+                attribute_name_index,
+                attribute_length,
+                number_of_classes,
+                classes,
+            };
+            AttributeType::InnerClasses(inner_class_attr)
+        }
+        ENCLOSINGMETHODS_STR => {
+            let class_index: u16 = reader.read_u16()?;
+            let method_index: u16 = reader.read_u16()?;
+            let enclosing_method_attr = AttributeEnclosingMethod {
+                attribute_name_index,
+                attribute_length,
+                class_index,
+                method_index,
+            };
+            AttributeType::EnclosingMethods(enclosing_method_attr)
+        }
+        SYNTHETIC_STR => {
+            let synthetic_attribute = AttributeSynthetic {
+                attribute_name_index,
+                attribute_length,
+            };
+            AttributeType::Synthetic(synthetic_attribute)
+        }
+        SIGNATURE_STR => {
+            let signature_index: u16 = reader.read_u16()?;
+            let signature_attribute: AttributeSignature = AttributeSignature {
+                attribute_name_index,
+                attribute_length,
+                signature_index,
+            };
+            AttributeType::Signature(signature_attribute)
+        }
+        SOURCEFILE_STR => {
+            let sourcefile_index: u16 = reader.read_u16()?;
+            let sourcefile_attr: AttributeSourceFile = AttributeSourceFile {
+                attribute_name_index,
+                attribute_length,
+                sourcefile_index,
+            };
+            AttributeType::SourceFile(sourcefile_attr)
+        }
+        SOURCEDEBUGEXTENSION_STR => {
+            let debug_extension: Vec<u8> = reader.read_bytes(attribute_length as usize)?;
+            let source_debug_extension_attr: AttributeSourceDebugExtension =
+                AttributeSourceDebugExtension {
+                    attribute_name_index,
+                    attribute_length,
+                    debug_extension,
+                };
+            AttributeType::SourceDebugExtension(source_debug_extension_attr)
+        }
+        LINENUMBERTABLE_STR => {
+            let line_number_table_length: u16 = reader.read_u16()?;
+            let mut line_number_table: Vec<LineNumberTableElement> = vec![];
+            for line_index in 0..line_number_table_length {
+                let entry =
+                    reader.scoped(format!("line_number_table[{line_index}]"), |reader| {
+                        let start_pc: u16 = reader.read_u16()?;
+                        let line_number: u16 = reader.read_u16()?;
+                        Ok(LineNumberTableElement {
+                            start_pc,
+                            line_number,
+                        })
+                    })?;
+                line_number_table.push(entry);
+            }
+            let line_number_table_attr = AttributeLineNumberTable {
+                attribute_name_index,
+                attribute_length,
+                line_number_table_length,
+                line_number_table,
+            };
+            AttributeType::LineNumberTable(line_number_table_attr)
+        }
+        LOCALVARIABLETABLE_STR => {
+            let local_variable_table_length: u16 = reader.read_u16()?;
+            let mut local_variable_table: Vec<LocalVariableTableElement> = vec![];
+            for variable_index in 0..local_variable_table_length {
+                let entry = reader.scoped(
+                    format!("local_variable_table[{variable_index}]"),
+                    |reader| {
+                        let start_pc: u16 = reader.read_u16()?;
+                        let length: u16 = reader.read_u16()?;
+                        let name_index: u16 = reader.read_u16()?;
+                        let descriptor_index: u16 = reader.read_u16()?;
+                        let index: u16 = reader.read_u16()?;
+                        Ok(LocalVariableTableElement {
+                            start_pc,
+                            length,
+                            name_index,
+                            descriptor_index,
+                            index,
+                        })
+                    },
+                )?;
+                local_variable_table.push(entry);
+            }
+            let local_variable_table_attr = AttributeLocalVariableTable {
+                attribute_name_index,
+                attribute_length,
+                local_variable_table_length,
+                local_variable_table,
+            };
+            AttributeType::LocalVariableTable(local_variable_table_attr)
+        }
+        LOCALVARIABLETYPETABLE_STR => {
+            let local_variable_type_table_length: u16 = reader.read_u16()?;
+            let mut local_variable_type_table: Vec<LocalVariableTypeTableElement> = vec![];
+            for variable_index in 0..local_variable_type_table_length {
+                let entry = reader.scoped(
+                    format!("local_variable_type_table[{variable_index}]"),
+                    |reader| {
+                        let start_pc: u16 = reader.read_u16()?;
+                        let length: u16 = reader.read_u16()?;
+                        let name_index: u16 = reader.read_u16()?;
+                        let signature_index: u16 = reader.read_u16()?;
+                        let index: u16 = reader.read_u16()?;
+                        Ok(LocalVariableTypeTableElement {
+                            start_pc,
+                            length,
+                            name_index,
                             signature_index,
-                        };
-                        attributes.push(AttributeType::Signature(signature_attribute));
-                    }
-                    SOURCEFILE_STR => {
-                        let sourcefile_index: u16 = reader
-                            .read_u16::<BigEndian>()
-                            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                        let sourcefile_attr: AttributeSourceFile = AttributeSourceFile {
-                            attribute_name_index,
-                            attribute_length,
-                            sourcefile_index,
-                        };
-                        attributes.push(AttributeType::SourceFile(sourcefile_attr));
-                        println!("match source file string");
-                    }
-                    SOURCEDEBUGEXTENSION_STR => {
-                        println!("match source debug extension string");
-                    }
-                    LINENUMBERTABLE_STR => {
-                        let line_number_table_length: u16 = reader
-                            .read_u16::<BigEndian>()
-                            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                        let mut line_number_table: Vec<LineNumberTable_Element> = vec![];
-                        for _ in 0..line_number_table_length {
-                            let start_pc: u16 = reader
-                                .read_u16::<BigEndian>()
-                                .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                            let line_number: u16 = reader
-                                .read_u16::<BigEndian>()
-                                .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                            let entry: LineNumberTable_Element = LineNumberTable_Element {
-                                start_pc,
-                                line_number,
-                            };
-                            line_number_table.push(entry);
-                        }
-                        let line_number_table_attr = AttributeLineNumberTable {
-                            attribute_name_index,
-                            attribute_length,
-                            line_number_table_length,
-                            line_number_table,
-                        };
-                        attributes.push(AttributeType::LineNumberTable(line_number_table_attr));
-                    }
-                    LOCALVARIABLETABLE_STR => {
-                        println!("match localvariable table string");
-                    }
-                    LOCALVARIABLETYPETABLE_STR => {
-                        println!("match local variable type table string");
-                    }
-                    DEPRECATED_STR => {
-                        let deprecated_attribute = AttributeDeprecated {
-                            attribute_name_index,
-                            attribute_length,
-                        };
-                        attributes.push(AttributeType::Deprecated(deprecated_attribute));
-                    }
-                    RUNTIMEINVISIBLEANNOTATIONS_STR => {
-                        println!("attribute index: {:?}", attribute_index);
-                        println!("match runtime invisible annotations");
-                    }
-                    RUNTIMEINVISIBLEPARAMETERANNOTATIONS_STR => {
-                        println!("attribute index: {:?}", attribute_index);
-                        println!("match runtime invisible parameter annotations");
-                    }
-                    RUNTIMEVISIBLEPARAMETERANNOTATIONS_STR => {
-                        println!("attribute index: {:?}", attribute_index);
-                        println!("match runtime visible parameter annotations");
-                    }
-                    ANNOTATIONDEFAULT_STR => {
-                        println!("match annotation default string");
-                    }
-                    BOOTSTRAPMETHODS_STR => {
-                        println!("attribute index: {:?}", attribute_index);
-                        println!("match bootstrap methods");
-                        let num_bootstrap_methods: u16 = reader
-                            .read_u16::<BigEndian>()
-                            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                        let mut bootstrap_methods: Vec<BootstrapMethodElement> = vec![];
-                        for _ in 0..num_bootstrap_methods {
-                            let bootstrap_method_ref: u16 = reader
-                                .read_u16::<BigEndian>()
-                                .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                            let num_bootstrap_arguments: u16 = reader
-                                .read_u16::<BigEndian>()
-                                .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                            let mut bootstrap_arguments: Vec<u16> = vec![];
-                            for _ in 0..num_bootstrap_arguments {
-                                let argument = reader
-                                    .read_u16::<BigEndian>()
-                                    .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                                bootstrap_arguments.push(argument);
-                            }
-                            let method: BootstrapMethodElement = BootstrapMethodElement {
-                                bootstrap_method_ref,
-                                num_bootstrap_arguments,
-                                bootstrap_arguments,
-                            };
-                            bootstrap_methods.push(method);
-                        }
-                        let bootstrap_attr: AttributeBootstrapMethods = AttributeBootstrapMethods {
-                            attribute_name_index,
-                            attribute_length,
-                            num_bootstrap_methods,
-                            bootstrap_methods,
-                        };
-                        attributes.push(AttributeType::BootstrapMethods(bootstrap_attr));
-                    }
-                    RUNTIMEVISIBLEANNOTATIONS_STR => {
-                        println!("attribute index: {:?}", attribute_index);
-                        let name = constant_pool
-                            .get(attribute_index as usize)
-                            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR)
-                            .as_ref()
-                            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-
-                        if attribute_length > 0 {
-                            println!(
-                                "name at the attribute index {attribute_index} is: {:?}",
-                                name
-                            );
-
-                            let num_annotations: u16 = reader
-                                .read_u16::<BigEndian>()
-                                .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                            let mut annotations: Vec<Annotation> = vec![];
-                            for _ in 0..num_annotations {
-                                let type_index: u16 = reader
-                                    .read_u16::<BigEndian>()
-                                    .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                                let num_element_value_pairs: u16 = reader
-                                    .read_u16::<BigEndian>()
-                                    .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-
-                                let mut element_value_pairs: Vec<ElementValuePair> = vec![];
-                                println!("num_element_value_pairs: {num_element_value_pairs}");
-                                for _ in 0..num_element_value_pairs {
-                                    let element_name_index: u16 = reader
-                                        .read_u16::<BigEndian>()
-                                        .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                                    let name = constant_pool
-                                        .get(element_name_index as usize)
-                                        .expect(BYTE_LENGTH_UNAVAILABLE_ERROR)
-                                        .as_ref()
-                                        .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                                    println!("name at element_name_index: {element_name_index}, is: {:?}", name);
-                                    let tag: u8 =
-                                        reader.read_u8().expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                                    println!("tag found: {:?}", tag);
-                                    let tag_char = tag as char;
-
-                                    let value: ElementValue = match tag_char {
-                                        's' | 'B' | 'C' | 'D' | 'F' | 'I' | 'J' | 'S' | 'Z' => {
-                                            let const_value_index: u16 = reader
-                                                .read_u16::<BigEndian>()
-                                                .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                                            let constant_value: ConstValueElement =
-                                                ConstValueElement { const_value_index };
-                                            ElementValue {
-                                                tag,
-                                                value: ElementValueEnum::ConstantValueIndex(
-                                                    constant_value,
-                                                ),
-                                            }
-                                        }
-                                        'e' => {
-                                            let type_name_index: u16 = reader
-                                                .read_u16::<BigEndian>()
-                                                .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                                            let const_name_index: u16 = reader
-                                                .read_u16::<BigEndian>()
-                                                .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                                            let enum_const_value: EnumConstValueElement =
-                                                EnumConstValueElement {
-                                                    type_name_index,
-                                                    const_name_index,
-                                                };
-                                            ElementValue {
-                                                tag,
-                                                value: ElementValueEnum::EnumConstValue(
-                                                    enum_const_value,
-                                                ),
-                                            }
-                                        }
-                                        'c' => {
-                                            let class_info_index: u16 = reader
-                                                .read_u16::<BigEndian>()
-                                                .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                                            let class_const =
-                                                ClassInfoIndexElement { class_info_index };
-                                            ElementValue {
-                                                tag,
-                                                value: ElementValueEnum::ClassInfoIndex(
-                                                    class_const,
-                                                ),
-                                            }
-                                        }
-                                        '@' => {
-                                            panic!("@ tag found for annotation");
-                                        }
-                                        '[' => {
-                                            panic!("[ value found for annotation");
-                                        }
-                                        _ => {
-                                            // println!("constant pool is: {:?}", constant_pool);
-                                            panic!("No valid tag value found for annotation: {:?}, num of annotations: {:?}, num of value pairs: {:?}", tag, num_annotations, num_element_value_pairs);
-                                        }
-                                    };
-
-                                    let element_value_pair: ElementValuePair = ElementValuePair {
-                                        element_name_index,
-                                        value,
-                                    };
-                                    element_value_pairs.push(element_value_pair);
-                                }
-                                let annotation: Annotation = Annotation {
-                                    type_index,
-                                    num_element_value_pairs,
-                                    element_value_pairs,
-                                };
-                                annotations.push(annotation);
-                            }
-                            let runtime_visible_annotation: AttributeRuntimeVisibleAnnotations =
-                                AttributeRuntimeVisibleAnnotations {
-                                    attribute_name_index,
-                                    attribute_length,
-                                    num_annotations,
-                                    annotations,
-                                };
-                            attributes.push(AttributeType::RuntimeVisibleAnnotations(
-                                runtime_visible_annotation,
-                            ));
+                            index,
+                        })
+                    },
+                )?;
+                local_variable_type_table.push(entry);
+            }
+            let local_variable_type_table_attr = AttributeLocalVariableTypeTable {
+                attribute_name_index,
+                attribute_length,
+                local_variable_type_table_length,
+                local_variable_type_table,
+            };
+            AttributeType::LocalVariableTypeTable(local_variable_type_table_attr)
+        }
+        METHODPARAMETERS_STR => {
+            let parameters_count: u8 = reader.read_u8()?;
+            let mut parameters: Vec<MethodParameter> = vec![];
+            for parameter_index in 0..parameters_count {
+                let parameter =
+                    reader.scoped(format!("parameters[{parameter_index}]"), |reader| {
+                        let name_index: u16 = reader.read_u16()?;
+                        let access_flags = ParameterFlags::from_bits(reader.read_u16()?);
+                        Ok(MethodParameter {
+                            name_index,
+                            access_flags,
+                        })
+                    })?;
+                parameters.push(parameter);
+            }
+            let method_parameters_attr = AttributeMethodParameters {
+                attribute_name_index,
+                attribute_length,
+                parameters_count,
+                parameters,
+            };
+            AttributeType::MethodParameters(method_parameters_attr)
+        }
+        DEPRECATED_STR => {
+            let deprecated_attribute = AttributeDeprecated {
+                attribute_name_index,
+                attribute_length,
+            };
+            AttributeType::Deprecated(deprecated_attribute)
+        }
+        RUNTIMEVISIBLEANNOTATIONS_STR => {
+            let num_annotations: u16 = reader.read_u16()?;
+            let annotations = generate_annotations(num_annotations, reader)?;
+            let runtime_visible_annotation: AttributeRuntimeVisibleAnnotations =
+                AttributeRuntimeVisibleAnnotations {
+                    attribute_name_index,
+                    attribute_length,
+                    num_annotations,
+                    annotations,
+                };
+            AttributeType::RuntimeVisibleAnnotations(runtime_visible_annotation)
+        }
+        RUNTIMEINVISIBLEANNOTATIONS_STR => {
+            let num_annotations: u16 = reader.read_u16()?;
+            let annotations = generate_annotations(num_annotations, reader)?;
+            let runtime_invisible_annotation = AttributeRuntimeInvisibleAnnotations {
+                attribute_name_index,
+                attribute_length,
+                num_annotations,
+                annotations,
+            };
+            AttributeType::RuntimeInvisibleAnnotations(runtime_invisible_annotation)
+        }
+        RUNTIMEVISIBLEPARAMETERANNOTATIONS_STR => {
+            let num_parameters: u8 = reader.read_u8()?;
+            let parameter_annotations = generate_parameter_annotations(num_parameters, reader)?;
+            let runtime_visible_parameter_annotations =
+                AttributeRuntimeVisibleParameterAnnotations {
+                    attribute_name_index,
+                    attribute_length,
+                    num_parameters,
+                    parameter_annotations,
+                };
+            AttributeType::RuntimeVisibleParameterAnnotations(runtime_visible_parameter_annotations)
+        }
+        RUNTIMEINVISIBLEPARAMETERANNOTATIONS_STR => {
+            let num_parameters: u8 = reader.read_u8()?;
+            let parameter_annotations = generate_parameter_annotations(num_parameters, reader)?;
+            let runtime_invisible_parameter_annotations =
+                AttributeRuntimeInvisibleParameterAnnotations {
+                    attribute_name_index,
+                    attribute_length,
+                    num_parameters,
+                    parameter_annotations,
+                };
+            AttributeType::RuntimeInvisibleParameterAnnotations(
+                runtime_invisible_parameter_annotations,
+            )
+        }
+        RUNTIMEVISIBLETYPEANNOTATIONS_STR => {
+            let num_annotations: u16 = reader.read_u16()?;
+            let annotations = generate_type_annotations(num_annotations, reader)?;
+            let runtime_visible_type_annotations = AttributeRuntimeVisibleTypeAnnotations {
+                attribute_name_index,
+                attribute_length,
+                num_annotations,
+                annotations,
+            };
+            AttributeType::RuntimeVisibleTypeAnnotations(runtime_visible_type_annotations)
+        }
+        RUNTIMEINVISIBLETYPEANNOTATIONS_STR => {
+            let num_annotations: u16 = reader.read_u16()?;
+            let annotations = generate_type_annotations(num_annotations, reader)?;
+            let runtime_invisible_type_annotations = AttributeRuntimeInvisibleTypeAnnotations {
+                attribute_name_index,
+                attribute_length,
+                num_annotations,
+                annotations,
+            };
+            AttributeType::RuntimeInvisibleTypeAnnotations(runtime_invisible_type_annotations)
+        }
+        ANNOTATIONDEFAULT_STR => {
+            let default_value = reader.scoped("default_value", generate_element_value)?;
+            let annotation_default_attr = AttributeAnnotationDefault {
+                attribute_name_index,
+                attribute_length,
+                default_value,
+            };
+            AttributeType::AnnotationDefault(annotation_default_attr)
+        }
+        BOOTSTRAPMETHODS_STR => {
+            let num_bootstrap_methods: u16 = reader.read_u16()?;
+            let mut bootstrap_methods: Vec<BootstrapMethodElement> = vec![];
+            for method_index in 0..num_bootstrap_methods {
+                let method =
+                    reader.scoped(format!("bootstrap_methods[{method_index}]"), |reader| {
+                        let bootstrap_method_ref: u16 = reader.read_u16()?;
+                        let num_bootstrap_arguments: u16 = reader.read_u16()?;
+                        let mut bootstrap_arguments: Vec<u16> = vec![];
+                        for _ in 0..num_bootstrap_arguments {
+                            bootstrap_arguments.push(reader.read_u16()?);
                         }
-                        let runtime_visible_annotation: AttributeRuntimeVisibleAnnotations =
-                            AttributeRuntimeVisibleAnnotations {
-                                attribute_name_index,
-                                attribute_length,
-                                num_annotations: 0,
-                                annotations: vec![],
-                            };
-                        attributes.push(AttributeType::RuntimeVisibleAnnotations(
-                            runtime_visible_annotation,
-                        ));
-                    }
-                    _ => {
-                        println!("about to panic. attribute name was: {:?}, attribute_length is: {attribute_length}", utf8_constant.utf_str.as_str());
-                        let mut buffer = vec![0u8; attribute_length as usize];
-                        let _ = reader.read_exact(&mut buffer);
-                    }
-                }
+                        Ok(BootstrapMethodElement {
+                            bootstrap_method_ref,
+                            num_bootstrap_arguments,
+                            bootstrap_arguments,
+                        })
+                    })?;
+                bootstrap_methods.push(method);
+            }
+            let bootstrap_attr: AttributeBootstrapMethods = AttributeBootstrapMethods {
+                attribute_name_index,
+                attribute_length,
+                num_bootstrap_methods,
+                bootstrap_methods,
+            };
+            AttributeType::BootstrapMethods(bootstrap_attr)
+        }
+        MODULE_STR => {
+            let module_name_index: u16 = reader.read_u16()?;
+            let module_flags = ModuleFlags::from_bits(reader.read_u16()?);
+            let module_version_index: u16 = reader.read_u16()?;
+
+            let requires_count: u16 = reader.read_u16()?;
+            let mut requires: Vec<ModuleRequires> = vec![];
+            for requires_index in 0..requires_count {
+                let entry = reader.scoped(format!("requires[{requires_index}]"), |reader| {
+                    let requires_index: u16 = reader.read_u16()?;
+                    let requires_flags = RequiresFlags::from_bits(reader.read_u16()?);
+                    let requires_version_index: u16 = reader.read_u16()?;
+                    Ok(ModuleRequires {
+                        requires_index,
+                        requires_flags,
+                        requires_version_index,
+                    })
+                })?;
+                requires.push(entry);
+            }
+
+            let exports_count: u16 = reader.read_u16()?;
+            let mut exports: Vec<ModuleExports> = vec![];
+            for exports_index in 0..exports_count {
+                let entry = reader.scoped(format!("exports[{exports_index}]"), |reader| {
+                    let exports_index: u16 = reader.read_u16()?;
+                    let exports_flags = ExportsFlags::from_bits(reader.read_u16()?);
+                    let exports_to_count: u16 = reader.read_u16()?;
+                    let exports_to_index = generate_index_table(exports_to_count, reader)?;
+                    Ok(ModuleExports {
+                        exports_index,
+                        exports_flags,
+                        exports_to_count,
+                        exports_to_index,
+                    })
+                })?;
+                exports.push(entry);
             }
-            _ => {
-                panic!(
-                    "No valid utf8 string entry found for attribute_name_index: {}",
-                    attribute_name_index
-                );
+
+            let opens_count: u16 = reader.read_u16()?;
+            let mut opens: Vec<ModuleOpens> = vec![];
+            for opens_index in 0..opens_count {
+                let entry = reader.scoped(format!("opens[{opens_index}]"), |reader| {
+                    let opens_index: u16 = reader.read_u16()?;
+                    let opens_flags = ExportsFlags::from_bits(reader.read_u16()?);
+                    let opens_to_count: u16 = reader.read_u16()?;
+                    let opens_to_index = generate_index_table(opens_to_count, reader)?;
+                    Ok(ModuleOpens {
+                        opens_index,
+                        opens_flags,
+                        opens_to_count,
+                        opens_to_index,
+                    })
+                })?;
+                opens.push(entry);
             }
+
+            let uses_count: u16 = reader.read_u16()?;
+            let uses_index =
+                reader.scoped("uses", |reader| generate_index_table(uses_count, reader))?;
+
+            let provides_count: u16 = reader.read_u16()?;
+            let mut provides: Vec<ModuleProvides> = vec![];
+            for provides_index in 0..provides_count {
+                let entry = reader.scoped(format!("provides[{provides_index}]"), |reader| {
+                    let provides_index: u16 = reader.read_u16()?;
+                    let provides_with_count: u16 = reader.read_u16()?;
+                    let provides_with_index = generate_index_table(provides_with_count, reader)?;
+                    Ok(ModuleProvides {
+                        provides_index,
+                        provides_with_count,
+                        provides_with_index,
+                    })
+                })?;
+                provides.push(entry);
+            }
+
+            let module_attr = AttributeModule {
+                attribute_name_index,
+                attribute_length,
+                module_name_index,
+                module_flags,
+                module_version_index,
+                requires_count,
+                requires,
+                exports_count,
+                exports,
+                opens_count,
+                opens,
+                uses_count,
+                uses_index,
+                provides_count,
+                provides,
+            };
+            AttributeType::Module(module_attr)
         }
+        MODULEPACKAGES_STR => {
+            let package_count: u16 = reader.read_u16()?;
+            let package_index = generate_index_table(package_count, reader)?;
+            let module_packages_attr = AttributeModulePackages {
+                attribute_name_index,
+                attribute_length,
+                package_count,
+                package_index,
+            };
+            AttributeType::ModulePackages(module_packages_attr)
+        }
+        MODULEMAINCLASS_STR => {
+            let main_class_index: u16 = reader.read_u16()?;
+            let module_main_class_attr = AttributeModuleMainClass {
+                attribute_name_index,
+                attribute_length,
+                main_class_index,
+            };
+            AttributeType::ModuleMainClass(module_main_class_attr)
+        }
+        NESTHOST_STR => {
+            let host_class_index: u16 = reader.read_u16()?;
+            let nest_host_attr = AttributeNestHost {
+                attribute_name_index,
+                attribute_length,
+                host_class_index,
+            };
+            AttributeType::NestHost(nest_host_attr)
+        }
+        NESTMEMBERS_STR => {
+            let number_of_classes: u16 = reader.read_u16()?;
+            let classes = generate_index_table(number_of_classes, reader)?;
+            let nest_members_attr = AttributeNestMembers {
+                attribute_name_index,
+                attribute_length,
+                number_of_classes,
+                classes,
+            };
+            AttributeType::NestMembers(nest_members_attr)
+        }
+        RECORD_STR => {
+            let components_count: u16 = reader.read_u16()?;
+            let mut components: Vec<RecordComponent> = vec![];
+            for component_index in 0..components_count {
+                let component =
+                    reader.scoped(format!("components[{component_index}]"), |reader| {
+                        let name_index: u16 = reader.read_u16()?;
+                        let descriptor_index: u16 = reader.read_u16()?;
+                        let attributes_count: u16 = reader.read_u16()?;
+                        let attributes =
+                            generate_attributes(attributes_count, constant_pool, reader)?;
+                        Ok(RecordComponent {
+                            name_index,
+                            descriptor_index,
+                            attributes_count,
+                            attributes,
+                        })
+                    })?;
+                components.push(component);
+            }
+            let record_attr = AttributeRecord {
+                attribute_name_index,
+                attribute_length,
+                components_count,
+                components,
+            };
+            AttributeType::Record(record_attr)
+        }
+        PERMITTEDSUBCLASSES_STR => {
+            let number_of_classes: u16 = reader.read_u16()?;
+            let classes = generate_index_table(number_of_classes, reader)?;
+            let permitted_subclasses_attr = AttributePermittedSubclasses {
+                attribute_name_index,
+                attribute_length,
+                number_of_classes,
+                classes,
+            };
+            AttributeType::PermittedSubclasses(permitted_subclasses_attr)
+        }
+        _ => {
+            let bytes_offset = reader.offset();
+            let bytes = reader.read_bytes(attribute_length as usize)?;
+            let name = attribute_name.to_string();
+            let parsed =
+                reader
+                    .options()
+                    .attribute_registry
+                    .parse(attribute_name, &bytes, constant_pool);
+            match parsed {
+                Some(Ok(value)) => AttributeType::Custom { name, bytes, value },
+                Some(Err(err)) => {
+                    return Err(reader.error_at(
+                        bytes_offset,
+                        ParseErrorKind::CustomAttribute {
+                            name,
+                            message: err.to_string(),
+                        },
+                    ));
+                }
+                None => AttributeType::Unknown { name, bytes },
+            }
+        }
+    };
+    Ok(attribute)
+}
+
+/// Reads a table of `count` constant pool indexes, as used by NestMembers, ModulePackages
+/// and similar attributes.
+fn generate_index_table<R: Read>(
+    count: u16,
+    reader: &mut ClassReader<R>,
+) -> Result<Vec<u16>, ClassParseError> {
+    let mut indexes: Vec<u16> = vec![];
+    for _ in 0..count {
+        indexes.push(reader.read_u16()?);
     }
-    attributes
+    Ok(indexes)
 }
 
 const CONSTANT_VALUE_STR: &str = "ConstantValue";
@@ -459,159 +719,997 @@ const RUNTIMEVISIBLEANNOTATIONS_STR: &str = "RuntimeVisibleAnnotations";
 const RUNTIMEINVISIBLEANNOTATIONS_STR: &str = "RuntimeInvisibleAnnotations";
 const RUNTIMEVISIBLEPARAMETERANNOTATIONS_STR: &str = "RuntimeVisibleParameterAnnotations";
 const RUNTIMEINVISIBLEPARAMETERANNOTATIONS_STR: &str = "RuntimeInvisibleParameterAnnotations";
-const ANNOTATIONDEFAULT_STR: &str = "AnnotationsDefault";
+const RUNTIMEVISIBLETYPEANNOTATIONS_STR: &str = "RuntimeVisibleTypeAnnotations";
+const RUNTIMEINVISIBLETYPEANNOTATIONS_STR: &str = "RuntimeInvisibleTypeAnnotations";
+const ANNOTATIONDEFAULT_STR: &str = "AnnotationDefault";
 const BOOTSTRAPMETHODS_STR: &str = "BootstrapMethods";
+const METHODPARAMETERS_STR: &str = "MethodParameters";
+const MODULE_STR: &str = "Module";
+const MODULEPACKAGES_STR: &str = "ModulePackages";
+const MODULEMAINCLASS_STR: &str = "ModuleMainClass";
+const NESTHOST_STR: &str = "NestHost";
+const NESTMEMBERS_STR: &str = "NestMembers";
+const RECORD_STR: &str = "Record";
+const PERMITTEDSUBCLASSES_STR: &str = "PermittedSubclasses";
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AttributeBootstrapMethods {
-    attribute_name_index: u16,
-    attribute_length: u32,
-    num_bootstrap_methods: u16,
-    bootstrap_methods: Vec<BootstrapMethodElement>,
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub num_bootstrap_methods: u16,
+    pub bootstrap_methods: Vec<BootstrapMethodElement>,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct BootstrapMethodElement {
-    bootstrap_method_ref: u16,
-    num_bootstrap_arguments: u16,
-    bootstrap_arguments: Vec<u16>,
+    pub bootstrap_method_ref: u16,
+    pub num_bootstrap_arguments: u16,
+    pub bootstrap_arguments: Vec<u16>,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AttributeSourceFile {
-    attribute_name_index: u16,
-    attribute_length: u32,
-    sourcefile_index: u16,
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub sourcefile_index: u16,
 }
 
+/// Extended debugging information, which the JVM ignores. The bytes are modified UTF-8
+/// text, usually a JSR-45 source map.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AttributeSourceDebugExtension {
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub debug_extension: Vec<u8>,
+}
+
+impl AttributeSignature {
+    /// The signature text, to be parsed as a [`ClassSignature`](super::signature::ClassSignature),
+    /// [`MethodSignature`](super::signature::MethodSignature) or
+    /// [`FieldSignature`](super::signature::FieldSignature) depending on where the attribute is.
+    pub fn signature<'c>(
+        &self,
+        constant_pool: &'c ConstantPool,
+    ) -> Result<&'c str, ConstantPoolError> {
+        constant_pool.utf8(self.signature_index)
+    }
+}
+
+impl AttributeSourceDebugExtension {
+    pub fn debug_extension(&self) -> &[u8] {
+        &self.debug_extension
+    }
+
+    /// The debug extension decoded as modified UTF-8, with undecodable bytes replaced.
+    pub fn text(&self) -> String {
+        mutf8::decode_lossy(&self.debug_extension)
+    }
+
+    /// Parses the debug extension as a source map, or returns `None` if it is not one.
+    pub fn smap(&self) -> Option<Result<Smap, SmapError>> {
+        let text = self.text();
+        text.starts_with("SMAP").then(|| Smap::parse(&text))
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Attribute {
-    attribute_name_index: u16,
-    attribute_length: u32,
-    info: Vec<AttributeType>,
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub info: Vec<AttributeType>,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum AttributeType {
     ConstantValue(AttributeConstantValue),
     Code(AttributeCode),
+    StackMapTable(AttributeStackMapTable),
     Exceptions(AttributeExceptions),
     InnerClasses(AttributeInnerClasses),
     EnclosingMethods(AttributeEnclosingMethod),
     Synthetic(AttributeSynthetic),
     Signature(AttributeSignature),
     SourceFile(AttributeSourceFile),
-    SourceDebugExtension,
+    SourceDebugExtension(AttributeSourceDebugExtension),
     LineNumberTable(AttributeLineNumberTable),
-    LocalVariableTable,
-    LocalVariableTypeTable,
+    LocalVariableTable(AttributeLocalVariableTable),
+    LocalVariableTypeTable(AttributeLocalVariableTypeTable),
     Deprecated(AttributeDeprecated),
     RuntimeVisibleAnnotations(AttributeRuntimeVisibleAnnotations),
-    RuntimeInvisibleAnnotations,
-    RuntimeVisibleParameterAnnotations,
-    RuntimeInvisibleParameterAnnotations,
-    AnnotationDefault,
+    RuntimeInvisibleAnnotations(AttributeRuntimeInvisibleAnnotations),
+    RuntimeVisibleParameterAnnotations(AttributeRuntimeVisibleParameterAnnotations),
+    RuntimeInvisibleParameterAnnotations(AttributeRuntimeInvisibleParameterAnnotations),
+    RuntimeVisibleTypeAnnotations(AttributeRuntimeVisibleTypeAnnotations),
+    RuntimeInvisibleTypeAnnotations(AttributeRuntimeInvisibleTypeAnnotations),
+    AnnotationDefault(AttributeAnnotationDefault),
     BootstrapMethods(AttributeBootstrapMethods),
+    MethodParameters(AttributeMethodParameters),
+    Module(AttributeModule),
+    ModulePackages(AttributeModulePackages),
+    ModuleMainClass(AttributeModuleMainClass),
+    NestHost(AttributeNestHost),
+    NestMembers(AttributeNestMembers),
+    Record(AttributeRecord),
+    PermittedSubclasses(AttributePermittedSubclasses),
+    /// An attribute with a name this crate does not recognise, kept byte for byte. Also
+    /// used for a predefined attribute in a class file older than the attribute itself.
+    Unknown {
+        name: String,
+        bytes: Vec<u8>,
+    },
+    /// An attribute that could not be parsed, kept byte for byte. Only produced by a
+    /// lenient parse; `name` is missing when `attribute_name_index` does not refer to a
+    /// `CONSTANT_Utf8` entry.
+    Invalid {
+        name: Option<String>,
+        bytes: Vec<u8>,
+    },
+    /// An attribute decoded by a parser from the [`AttributeRegistry`](crate::classfile::options::AttributeRegistry).
+    Custom {
+        name: String,
+        bytes: Vec<u8>,
+        #[cfg_attr(feature = "serde", serde(skip))]
+        value: Arc<dyn CustomAttribute>,
+    },
+}
+
+impl AttributeType {
+    /// The attribute's name as it appears in the constant pool, if known.
+    pub fn name(&self) -> Option<&str> {
+        let name = match self {
+            AttributeType::ConstantValue(_) => CONSTANT_VALUE_STR,
+            AttributeType::Code(_) => CODE_STR,
+            AttributeType::StackMapTable(_) => STACKMAPTABLE_STR,
+            AttributeType::Exceptions(_) => EXCEPTIONS_STR,
+            AttributeType::InnerClasses(_) => INNERCLASSES_STR,
+            AttributeType::EnclosingMethods(_) => ENCLOSINGMETHODS_STR,
+            AttributeType::Synthetic(_) => SYNTHETIC_STR,
+            AttributeType::Signature(_) => SIGNATURE_STR,
+            AttributeType::SourceFile(_) => SOURCEFILE_STR,
+            AttributeType::SourceDebugExtension(_) => SOURCEDEBUGEXTENSION_STR,
+            AttributeType::LineNumberTable(_) => LINENUMBERTABLE_STR,
+            AttributeType::LocalVariableTable(_) => LOCALVARIABLETABLE_STR,
+            AttributeType::LocalVariableTypeTable(_) => LOCALVARIABLETYPETABLE_STR,
+            AttributeType::Deprecated(_) => DEPRECATED_STR,
+            AttributeType::RuntimeVisibleAnnotations(_) => RUNTIMEVISIBLEANNOTATIONS_STR,
+            AttributeType::RuntimeInvisibleAnnotations(_) => RUNTIMEINVISIBLEANNOTATIONS_STR,
+            AttributeType::RuntimeVisibleParameterAnnotations(_) => {
+                RUNTIMEVISIBLEPARAMETERANNOTATIONS_STR
+            }
+            AttributeType::RuntimeInvisibleParameterAnnotations(_) => {
+                RUNTIMEINVISIBLEPARAMETERANNOTATIONS_STR
+            }
+            AttributeType::RuntimeVisibleTypeAnnotations(_) => RUNTIMEVISIBLETYPEANNOTATIONS_STR,
+            AttributeType::RuntimeInvisibleTypeAnnotations(_) => {
+                RUNTIMEINVISIBLETYPEANNOTATIONS_STR
+            }
+            AttributeType::AnnotationDefault(_) => ANNOTATIONDEFAULT_STR,
+            AttributeType::BootstrapMethods(_) => BOOTSTRAPMETHODS_STR,
+            AttributeType::MethodParameters(_) => METHODPARAMETERS_STR,
+            AttributeType::Module(_) => MODULE_STR,
+            AttributeType::ModulePackages(_) => MODULEPACKAGES_STR,
+            AttributeType::ModuleMainClass(_) => MODULEMAINCLASS_STR,
+            AttributeType::NestHost(_) => NESTHOST_STR,
+            AttributeType::NestMembers(_) => NESTMEMBERS_STR,
+            AttributeType::Record(_) => RECORD_STR,
+            AttributeType::PermittedSubclasses(_) => PERMITTEDSUBCLASSES_STR,
+            AttributeType::Unknown { name, .. } | AttributeType::Custom { name, .. } => name,
+            AttributeType::Invalid { name, .. } => return name.as_deref(),
+        };
+        Some(name)
+    }
+
+    /// Returns the decoded value of a custom attribute if it has type `T`.
+    pub fn downcast_custom<T: 'static>(&self) -> Option<&T> {
+        match self {
+            AttributeType::Custom { value, .. } => value.as_ref().as_any().downcast_ref::<T>(),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AttributeLineNumberTable {
-    attribute_name_index: u16,
-    attribute_length: u32,
-    line_number_table_length: u16,
-    line_number_table: Vec<LineNumberTable_Element>,
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub line_number_table_length: u16,
+    pub line_number_table: Vec<LineNumberTableElement>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LineNumberTableElement {
+    pub start_pc: u16,
+    pub line_number: u16,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AttributeLocalVariableTable {
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub local_variable_table_length: u16,
+    pub local_variable_table: Vec<LocalVariableTableElement>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LocalVariableTableElement {
+    pub start_pc: u16,
+    pub length: u16,
+    pub name_index: u16,
+    pub descriptor_index: u16,
+    pub index: u16,
 }
 
 #[derive(Debug, Clone)]
-pub struct LineNumberTable_Element {
-    start_pc: u16,
-    line_number: u16,
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AttributeLocalVariableTypeTable {
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub local_variable_type_table_length: u16,
+    pub local_variable_type_table: Vec<LocalVariableTypeTableElement>,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LocalVariableTypeTableElement {
+    pub start_pc: u16,
+    pub length: u16,
+    pub name_index: u16,
+    pub signature_index: u16,
+    pub index: u16,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AttributeMethodParameters {
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub parameters_count: u8,
+    pub parameters: Vec<MethodParameter>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MethodParameter {
+    pub name_index: u16,
+    pub access_flags: ParameterFlags,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AttributeDeprecated {
-    attribute_name_index: u16,
-    attribute_length: u32,
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AttributeSignature {
-    attribute_name_index: u16,
-    attribute_length: u32,
-    signature_index: u16,
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub signature_index: u16,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AttributeConstantValue {
-    attribute_name_index: u16,
-    attribute_length: u32,
-    constantvalue_index: u16,
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub constantvalue_index: u16,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AttributeCode {
-    attribute_name_index: u16,
-    attribute_length: u32,
-    max_stack: u16,
-    max_locals: u16,
-    code_length: u32,
-    code: Vec<u8>,
-    exception_table_length: u16,
-    exception_table: Vec<ExceptionTableEntry>,
-    attributes_count: u16,
-    attribute_info: Vec<AttributeType>,
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub max_stack: u16,
+    pub max_locals: u16,
+    pub code_length: u32,
+    pub code: Vec<u8>,
+    pub exception_table_length: u16,
+    pub exception_table: Vec<ExceptionTableEntry>,
+    pub attributes_count: u16,
+    pub attribute_info: Vec<AttributeType>,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AttributeStackMapTable {
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub number_of_entries: u16,
+    pub entries: Vec<StackMapFrame>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ExceptionTableEntry {
-    start_pc: u16,
-    end_pc: u16,
-    handler_pc: u16,
-    catch_type: u16,
+    pub start_pc: u16,
+    pub end_pc: u16,
+    pub handler_pc: u16,
+    pub catch_type: u16,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AttributeExceptions {
-    attribute_name_index: u16,
-    attribute_length: u32,
-    number_of_exceptions: u16,
-    exception_index_table: Vec<u16>,
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub number_of_exceptions: u16,
+    pub exception_index_table: Vec<u16>,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AttributeInnerClasses {
-    attribute_name_index: u16,
-    attribute_length: u32,
-    number_of_classes: u16,
-    classes: Vec<InnerClass>,
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub number_of_classes: u16,
+    pub classes: Vec<InnerClass>,
 }
 
 #[derive(Debug, Clone)]
-struct InnerClass {
-    inner_class_info_index: u16,
-    outer_class_info_index: u16,
-    inner_name_index: u16,
-    inner_class_access_flags: u16,
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct InnerClass {
+    pub inner_class_info_index: u16,
+    pub outer_class_info_index: u16,
+    pub inner_name_index: u16,
+    pub inner_class_access_flags: InnerClassAccessFlags,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AttributeEnclosingMethod {
-    attribute_name_index: u16,
-    attribute_length: u32,
-    class_index: u16,
-    method_index: u16,
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub class_index: u16,
+    pub method_index: u16,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AttributeSynthetic {
-    attribute_name_index: u16,
-    attribute_length: u32,
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AttributeRuntimeVisibleAnnotations {
-    attribute_name_index: u16,
-    attribute_length: u32,
-    num_annotations: u16,
-    annotations: Vec<Annotation>,
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub num_annotations: u16,
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AttributeModule {
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub module_name_index: u16,
+    pub module_flags: ModuleFlags,
+    pub module_version_index: u16,
+    pub requires_count: u16,
+    pub requires: Vec<ModuleRequires>,
+    pub exports_count: u16,
+    pub exports: Vec<ModuleExports>,
+    pub opens_count: u16,
+    pub opens: Vec<ModuleOpens>,
+    pub uses_count: u16,
+    pub uses_index: Vec<u16>,
+    pub provides_count: u16,
+    pub provides: Vec<ModuleProvides>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ModuleRequires {
+    pub requires_index: u16,
+    pub requires_flags: RequiresFlags,
+    pub requires_version_index: u16,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ModuleExports {
+    pub exports_index: u16,
+    pub exports_flags: ExportsFlags,
+    pub exports_to_count: u16,
+    pub exports_to_index: Vec<u16>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ModuleOpens {
+    pub opens_index: u16,
+    pub opens_flags: ExportsFlags,
+    pub opens_to_count: u16,
+    pub opens_to_index: Vec<u16>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ModuleProvides {
+    pub provides_index: u16,
+    pub provides_with_count: u16,
+    pub provides_with_index: Vec<u16>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AttributeModulePackages {
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub package_count: u16,
+    pub package_index: Vec<u16>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AttributeModuleMainClass {
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub main_class_index: u16,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AttributeNestHost {
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub host_class_index: u16,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AttributeNestMembers {
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub number_of_classes: u16,
+    pub classes: Vec<u16>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AttributeRecord {
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub components_count: u16,
+    pub components: Vec<RecordComponent>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RecordComponent {
+    pub name_index: u16,
+    pub descriptor_index: u16,
+    pub attributes_count: u16,
+    pub attributes: Vec<AttributeType>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AttributePermittedSubclasses {
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub number_of_classes: u16,
+    pub classes: Vec<u16>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AttributeRuntimeInvisibleAnnotations {
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub num_annotations: u16,
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AttributeRuntimeVisibleParameterAnnotations {
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub num_parameters: u8,
+    pub parameter_annotations: Vec<ParameterAnnotations>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AttributeRuntimeInvisibleParameterAnnotations {
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub num_parameters: u8,
+    pub parameter_annotations: Vec<ParameterAnnotations>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AttributeRuntimeVisibleTypeAnnotations {
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub num_annotations: u16,
+    pub annotations: Vec<TypeAnnotation>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AttributeRuntimeInvisibleTypeAnnotations {
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub num_annotations: u16,
+    pub annotations: Vec<TypeAnnotation>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AttributeAnnotationDefault {
+    pub attribute_name_index: u16,
+    pub attribute_length: u32,
+    pub default_value: ElementValue,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classfile::{
+        options::{AttributeRegistry, ParseOptions},
+        testing::{u16_bytes, ClassBuilder},
+        ClassFile,
+    };
+
+    const MODULE_INFO: &[u8] = include_bytes!("../../testdata/module/module-info.class");
+    const SHAPES: &[u8] = include_bytes!("../../testdata/Shapes.class");
+    const CIRCLE: &[u8] = include_bytes!("../../testdata/Shapes$Circle.class");
+    const POLYGON: &[u8] = include_bytes!("../../testdata/Shapes$Polygon.class");
+    const MEMBERS: &[u8] = include_bytes!("../../testdata/Members.class");
+    const ANONYMOUS: &[u8] = include_bytes!("../../testdata/Members$1.class");
+    const COUNTER: &[u8] = include_bytes!("../../testdata/Members$1Counter.class");
+
+    /// The name behind a Utf8, Class, Module or Package entry.
+    fn name(class_file: &ClassFile, index: u16) -> &str {
+        let constant_pool = &class_file.constant_pool;
+        let name_index = match constant_pool.entry(index).unwrap() {
+            Constant::Class(class) => class.name_index,
+            Constant::Module(module) => module.name_index,
+            Constant::Package(package) => package.name_index,
+            _ => index,
+        };
+        constant_pool.utf8(name_index).unwrap()
+    }
+
+    fn names<'c>(class_file: &'c ClassFile, indexes: &[u16]) -> Vec<&'c str> {
+        indexes
+            .iter()
+            .map(|&index| name(class_file, index))
+            .collect()
+    }
+
+    #[test]
+    fn parses_a_module_declaration() {
+        let class_file = ClassFile::from_bytes(MODULE_INFO).unwrap();
+        let module = class_file
+            .attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                AttributeType::Module(module) => Some(module),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(name(&class_file, module.module_name_index), "com.example");
+        assert_eq!(module.module_flags.bits(), 0);
+        assert_eq!(module.module_version_index, 0);
+
+        let requires: Vec<(&str, u16, &str)> = module
+            .requires
+            .iter()
+            .map(|requires| {
+                (
+                    name(&class_file, requires.requires_index),
+                    requires.requires_flags.bits(),
+                    name(&class_file, requires.requires_version_index),
+                )
+            })
+            .collect();
+        assert_eq!(
+            requires,
+            [
+                ("java.base", 0x8000, "17.0.15"),
+                ("java.logging", 0x0020, "17.0.15")
+            ]
+        );
+
+        assert_eq!(module.exports_count, 1);
+        assert_eq!(
+            name(&class_file, module.exports[0].exports_index),
+            "com/example/api"
+        );
+        assert_eq!(module.exports[0].exports_to_count, 0);
+        assert_eq!(
+            name(&class_file, module.opens[0].opens_index),
+            "com/example/internal"
+        );
+        assert_eq!(
+            names(&class_file, &module.opens[0].opens_to_index),
+            ["java.logging"]
+        );
+        assert_eq!(
+            names(&class_file, &module.uses_index),
+            ["com/example/api/Service"]
+        );
+        let provides = &module.provides[0];
+        assert_eq!(
+            name(&class_file, provides.provides_index),
+            "com/example/api/Service"
+        );
+        assert_eq!(
+            names(&class_file, &provides.provides_with_index),
+            ["com/example/internal/ServiceImpl"]
+        );
+    }
+
+    #[test]
+    fn parses_the_attributes_jar_adds_to_a_module() {
+        let class_file = ClassFile::from_bytes(MODULE_INFO).unwrap();
+        let mut packages = None;
+        let mut main_class = None;
+        for attribute in &class_file.attributes {
+            match attribute {
+                AttributeType::ModulePackages(attribute) => {
+                    packages = Some(names(&class_file, &attribute.package_index));
+                }
+                AttributeType::ModuleMainClass(attribute) => {
+                    main_class = Some(name(&class_file, attribute.main_class_index));
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(
+            packages.unwrap(),
+            ["com/example/api", "com/example/internal"]
+        );
+        assert_eq!(main_class, Some("com/example/api/Service"));
+    }
+
+    #[test]
+    fn parses_the_nest_and_permitted_subclasses() {
+        let shapes = ClassFile::from_bytes(SHAPES).unwrap();
+        let mut members = vec![];
+        let mut permitted = vec![];
+        for attribute in &shapes.attributes {
+            match attribute {
+                AttributeType::NestMembers(attribute) => {
+                    members = names(&shapes, &attribute.classes);
+                }
+                AttributeType::PermittedSubclasses(attribute) => {
+                    permitted = names(&shapes, &attribute.classes);
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(members, ["Shapes$Polygon", "Shapes$Circle", "Shapes$Unit"]);
+        assert_eq!(permitted, ["Shapes$Circle", "Shapes$Polygon"]);
+
+        let circle = ClassFile::from_bytes(CIRCLE).unwrap();
+        let host = circle
+            .attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                AttributeType::NestHost(attribute) => {
+                    Some(name(&circle, attribute.host_class_index))
+                }
+                _ => None,
+            });
+        assert_eq!(host, Some("Shapes"));
+    }
+
+    fn record(class_file: &ClassFile) -> &AttributeRecord {
+        class_file
+            .attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                AttributeType::Record(record) => Some(record),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn parses_record_components_with_their_own_attributes() {
+        let circle = ClassFile::from_bytes(CIRCLE).unwrap();
+        let [radius] = record(&circle).components.as_slice() else {
+            panic!("expected one component");
+        };
+        assert_eq!(name(&circle, radius.name_index), "radius");
+        assert_eq!(name(&circle, radius.descriptor_index), "D");
+        let [AttributeType::RuntimeVisibleAnnotations(annotations)] = radius.attributes.as_slice()
+        else {
+            panic!("expected the @Unit annotation: {:?}", radius.attributes);
+        };
+        assert_eq!(
+            name(&circle, annotations.annotations[0].type_index),
+            "LShapes$Unit;"
+        );
+
+        let polygon = ClassFile::from_bytes(POLYGON).unwrap();
+        let components = &record(&polygon).components;
+        assert_eq!(record(&polygon).components_count, 2);
+        let [AttributeType::Signature(signature)] = components[0].attributes.as_slice() else {
+            panic!("expected a Signature: {:?}", components[0].attributes);
+        };
+        assert_eq!(
+            name(&polygon, signature.signature_index),
+            "Ljava/util/List<TP;>;"
+        );
+        assert_eq!(name(&polygon, components[1].name_index), "sides");
+        assert!(components[1].attributes.is_empty());
+    }
+
+    fn method<'c>(class_file: &'c ClassFile, method_name: &str) -> &'c [AttributeType] {
+        let method = class_file
+            .methods
+            .iter()
+            .find(|method| name(class_file, method.name_index) == method_name)
+            .unwrap();
+        &method.attributes
+    }
+
+    fn code(attributes: &[AttributeType]) -> &AttributeCode {
+        attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                AttributeType::Code(code) => Some(code),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn parses_the_exceptions_a_method_declares() {
+        let class_file = ClassFile::from_bytes(MEMBERS).unwrap();
+        let exceptions = method(&class_file, "names")
+            .iter()
+            .find_map(|attribute| match attribute {
+                AttributeType::Exceptions(exceptions) => Some(exceptions),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(exceptions.number_of_exceptions, 2);
+        assert_eq!(
+            names(&class_file, &exceptions.exception_index_table),
+            ["java/io/IOException", "java/lang/InterruptedException"]
+        );
+    }
+
+    fn enclosing_method(class_file: &ClassFile) -> &AttributeEnclosingMethod {
+        class_file
+            .attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                AttributeType::EnclosingMethods(enclosing) => Some(enclosing),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn a_local_class_is_enclosed_by_its_method() {
+        let counter = ClassFile::from_bytes(COUNTER).unwrap();
+        let enclosing = enclosing_method(&counter);
+        assert_eq!(name(&counter, enclosing.class_index), "Members");
+        assert_eq!(
+            counter.constant_pool.name_and_type(enclosing.method_index),
+            Ok(("names", "(ILjava/lang/String;)Ljava/util/List;"))
+        );
+    }
+
+    #[test]
+    fn a_class_in_a_field_initialiser_has_no_enclosing_method() {
+        let anonymous = ClassFile::from_bytes(ANONYMOUS).unwrap();
+        let enclosing = enclosing_method(&anonymous);
+        assert_eq!(name(&anonymous, enclosing.class_index), "Members");
+        assert_eq!(enclosing.method_index, 0);
+    }
+
+    #[test]
+    fn parses_local_variable_tables() {
+        let class_file = ClassFile::from_bytes(MEMBERS).unwrap();
+        let code = code(method(&class_file, "names"));
+        let mut variables = vec![];
+        let mut generic_variables = vec![];
+        for attribute in &code.attribute_info {
+            match attribute {
+                AttributeType::LocalVariableTable(table) => {
+                    assert_eq!(table.local_variable_table_length, 5);
+                    for entry in &table.local_variable_table {
+                        variables.push((
+                            entry.start_pc,
+                            entry.length,
+                            entry.index,
+                            name(&class_file, entry.name_index),
+                            name(&class_file, entry.descriptor_index),
+                        ));
+                    }
+                }
+                AttributeType::LocalVariableTypeTable(table) => {
+                    for entry in &table.local_variable_type_table {
+                        generic_variables.push((
+                            entry.start_pc,
+                            entry.length,
+                            entry.index,
+                            name(&class_file, entry.name_index),
+                            name(&class_file, entry.signature_index),
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(
+            variables,
+            [
+                (11, 27, 4, "i", "I"),
+                (0, 57, 0, "this", "LMembers;"),
+                (0, 57, 1, "count", "I"),
+                (0, 57, 2, "prefix", "Ljava/lang/String;"),
+                (8, 49, 3, "names", "Ljava/util/List;"),
+            ]
+        );
+        assert_eq!(
+            generic_variables,
+            [
+                (0, 57, 0, "this", "LMembers<TK;>;"),
+                (8, 49, 3, "names", "Ljava/util/List<Ljava/lang/String;>;"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_method_parameters() {
+        let class_file = ClassFile::from_bytes(MEMBERS).unwrap();
+        let parameters = method(&class_file, "names")
+            .iter()
+            .find_map(|attribute| match attribute {
+                AttributeType::MethodParameters(parameters) => Some(parameters),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(parameters.parameters_count, 2);
+        let parameters: Vec<(&str, u16)> = parameters
+            .parameters
+            .iter()
+            .map(|parameter| {
+                (
+                    name(&class_file, parameter.name_index),
+                    parameter.access_flags.bits(),
+                )
+            })
+            .collect();
+        assert_eq!(parameters, [("count", 0x0010), ("prefix", 0)]);
+    }
+
+    #[test]
+    fn a_parameter_without_a_name_has_name_index_zero() {
+        let mut builder = ClassBuilder::new("Outer$Inner");
+        // javac marks the outer instance of an inner class constructor as mandated.
+        let parameters = [&[1][..], &u16_bytes(&[0, 0x8000])].concat();
+        let parameters = builder.attribute("MethodParameters", &parameters);
+        builder.method(0x0001, "<init>", "(LOuter;)V", &[parameters]);
+        let class_file = ClassFile::from_bytes(&builder.build()).unwrap();
+        let [AttributeType::MethodParameters(parameters)] =
+            class_file.methods[0].attributes.as_slice()
+        else {
+            panic!("expected MethodParameters");
+        };
+        assert_eq!(parameters.parameters[0].name_index, 0);
+        assert!(parameters.parameters[0].access_flags.is_mandated());
+    }
+
+    /// What a registered decoder turns a `CompilationID` attribute into.
+    #[derive(Debug, PartialEq)]
+    struct CompilationId(String);
+
+    fn compilation_id_registry() -> AttributeRegistry {
+        let mut registry = AttributeRegistry::new();
+        registry.register("CompilationID", |bytes, constant_pool| {
+            let index = match bytes {
+                [high, low] => u16::from_be_bytes([*high, *low]),
+                _ => return Err(format!("expected 2 bytes, got {}", bytes.len()).into()),
+            };
+            Ok(CompilationId(constant_pool.utf8(index)?.to_string()))
+        });
+        registry
+    }
+
+    fn with_class_attribute(name: &str, body: &[u8]) -> Vec<u8> {
+        let mut builder = ClassBuilder::new("Sample");
+        let attribute = builder.attribute(name, body);
+        builder.class_attribute(attribute);
+        builder.build()
+    }
+
+    #[test]
+    fn keeps_an_unknown_attribute_byte_for_byte() {
+        let bytes = with_class_attribute("ScalaSig", &[0x05, 0x00, 0x00]);
+        let class_file = ClassFile::from_bytes(&bytes).unwrap();
+        let [AttributeType::Unknown { name, bytes }] = class_file.attributes.as_slice() else {
+            panic!("expected an unknown attribute: {:?}", class_file.attributes);
+        };
+        assert_eq!(name, "ScalaSig");
+        assert_eq!(bytes, &[0x05, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn a_registered_parser_decodes_its_attribute() {
+        let mut builder = ClassBuilder::new("Sample");
+        let id = builder.utf8("build-42");
+        let attribute = builder.attribute("CompilationID", &u16_bytes(&[id]));
+        builder.class_attribute(attribute);
+        let options = ParseOptions {
+            attribute_registry: compilation_id_registry(),
+            ..ParseOptions::default()
+        };
+        let class_file = ClassFile::from_bytes_with(&builder.build(), &options).unwrap();
+
+        let attribute = &class_file.attributes[0];
+        assert!(matches!(attribute, AttributeType::Custom { name, .. } if name == "CompilationID"));
+        assert_eq!(attribute.name(), Some("CompilationID"));
+        assert_eq!(
+            attribute.downcast_custom::<CompilationId>(),
+            Some(&CompilationId("build-42".to_string()))
+        );
+        assert_eq!(attribute.downcast_custom::<String>(), None);
+    }
+
+    #[test]
+    fn a_failing_custom_parser_is_a_parse_error() {
+        let bytes = with_class_attribute("CompilationID", &[0x00]);
+        let options = ParseOptions {
+            attribute_registry: compilation_id_registry(),
+            ..ParseOptions::default()
+        };
+        let err = ClassFile::from_bytes_with(&bytes, &options).unwrap_err();
+        let ParseErrorKind::CustomAttribute { name, message } = &err.kind else {
+            panic!("expected a custom attribute error: {err}");
+        };
+        assert_eq!(name, "CompilationID");
+        assert_eq!(message, "expected 2 bytes, got 1");
+        assert_eq!(err.path, "attributes[0].CompilationID");
+        assert_eq!(err.offset, bytes.len() - 1);
+    }
+
+    #[test]
+    fn a_lenient_parse_keeps_an_attribute_its_parser_rejects() {
+        let bytes = with_class_attribute("CompilationID", &[0x00]);
+        let options = ParseOptions {
+            attribute_registry: compilation_id_registry(),
+            ..ParseOptions::default()
+        };
+        let (class_file, diagnostics) =
+            ClassFile::from_reader_lenient(bytes.as_slice(), &options).unwrap();
+        let [AttributeType::Invalid { name, bytes }] = class_file.attributes.as_slice() else {
+            panic!("expected an invalid attribute: {:?}", class_file.attributes);
+        };
+        assert_eq!(name.as_deref(), Some("CompilationID"));
+        assert_eq!(bytes, &[0x00]);
+        let [diagnostic] = diagnostics.as_slice() else {
+            panic!("expected one diagnostic: {diagnostics:?}");
+        };
+        assert!(matches!(
+            diagnostic.kind,
+            ParseErrorKind::CustomAttribute { .. }
+        ));
+        assert_eq!(diagnostic.path, "attributes[0].CompilationID");
+    }
 }
@@ -0,0 +1,670 @@
+//! A class file view that borrows from its input instead of copying it.
+//!
+//! [`ClassFileRef::parse`] only walks the structure of the class: constants are kept as
+//! slices and decoded to text on first use, and attributes (method bodies included) are
+//! kept as undecoded slices until asked for. [`ClassFileRef::to_class_file`] produces the
+//! owned [`ClassFile`] when the full model is needed.
+
+use std::{
+    borrow::Cow,
+    cell::{OnceCell, RefCell},
+    sync::Arc,
+};
+
+use crate::classfile::{
+    access_flags::{ClassAccessFlags, FieldAccessFlags, MethodAccessFlags},
+    attributes::{
+        generate_attribute_at, generate_exception_table, AttributeType, ExceptionTableEntry,
+    },
+    constant_pool::{
+        generate_constant, Constant, ConstantPool, ConstantUtf8Info, CONSTANT_POOL_UTF8,
+    },
+    error::{ClassParseError, ParseErrorKind},
+    fields::FieldInfo,
+    interfaces::generate_interfaces,
+    methods::Method,
+    mutf8::{self, Mutf8Error},
+    options::ParseOptions,
+    reader::ClassReader,
+    version::ClassVersion,
+    ClassFile, CLASS_FILE_MAGIC,
+};
+
+const CODE_STR: &str = "Code";
+
+#[derive(Debug)]
+pub struct ClassFileRef<'a> {
+    bytes: &'a [u8],
+    options: Arc<ParseOptions>,
+    pub minor_version: u16,
+    pub major_version: u16,
+    pub constant_pool: Vec<Option<ConstantEntry<'a>>>,
+    pub access_flags: ClassAccessFlags,
+    pub this_class: u16,
+    pub super_class: u16,
+    pub interfaces: Vec<u16>,
+    pub fields: Vec<FieldRef<'a>>,
+    pub methods: Vec<MethodRef<'a>>,
+    pub attributes: Vec<AttributeRef<'a>>,
+    /// The constant pool in owned form, built the first time an attribute is decoded.
+    owned_constant_pool: OnceCell<ConstantPool>,
+    /// Problems skipped by a lenient parse, gathered as parts of the class are decoded.
+    diagnostics: RefCell<Vec<ClassParseError>>,
+}
+
+/// A constant pool entry. Only `CONSTANT_Utf8` entries borrow, the others are a few
+/// indexes or a number and are stored as they are in [`ClassFile`].
+#[derive(Debug)]
+pub enum ConstantEntry<'a> {
+    Utf8(Utf8Ref<'a>),
+    Other(Constant),
+}
+
+#[derive(Debug)]
+pub struct Utf8Ref<'a> {
+    bytes: &'a [u8],
+    /// Offset of `bytes` in the class file.
+    offset: usize,
+    text: OnceCell<Result<Cow<'a, str>, Mutf8Error>>,
+}
+
+/// A field or method, with `F` the flag type of its `access_flags`. Methods also give
+/// access to their decoded body through [`MethodRef::code`].
+#[derive(Debug)]
+pub struct MemberRef<'a, F> {
+    pub access_flags: F,
+    pub name_index: u16,
+    pub descriptor_index: u16,
+    pub attributes: Vec<AttributeRef<'a>>,
+    code: OnceCell<CodeRef<'a>>,
+}
+
+pub type FieldRef<'a> = MemberRef<'a, FieldAccessFlags>;
+pub type MethodRef<'a> = MemberRef<'a, MethodAccessFlags>;
+
+/// A method's Code attribute with the bytecode borrowed from the input.
+#[derive(Debug)]
+pub struct CodeRef<'a> {
+    pub max_stack: u16,
+    pub max_locals: u16,
+    pub code: &'a [u8],
+    pub exception_table: Vec<ExceptionTableEntry>,
+    pub attributes: Vec<AttributeRef<'a>>,
+}
+
+/// An attribute whose contents have not been decoded yet.
+#[derive(Debug)]
+pub struct AttributeRef<'a> {
+    pub name_index: u16,
+    /// The attribute's contents, following `attribute_length`.
+    pub info: &'a [u8],
+    /// Offset of `attribute_name_index` in the class file.
+    offset: usize,
+    index: u16,
+    owner: Owner,
+    decoded: OnceCell<AttributeType>,
+}
+
+/// The structure holding an attributes table, used to rebuild the path reported in
+/// errors when an attribute is decoded later.
+#[derive(Debug, Clone, Copy)]
+enum Owner {
+    Class,
+    Field(u16),
+    Method(u16),
+    Code { method: u16, attribute: u16 },
+}
+
+impl Owner {
+    fn path(self) -> Vec<String> {
+        match self {
+            Owner::Class => vec![],
+            Owner::Field(index) => vec![format!("fields[{index}]")],
+            Owner::Method(index) => vec![format!("methods[{index}]")],
+            Owner::Code { method, attribute } => vec![
+                format!("methods[{method}]"),
+                format!("attributes[{attribute}]"),
+                CODE_STR.to_string(),
+            ],
+        }
+    }
+}
+
+impl<'a> ClassFileRef<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<ClassFileRef<'a>, ClassParseError> {
+        ClassFileRef::parse_with(bytes, &ParseOptions::default())
+    }
+
+    /// Parses the structure of the class file in `bytes`. The options apply when
+    /// attributes are decoded; problems skipped by a lenient parse are collected as each
+    /// part is decoded and returned by [`ClassFileRef::take_diagnostics`].
+    pub fn parse_with(
+        bytes: &'a [u8],
+        options: &ParseOptions,
+    ) -> Result<ClassFileRef<'a>, ClassParseError> {
+        let options = Arc::new(options.clone());
+        let reader = &mut ClassReader::resume(bytes, Arc::clone(&options), 0, vec![]);
+        let magic = reader.scoped("magic", |reader| reader.read_u32())?;
+        if magic != CLASS_FILE_MAGIC {
+            return Err(reader.error_at(0, ParseErrorKind::InvalidMagic(magic)));
+        }
+        let minor_version = reader.scoped("minor_version", |reader| reader.read_u16())?;
+        let major_version = reader.scoped("major_version", |reader| reader.read_u16())?;
+        reader.set_version(ClassVersion::new(major_version, minor_version));
+        let constant_pool_count =
+            reader.scoped("constant_pool_count", |reader| reader.read_u16())?;
+        let constant_pool = scan_constant_pool(reader, constant_pool_count)?;
+
+        let access_flags =
+            ClassAccessFlags::from_bits(reader.scoped("access_flags", |reader| reader.read_u16())?);
+        let this_class: u16 = reader.scoped("this_class", |reader| reader.read_u16())?;
+        let super_class: u16 = reader.scoped("super_class", |reader| reader.read_u16())?;
+
+        let interfaces_count: u16 =
+            reader.scoped("interfaces_count", |reader| reader.read_u16())?;
+        let interfaces = generate_interfaces(interfaces_count, reader)?;
+
+        let fields_count: u16 = reader.scoped("fields_count", |reader| reader.read_u16())?;
+        let fields = scan_members("fields", fields_count, Owner::Field, reader)?;
+
+        let methods_count: u16 = reader.scoped("methods_count", |reader| reader.read_u16())?;
+        let methods = scan_members("methods", methods_count, Owner::Method, reader)?;
+
+        let attributes_count: u16 =
+            reader.scoped("attributes_count", |reader| reader.read_u16())?;
+        let attributes = scan_attributes(attributes_count, Owner::Class, reader)?;
+
+        Ok(ClassFileRef {
+            bytes,
+            options,
+            minor_version,
+            major_version,
+            constant_pool,
+            access_flags,
+            this_class,
+            super_class,
+            interfaces,
+            fields,
+            methods,
+            attributes,
+            owned_constant_pool: OnceCell::new(),
+            diagnostics: RefCell::new(reader.take_diagnostics()),
+        })
+    }
+
+    /// Returns the problems a lenient parse has skipped over so far. Attributes are
+    /// decoded lazily, so call this after [`ClassFileRef::to_class_file`] to get the
+    /// same diagnostics as [`ClassFile::from_bytes_lenient`].
+    pub fn take_diagnostics(&self) -> Vec<ClassParseError> {
+        self.diagnostics.take()
+    }
+
+    pub fn version(&self) -> ClassVersion {
+        ClassVersion::new(self.major_version, self.minor_version)
+    }
+
+    /// The input this class was parsed from.
+    pub fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    pub fn constant(&self, index: u16) -> Option<&ConstantEntry<'a>> {
+        self.constant_pool.get(index as usize)?.as_ref()
+    }
+
+    /// The text of the `CONSTANT_Utf8` entry at `index`, if there is one and it decodes.
+    pub fn utf8(&self, index: u16) -> Option<&str> {
+        match self.constant(index)? {
+            ConstantEntry::Utf8(utf8) => utf8.as_str().ok(),
+            ConstantEntry::Other(_) => None,
+        }
+    }
+
+    /// The internal name of the `CONSTANT_Class` entry at `index`.
+    pub fn class_name(&self, index: u16) -> Option<&str> {
+        match self.constant(index)? {
+            ConstantEntry::Other(Constant::Class(class)) => self.utf8(class.name_index),
+            _ => None,
+        }
+    }
+
+    pub fn this_class_name(&self) -> Option<&str> {
+        self.class_name(self.this_class)
+    }
+
+    /// The constant pool as [`ClassFile`] holds it, decoding every `CONSTANT_Utf8`.
+    pub fn owned_constant_pool(&self) -> Result<&ConstantPool, ClassParseError> {
+        if let Some(constant_pool) = self.owned_constant_pool.get() {
+            return Ok(constant_pool);
+        }
+        let mut constant_pool: Vec<Option<Constant>> = Vec::with_capacity(self.constant_pool.len());
+        for (index, entry) in self.constant_pool.iter().enumerate() {
+            let constant = match entry {
+                None => None,
+                Some(ConstantEntry::Other(constant)) => Some(constant.clone()),
+                Some(ConstantEntry::Utf8(utf8)) => {
+                    let utf_str = match utf8.as_str() {
+                        Ok(utf_str) => utf_str.to_string(),
+                        Err(err) => {
+                            let err = ClassParseError {
+                                kind: ParseErrorKind::InvalidUtf8,
+                                offset: utf8.offset + err.position,
+                                path: format!("constant_pool[{index}]"),
+                            };
+                            if !self.options.lenient {
+                                return Err(err);
+                            }
+                            self.diagnostics.borrow_mut().push(err);
+                            mutf8::decode_lossy(utf8.bytes)
+                        }
+                    };
+                    Some(Constant::Utf8Info(ConstantUtf8Info {
+                        tag: CONSTANT_POOL_UTF8,
+                        bytes: utf8.bytes.to_vec(),
+                        utf_str,
+                    }))
+                }
+            };
+            constant_pool.push(constant);
+        }
+        Ok(self
+            .owned_constant_pool
+            .get_or_init(|| ConstantPool::new(constant_pool)))
+    }
+
+    /// Decodes everything into the owned model, reusing attributes decoded so far.
+    pub fn to_class_file(&self) -> Result<ClassFile, ClassParseError> {
+        let decode_all = |attributes: &[AttributeRef<'a>]| {
+            attributes
+                .iter()
+                .map(|attribute| attribute.decode(self).cloned())
+                .collect::<Result<Vec<AttributeType>, ClassParseError>>()
+        };
+        let fields = self
+            .fields
+            .iter()
+            .map(|field| {
+                Ok(FieldInfo {
+                    access_flags: field.access_flags,
+                    name_index: field.name_index,
+                    descriptor_index: field.descriptor_index,
+                    attributes_count: field.attributes.len() as u16,
+                    attributes: decode_all(&field.attributes)?,
+                })
+            })
+            .collect::<Result<Vec<FieldInfo>, ClassParseError>>()?;
+        let methods = self
+            .methods
+            .iter()
+            .map(|method| {
+                Ok(Method {
+                    access_flags: method.access_flags,
+                    name_index: method.name_index,
+                    descriptor_index: method.descriptor_index,
+                    attributes_count: method.attributes.len() as u16,
+                    attributes: decode_all(&method.attributes)?,
+                })
+            })
+            .collect::<Result<Vec<Method>, ClassParseError>>()?;
+        Ok(ClassFile {
+            magic: CLASS_FILE_MAGIC,
+            minor_version: self.minor_version,
+            major_version: self.major_version,
+            constant_pool_count: self.constant_pool.len() as u16,
+            constant_pool: self.owned_constant_pool()?.clone(),
+            access_flags: self.access_flags,
+            this_class: self.this_class,
+            super_class: self.super_class,
+            interfaces_count: self.interfaces.len() as u16,
+            interfaces: self.interfaces.clone(),
+            fields_count: fields.len() as u16,
+            fields,
+            methods_count: methods.len() as u16,
+            methods,
+            attributes_count: self.attributes.len() as u16,
+            attributes: decode_all(&self.attributes)?,
+        })
+    }
+
+    fn reader(&self, offset: usize, length: usize, path: Vec<String>) -> ClassReader<&'a [u8]> {
+        let mut reader = ClassReader::resume(
+            &self.bytes[offset..offset + length],
+            Arc::clone(&self.options),
+            offset,
+            path,
+        );
+        reader.set_version(self.version());
+        reader
+    }
+
+    fn forward_diagnostics(&self, reader: &mut ClassReader<&'a [u8]>) {
+        self.diagnostics
+            .borrow_mut()
+            .extend(reader.take_diagnostics());
+    }
+}
+
+impl<'a> Utf8Ref<'a> {
+    pub fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// The decoded text, borrowed from the input unless it contains NUL or supplementary
+    /// characters.
+    pub fn as_str(&self) -> Result<&str, Mutf8Error> {
+        match self.text.get_or_init(|| mutf8::decode_borrowed(self.bytes)) {
+            Ok(text) => Ok(text),
+            Err(err) => Err(*err),
+        }
+    }
+}
+
+impl<'a, F> MemberRef<'a, F> {
+    pub fn name<'c>(&self, class: &'c ClassFileRef<'a>) -> Option<&'c str> {
+        class.utf8(self.name_index)
+    }
+
+    pub fn descriptor<'c>(&self, class: &'c ClassFileRef<'a>) -> Option<&'c str> {
+        class.utf8(self.descriptor_index)
+    }
+
+    pub fn attribute(&self, class: &ClassFileRef<'a>, name: &str) -> Option<&AttributeRef<'a>> {
+        self.attributes
+            .iter()
+            .find(|attribute| class.utf8(attribute.name_index) == Some(name))
+    }
+}
+
+impl<'a> MethodRef<'a> {
+    /// The method's Code attribute, decoded on first access. `None` for abstract and
+    /// native methods.
+    pub fn code(&self, class: &ClassFileRef<'a>) -> Option<Result<&CodeRef<'a>, ClassParseError>> {
+        if let Some(code) = self.code.get() {
+            return Some(Ok(code));
+        }
+        let attribute = self.attribute(class, CODE_STR)?;
+        let Owner::Method(method) = attribute.owner else {
+            return None;
+        };
+        Some(CodeRef::parse(method, attribute, class).map(|code| self.code.get_or_init(|| code)))
+    }
+}
+
+impl<'a> CodeRef<'a> {
+    fn parse(
+        method: u16,
+        attribute: &AttributeRef<'a>,
+        class: &ClassFileRef<'a>,
+    ) -> Result<CodeRef<'a>, ClassParseError> {
+        let info_offset = attribute.offset + 6;
+        let mut path = attribute.path();
+        path.push(CODE_STR.to_string());
+        let reader = &mut class.reader(info_offset, attribute.info.len(), path);
+
+        let max_stack: u16 = reader.read_u16()?;
+        let max_locals: u16 = reader.read_u16()?;
+        let code_length: u32 = reader.read_u32()?;
+        let code = reader.scoped("code", |reader| reader.read_slice(code_length as usize))?;
+        let exception_table_length: u16 = reader.read_u16()?;
+        let exception_table = generate_exception_table(exception_table_length, reader)?;
+        let attributes_count: u16 = reader.read_u16()?;
+        let owner = Owner::Code {
+            method,
+            attribute: attribute.index,
+        };
+        let attributes = scan_attributes(attributes_count, owner, reader)?;
+
+        class.forward_diagnostics(reader);
+        let consumed = reader.offset() - info_offset;
+        if consumed != attribute.info.len() {
+            return Err(reader.error(ParseErrorKind::AttributeLengthMismatch {
+                declared: attribute.info.len(),
+                consumed,
+            }));
+        }
+        Ok(CodeRef {
+            max_stack,
+            max_locals,
+            code,
+            exception_table,
+            attributes,
+        })
+    }
+}
+
+impl<'a> AttributeRef<'a> {
+    pub fn name<'c>(&self, class: &'c ClassFileRef<'a>) -> Option<&'c str> {
+        class.utf8(self.name_index)
+    }
+
+    /// Decodes the attribute into the owned model on first access.
+    pub fn decode(&self, class: &ClassFileRef<'a>) -> Result<&AttributeType, ClassParseError> {
+        if let Some(attribute) = self.decoded.get() {
+            return Ok(attribute);
+        }
+        let constant_pool = class.owned_constant_pool()?;
+        let reader = &mut class.reader(self.offset, 6 + self.info.len(), self.owner.path());
+        let attribute = generate_attribute_at(self.index, constant_pool, reader)?;
+        class.forward_diagnostics(reader);
+        Ok(self.decoded.get_or_init(|| attribute))
+    }
+
+    fn path(&self) -> Vec<String> {
+        let mut path = self.owner.path();
+        path.push(format!("attributes[{}]", self.index));
+        path
+    }
+}
+
+fn scan_constant_pool<'a>(
+    reader: &mut ClassReader<&'a [u8]>,
+    constant_pool_count: u16,
+) -> Result<Vec<Option<ConstantEntry<'a>>>, ClassParseError> {
+    let constant_pool_count = constant_pool_count as usize;
+    let mut constant_pool: Vec<Option<ConstantEntry<'a>>> = Vec::with_capacity(constant_pool_count);
+    if constant_pool_count > 0 {
+        constant_pool.push(None);
+    }
+
+    while constant_pool.len() < constant_pool_count {
+        let index = constant_pool.len();
+        let entry = reader.scoped(format!("constant_pool[{index}]"), |reader| {
+            if reader.peek_u8() == Some(CONSTANT_POOL_UTF8) {
+                reader.read_u8()?;
+                let length = reader.read_u16()?;
+                let offset = reader.offset();
+                let bytes = reader.read_slice(length.into())?;
+                return Ok(ConstantEntry::Utf8(Utf8Ref {
+                    bytes,
+                    offset,
+                    text: OnceCell::new(),
+                }));
+            }
+            let entry_offset = reader.offset();
+            let constant = generate_constant(reader)?;
+            if constant.takes_two_slots() && index + 1 == constant_pool_count {
+                return Err(reader.error_at(
+                    entry_offset,
+                    ParseErrorKind::InvalidConstantIndex(constant_pool_count as u16),
+                ));
+            }
+            Ok(ConstantEntry::Other(constant))
+        })?;
+        let takes_two_slots =
+            matches!(&entry, ConstantEntry::Other(constant) if constant.takes_two_slots());
+        constant_pool.push(Some(entry));
+        if takes_two_slots {
+            constant_pool.push(Some(ConstantEntry::Other(Constant::Unusable)));
+        }
+    }
+    Ok(constant_pool)
+}
+
+fn scan_members<'a, F: From<u16>>(
+    table: &str,
+    count: u16,
+    owner: fn(u16) -> Owner,
+    reader: &mut ClassReader<&'a [u8]>,
+) -> Result<Vec<MemberRef<'a, F>>, ClassParseError> {
+    let mut members: Vec<MemberRef<'a, F>> = Vec::with_capacity(count as usize);
+    for index in 0..count {
+        let member = reader.scoped(format!("{table}[{index}]"), |reader| {
+            let access_flags = F::from(reader.read_u16()?);
+            let name_index: u16 = reader.read_u16()?;
+            let descriptor_index: u16 = reader.read_u16()?;
+            let attributes_count: u16 = reader.read_u16()?;
+            let attributes = scan_attributes(attributes_count, owner(index), reader)?;
+            Ok(MemberRef {
+                access_flags,
+                name_index,
+                descriptor_index,
+                attributes,
+                code: OnceCell::new(),
+            })
+        })?;
+        members.push(member);
+    }
+    Ok(members)
+}
+
+fn scan_attributes<'a>(
+    attributes_count: u16,
+    owner: Owner,
+    reader: &mut ClassReader<&'a [u8]>,
+) -> Result<Vec<AttributeRef<'a>>, ClassParseError> {
+    let mut attributes: Vec<AttributeRef<'a>> = Vec::with_capacity(attributes_count as usize);
+    for index in 0..attributes_count {
+        let attribute = reader.scoped(format!("attributes[{index}]"), |reader| {
+            let offset = reader.offset();
+            let name_index: u16 = reader.read_u16()?;
+            let attribute_length: u32 = reader.read_u32()?;
+            let info = reader.read_slice(attribute_length as usize)?;
+            Ok(AttributeRef {
+                name_index,
+                info,
+                offset,
+                index,
+                owner,
+                decoded: OnceCell::new(),
+            })
+        })?;
+        attributes.push(attribute);
+    }
+    Ok(attributes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classfile::testing::{u16_bytes, ClassBuilder};
+
+    /// A class with a constant field, a method with a body and line numbers, and a
+    /// SourceFile attribute.
+    fn sample_class() -> ClassBuilder {
+        let mut builder = ClassBuilder::new("Sample");
+        let value = builder.long(42);
+        let constant_value = builder.attribute("ConstantValue", &u16_bytes(&[value]));
+        builder.field(0x0019, "ANSWER", "J", &[constant_value]);
+        let line_numbers = builder.attribute("LineNumberTable", &u16_bytes(&[1, 0, 7]));
+        let code = builder.code(1, 1, &[0x2A, 0xB0], &[], &[line_numbers]);
+        builder.method(0x0001, "self", "()LSample;", &[code]);
+        let source_file = builder.utf8("Sample.java");
+        let source_file = builder.attribute("SourceFile", &u16_bytes(&[source_file]));
+        builder.class_attribute(source_file);
+        builder
+    }
+
+    #[test]
+    fn to_class_file_matches_the_owned_parser() {
+        let bytes = sample_class().build();
+        let class_file = ClassFile::from_bytes(&bytes).unwrap();
+        let class_ref = ClassFileRef::parse(&bytes).unwrap();
+        assert_eq!(class_ref.this_class_name(), Some("Sample"));
+        assert_eq!(
+            format!("{:?}", class_ref.to_class_file().unwrap()),
+            format!("{class_file:?}")
+        );
+        assert!(class_ref.take_diagnostics().is_empty());
+    }
+
+    #[test]
+    fn decodes_javac_output_like_the_owned_parser() {
+        let classes: [&[u8]; 4] = [
+            include_bytes!("../../ClassFileCheck.class"),
+            include_bytes!("../../testdata/Members.class"),
+            include_bytes!("../../testdata/Shapes$Circle.class"),
+            include_bytes!("../../testdata/module/module-info.class"),
+        ];
+        for bytes in classes {
+            let class_file = ClassFile::from_bytes(bytes).unwrap();
+            let class_ref = ClassFileRef::parse(bytes).unwrap();
+            assert_eq!(
+                format!("{:?}", class_ref.to_class_file().unwrap()),
+                format!("{class_file:?}")
+            );
+        }
+    }
+
+    #[test]
+    fn code_borrows_the_bytecode() {
+        let bytes = sample_class().build();
+        let class_ref = ClassFileRef::parse(&bytes).unwrap();
+        let method = &class_ref.methods[0];
+        assert_eq!(method.name(&class_ref), Some("self"));
+        let code = method.code(&class_ref).unwrap().unwrap();
+        assert_eq!((code.max_stack, code.max_locals), (1, 1));
+        assert_eq!(code.code, [0x2A, 0xB0]);
+        assert_eq!(code.attributes[0].name(&class_ref), Some("LineNumberTable"));
+        assert!(method.access_flags.is_public());
+        assert_eq!(
+            class_ref.fields[0].access_flags.to_string(),
+            "public static final"
+        );
+    }
+
+    #[test]
+    fn lenient_diagnostics_are_forwarded() {
+        let mut builder = sample_class();
+        builder.constant(&[CONSTANT_POOL_UTF8, 0, 1, 0xFF], 1);
+        let value = builder.integer(1);
+        let mut constant_value = builder.attribute("ConstantValue", &u16_bytes(&[value]));
+        constant_value[5] = 3;
+        constant_value.push(0);
+        builder.field(0x0018, "ONE", "I", &[constant_value]);
+        let bytes = builder.build();
+        let (class_file, diagnostics) = ClassFile::from_bytes_lenient(&bytes).unwrap();
+
+        let options = ParseOptions {
+            lenient: true,
+            ..ParseOptions::default()
+        };
+        let class_ref = ClassFileRef::parse_with(&bytes, &options).unwrap();
+        assert_eq!(
+            format!("{:?}", class_ref.to_class_file().unwrap()),
+            format!("{class_file:?}")
+        );
+        let forwarded = class_ref.take_diagnostics();
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(format!("{forwarded:?}"), format!("{diagnostics:?}"));
+        assert!(class_ref.take_diagnostics().is_empty());
+    }
+
+    #[test]
+    fn strict_decode_reports_the_attribute_path() {
+        let mut builder = sample_class();
+        let value = builder.integer(1);
+        let mut constant_value = builder.attribute("ConstantValue", &u16_bytes(&[value]));
+        constant_value[5] = 3;
+        constant_value.push(0);
+        builder.field(0x0018, "ONE", "I", &[constant_value]);
+        let bytes = builder.build();
+        let expected = ClassFile::from_bytes(&bytes).unwrap_err();
+
+        let class_ref = ClassFileRef::parse(&bytes).unwrap();
+        let err = class_ref.fields[1].attributes[0]
+            .decode(&class_ref)
+            .unwrap_err();
+        assert_eq!(err.to_string(), expected.to_string());
+        assert_eq!(err.path, "fields[1].attributes[0].ConstantValue");
+    }
+}
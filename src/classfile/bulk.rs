@@ -0,0 +1,356 @@
+//! Parsing many class files at once, spread over all cores.
+//!
+//! Inputs are handed out to worker threads one at a time, so a few large classes do not
+//! hold up the rest, and results are put back into input order before they are returned.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+};
+
+use memmap2::Mmap;
+
+use crate::classfile::{
+    archive::Archive,
+    error::{ClassParseError, ParseErrorKind},
+    options::ParseOptions,
+    ClassFile,
+};
+
+/// One class file to parse.
+pub enum ClassInput {
+    /// A file that is read into memory in one go.
+    Path(PathBuf),
+    /// A file that is memory mapped. It must not be modified while it is being parsed.
+    Mapped(PathBuf),
+    Bytes {
+        name: String,
+        bytes: Vec<u8>,
+    },
+    /// The entry at `index` in [`Archive::entries`].
+    ArchiveEntry {
+        archive: Arc<Archive>,
+        index: usize,
+    },
+}
+
+impl ClassInput {
+    /// The path of the file, or `archive!/entry` for an archive entry as in a jar URL.
+    pub fn name(&self) -> String {
+        match self {
+            ClassInput::Path(path) | ClassInput::Mapped(path) => path.display().to_string(),
+            ClassInput::Bytes { name, .. } => name.clone(),
+            ClassInput::ArchiveEntry { archive, index } => format!(
+                "{}!/{}",
+                archive.path().display(),
+                archive.entries()[*index].name
+            ),
+        }
+    }
+
+    pub fn parse(&self, options: &ParseOptions) -> Result<ClassFile, ClassParseError> {
+        match self {
+            ClassInput::Path(path) => {
+                let bytes = fs::read(path).map_err(io_error)?;
+                ClassFile::from_bytes_with(&bytes, options)
+            }
+            ClassInput::Mapped(path) => {
+                let file = File::open(path).map_err(io_error)?;
+                if file.metadata().map_err(io_error)?.len() == 0 {
+                    return ClassFile::from_bytes_with(&[], options);
+                }
+                // SAFETY: the map is only read, and callers are told not to modify the
+                // file while it is parsed.
+                let map = unsafe { Mmap::map(&file) }.map_err(io_error)?;
+                ClassFile::from_bytes_with(&map, options)
+            }
+            ClassInput::Bytes { bytes, .. } => ClassFile::from_bytes_with(bytes, options),
+            ClassInput::ArchiveEntry { archive, index } => {
+                let bytes = archive.read(&archive.entries()[*index]).map_err(io_error)?;
+                ClassFile::from_bytes_with(&bytes, options)
+            }
+        }
+    }
+
+    /// Every `.class` file below `directory`, sorted by path. `mapped` chooses
+    /// [`ClassInput::Mapped`] over [`ClassInput::Path`].
+    pub fn from_directory(directory: &Path, mapped: bool) -> io::Result<Vec<ClassInput>> {
+        let mut paths: Vec<PathBuf> = vec![];
+        let mut directories: Vec<PathBuf> = vec![directory.to_path_buf()];
+        while let Some(directory) = directories.pop() {
+            for entry in fs::read_dir(&directory)? {
+                let entry = entry?;
+                let path = entry.path();
+                if entry.file_type()?.is_dir() {
+                    directories.push(path);
+                } else if path
+                    .extension()
+                    .is_some_and(|extension| extension == "class")
+                {
+                    paths.push(path);
+                }
+            }
+        }
+        paths.sort();
+        Ok(paths
+            .into_iter()
+            .map(|path| match mapped {
+                true => ClassInput::Mapped(path),
+                false => ClassInput::Path(path),
+            })
+            .collect())
+    }
+
+    /// Every `.class` entry of `archive`, in the order of its central directory.
+    pub fn from_archive(archive: Arc<Archive>) -> Vec<ClassInput> {
+        (0..archive.entries().len())
+            .filter(|&index| archive.entries()[index].is_class())
+            .map(|index| ClassInput::ArchiveEntry {
+                archive: Arc::clone(&archive),
+                index,
+            })
+            .collect()
+    }
+}
+
+/// Parses inputs in parallel.
+#[derive(Debug, Clone, Default)]
+pub struct BulkParser {
+    pub options: ParseOptions,
+    /// Number of worker threads; 0 uses one per available core.
+    pub threads: usize,
+}
+
+impl BulkParser {
+    /// Parses every input and returns the results in input order.
+    pub fn parse(
+        &self,
+        inputs: &[ClassInput],
+    ) -> Vec<(String, Result<ClassFile, ClassParseError>)> {
+        let mut results = Vec::with_capacity(inputs.len());
+        self.for_each(inputs, |name, result| results.push((name, result)));
+        results
+    }
+
+    /// Parses every input and passes each result to `handle` in input order, as soon as
+    /// it and all results before it are ready. `handle` runs on the calling thread.
+    pub fn for_each(
+        &self,
+        inputs: &[ClassInput],
+        mut handle: impl FnMut(String, Result<ClassFile, ClassParseError>),
+    ) {
+        let threads = match self.threads {
+            0 => thread::available_parallelism().map_or(1, |threads| threads.get()),
+            threads => threads,
+        }
+        .min(inputs.len());
+        if threads <= 1 {
+            for input in inputs {
+                handle(input.name(), input.parse(&self.options));
+            }
+            return;
+        }
+
+        let next = AtomicUsize::new(0);
+        let (sender, receiver) = mpsc::channel();
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                let sender = sender.clone();
+                let next = &next;
+                scope.spawn(move || loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(input) = inputs.get(index) else {
+                        break;
+                    };
+                    if sender.send((index, input.parse(&self.options))).is_err() {
+                        break;
+                    }
+                });
+            }
+            drop(sender);
+
+            // Results arrive in completion order; hold each until those before it are in.
+            let mut pending: BTreeMap<usize, Result<ClassFile, ClassParseError>> = BTreeMap::new();
+            let mut expected = 0;
+            for (index, result) in receiver {
+                pending.insert(index, result);
+                while let Some(result) = pending.remove(&expected) {
+                    handle(inputs[expected].name(), result);
+                    expected += 1;
+                }
+            }
+        });
+    }
+}
+
+fn io_error(err: io::Error) -> ClassParseError {
+    ClassParseError {
+        kind: ParseErrorKind::Io(err),
+        offset: 0,
+        path: String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHAPES: &[u8] = include_bytes!("../../testdata/Shapes.class");
+    const CIRCLE: &[u8] = include_bytes!("../../testdata/Shapes$Circle.class");
+    const POLYGON: &[u8] = include_bytes!("../../testdata/Shapes$Polygon.class");
+
+    fn bytes(name: &str, bytes: &[u8]) -> ClassInput {
+        ClassInput::Bytes {
+            name: name.to_string(),
+            bytes: bytes.to_vec(),
+        }
+    }
+
+    /// A directory of its own under the system temporary directory, removed on drop.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let path =
+                std::env::temp_dir().join(format!("rust-java-bulk-{}-{name}", std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+
+        fn write(&self, name: &str, bytes: &[u8]) {
+            let path = self.0.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, bytes).unwrap();
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn results_come_back_in_input_order() {
+        let classes = [SHAPES, CIRCLE, POLYGON];
+        let inputs: Vec<ClassInput> = (0..60)
+            .map(|n| bytes(&format!("class {n}"), classes[n % 3]))
+            .collect();
+        let parser = BulkParser {
+            threads: 4,
+            ..BulkParser::default()
+        };
+        let results = parser.parse(&inputs);
+        assert_eq!(results.len(), inputs.len());
+        for (n, (name, result)) in results.iter().enumerate() {
+            assert_eq!(*name, format!("class {n}"));
+            let expected = ClassFile::from_bytes(classes[n % 3]).unwrap();
+            assert_eq!(
+                format!("{:?}", result.as_ref().unwrap()),
+                format!("{expected:?}")
+            );
+        }
+    }
+
+    #[test]
+    fn a_failure_does_not_stop_the_other_inputs() {
+        let inputs = [
+            bytes("Shapes", SHAPES),
+            bytes("truncated", &CIRCLE[..100]),
+            bytes("Polygon", POLYGON),
+            bytes("not a class", b"PK\x03\x04"),
+            ClassInput::Path(PathBuf::from("/nonexistent/Missing.class")),
+        ];
+        let parser = BulkParser {
+            threads: 3,
+            ..BulkParser::default()
+        };
+        let results = parser.parse(&inputs);
+        let names: Vec<&str> = results.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "Shapes",
+                "truncated",
+                "Polygon",
+                "not a class",
+                "/nonexistent/Missing.class"
+            ]
+        );
+        assert!(results[0].1.is_ok());
+        assert!(results[2].1.is_ok());
+
+        let err = results[1].1.as_ref().unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::UnexpectedEof));
+        assert_eq!(err.offset, 100);
+        let err = results[3].1.as_ref().unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::InvalidMagic(0x504B0304)));
+        let err = results[4].1.as_ref().unwrap_err();
+        assert!(
+            matches!(&err.kind, ParseErrorKind::Io(err) if err.kind() == io::ErrorKind::NotFound)
+        );
+    }
+
+    #[test]
+    fn finds_every_class_file_below_a_directory_in_path_order() {
+        let directory = TempDir::new("directory");
+        directory.write("Shapes.class", SHAPES);
+        directory.write("shapes/Shapes$Polygon.class", POLYGON);
+        directory.write("README.md", b"Not a class file.");
+        directory.write("shapes/Shapes$Circle.class", CIRCLE);
+        directory.write("Shapes.class.orig", SHAPES);
+        directory.write("empty/Empty.class", &[]);
+
+        let inputs = ClassInput::from_directory(&directory.0, false).unwrap();
+        let names: Vec<String> = inputs.iter().map(ClassInput::name).collect();
+        let expected: Vec<String> = [
+            "Shapes.class",
+            "empty/Empty.class",
+            "shapes/Shapes$Circle.class",
+            "shapes/Shapes$Polygon.class",
+        ]
+        .iter()
+        .map(|name| directory.0.join(name).display().to_string())
+        .collect();
+        assert_eq!(names, expected);
+        assert!(inputs
+            .iter()
+            .all(|input| matches!(input, ClassInput::Path(_))));
+
+        let mapped = ClassInput::from_directory(&directory.0, true).unwrap();
+        let results = BulkParser::default().parse(&mapped);
+        let parsed: Vec<bool> = results.iter().map(|(_, result)| result.is_ok()).collect();
+        assert_eq!(parsed, [true, false, true, true]);
+        assert!(matches!(
+            results[1].1.as_ref().unwrap_err().kind,
+            ParseErrorKind::UnexpectedEof
+        ));
+    }
+
+    #[test]
+    fn archive_entries_are_named_like_jar_urls() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/shapes.jar");
+        let archive = Arc::new(Archive::open(&path).unwrap());
+        let inputs = ClassInput::from_archive(archive);
+        let names: Vec<String> = inputs.iter().map(ClassInput::name).collect();
+        assert_eq!(
+            names,
+            [
+                format!("{}!/Shapes$Circle.class", path.display()),
+                format!("{}!/Shapes$Polygon.class", path.display()),
+                format!("{}!/Shapes.class", path.display()),
+            ]
+        );
+        for (name, result) in BulkParser::default().parse(&inputs) {
+            assert!(result.is_ok(), "{name}: {:?}", result.err());
+        }
+    }
+}
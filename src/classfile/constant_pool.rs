@@ -1,313 +1,616 @@
-use byteorder::{BigEndian, ReadBytesExt};
-use std::{fs::File, io::BufReader, str};
+use std::{fmt, io::Read};
 
-use crate::classfile::BYTE_LENGTH_UNAVAILABLE_ERROR;
+use crate::classfile::{
+    error::{ClassParseError, ParseErrorKind},
+    mutf8,
+    reader::ClassReader,
+    version,
+};
 
-pub fn generate_constant_pool(
-    reader: &mut BufReader<File>,
-    constant_pool_size: usize,
-) -> Vec<Option<Constant>> {
-    let mut constant_pool: Vec<Option<Constant>> = vec![];
-
-    for _ in 1..(constant_pool_size + 1) {
+/// Reads the constant pool into a vector indexed exactly like the class file: entry 0
+/// is always `None`, and the slot following a `CONSTANT_Long` or `CONSTANT_Double`
+/// holds [`Constant::Unusable`] as required by JVMS §4.4.5.
+pub fn generate_constant_pool<R: Read>(
+    reader: &mut ClassReader<R>,
+    constant_pool_count: u16,
+) -> Result<ConstantPool, ClassParseError> {
+    let constant_pool_count = constant_pool_count as usize;
+    let mut constant_pool: Vec<Option<Constant>> = Vec::with_capacity(constant_pool_count);
+    if constant_pool_count > 0 {
         constant_pool.push(None);
     }
 
-    println!(
-        "constant pool size: {:?}, given size: {:?}",
-        constant_pool.len(),
-        constant_pool_size + 1
-    );
-    for index in 1..constant_pool_size {
-        let tag: u8 = reader.read_u8().expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-
-        match tag {
-            CONSTANT_POOL_CLASS => {
-                let name_index: u16 = reader
-                    .read_u16::<BigEndian>()
-                    .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                let class = ConstantClass { tag, name_index };
-                println!("index: {index}, class: {:?}", class);
-                constant_pool[index] = Some(Constant::Class(class));
-                println!("Class name index: {name_index}");
-            }
-            CONSTANT_POOL_FIELDREF | CONSTANT_POOL_METHODREF | CONSTANT_POOL_INTERFACEMETHODREF => {
-                let class_index: u16 = reader
-                    .read_u16::<BigEndian>()
-                    .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                let name_and_type_index: u16 = reader
-                    .read_u16::<BigEndian>()
-                    .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                let constant_ref = ConstantRef {
-                    tag,
-                    class_index,
-                    name_and_type_index,
-                };
-                constant_pool[index] = Some(Constant::Ref(constant_ref));
-                println!("Class index: {class_index}, name and type index: {name_and_type_index}");
-            }
-            CONSTANT_POOL_STRING => {
-                let string_index: u16 = reader
-                    .read_u16::<BigEndian>()
-                    .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                let string = ConstantStringInfo { tag, string_index };
-                constant_pool[index] = Some(Constant::String(string));
-                println!("string index: {string_index}");
-            }
-            CONSTANT_POOL_INTEGER => {
-                let bytes: u32 = reader
-                    .read_u32::<BigEndian>()
-                    .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                let integer = ConstantInteger { tag, bytes };
-                constant_pool[index] = Some(Constant::Integer(integer));
-                println!("integer bytes: {bytes}");
-            }
-            CONSTANT_POOL_FLOAT => {
-                let bytes: u32 = reader
-                    .read_u32::<BigEndian>()
-                    .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                let float = ConstantFloat { tag, bytes };
-                constant_pool[index] = Some(Constant::Float(float));
-                println!("float bytes: {bytes}");
-            }
-            CONSTANT_POOL_LONG => {
-                let high_bytes: u32 = reader
-                    .read_u32::<BigEndian>()
-                    .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                let low_bytes: u32 = reader
-                    .read_u32::<BigEndian>()
-                    .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                let long = ConstantLong {
-                    tag,
-                    high_bytes,
-                    low_bytes,
-                };
-                constant_pool[index] = Some(Constant::Long(long));
-                println!("long high bytes: {high_bytes}, long low bytes: {low_bytes}");
+    while constant_pool.len() < constant_pool_count {
+        let index = constant_pool.len();
+        let constant = reader.scoped(format!("constant_pool[{index}]"), |reader| {
+            let entry_offset = reader.offset();
+            let constant = generate_constant(reader)?;
+            if constant.takes_two_slots() && index + 1 == constant_pool_count {
+                return Err(reader.error_at(
+                    entry_offset,
+                    ParseErrorKind::InvalidConstantIndex(constant_pool_count as u16),
+                ));
             }
-            CONSTANT_POOL_DOUBLE => {
-                let high_bytes: u32 = reader
-                    .read_u32::<BigEndian>()
-                    .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                let low_bytes: u32 = reader
-                    .read_u32::<BigEndian>()
-                    .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                let double = ConstantDouble {
-                    tag,
-                    high_bytes,
-                    low_bytes,
-                };
-                constant_pool[index] = Some(Constant::Double(double));
-                println!("double high bytes: {high_bytes}");
-            }
-            CONSTANT_POOL_NAME_AND_TYPE => {
-                let name_index: u16 = reader
-                    .read_u16::<BigEndian>()
-                    .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                let descriptor_index: u16 = reader
-                    .read_u16::<BigEndian>()
-                    .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                let name_and_type = ConstantNameAndType {
-                    tag,
-                    name_index,
-                    descriptor_index,
-                };
-                constant_pool[index] = Some(Constant::NameAndType(name_and_type));
-                println!(
-                    "name and type. name index: {name_index}, descriptor index: {descriptor_index}"
-                );
-            }
-            CONSTANT_POOL_UTF8 => {
-                let length = reader
-                    .read_u16::<BigEndian>()
-                    .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                let mut bytes: Vec<u8> = vec![];
-                for _ in 0..length {
-                    bytes.push(reader.read_u8().expect(BYTE_LENGTH_UNAVAILABLE_ERROR));
+            Ok(constant)
+        })?;
+        let takes_two_slots = constant.takes_two_slots();
+        constant_pool.push(Some(constant));
+        if takes_two_slots {
+            constant_pool.push(Some(Constant::Unusable));
+        }
+    }
+    Ok(ConstantPool::new(constant_pool))
+}
+
+pub(crate) fn generate_constant<R: Read>(
+    reader: &mut ClassReader<R>,
+) -> Result<Constant, ClassParseError> {
+    let tag_offset = reader.offset();
+    let tag: u8 = reader.read_u8()?;
+
+    let constant = match tag {
+        CONSTANT_POOL_CLASS => {
+            let name_index: u16 = reader.read_u16()?;
+            Constant::Class(ConstantClass { tag, name_index })
+        }
+        CONSTANT_POOL_FIELDREF => {
+            let class_index: u16 = reader.read_u16()?;
+            let name_and_type_index: u16 = reader.read_u16()?;
+            Constant::FieldRef(ConstantFieldRef {
+                tag,
+                class_index,
+                name_and_type_index,
+            })
+        }
+        CONSTANT_POOL_METHODREF => {
+            let class_index: u16 = reader.read_u16()?;
+            let name_and_type_index: u16 = reader.read_u16()?;
+            Constant::MethodRef(ConstantMethodRef {
+                tag,
+                class_index,
+                name_and_type_index,
+            })
+        }
+        CONSTANT_POOL_INTERFACEMETHODREF => {
+            let class_index: u16 = reader.read_u16()?;
+            let name_and_type_index: u16 = reader.read_u16()?;
+            Constant::InterfaceMethodRef(ConstantInterfaceMethodRef {
+                tag,
+                class_index,
+                name_and_type_index,
+            })
+        }
+        CONSTANT_POOL_STRING => {
+            let string_index: u16 = reader.read_u16()?;
+            Constant::String(ConstantStringInfo { tag, string_index })
+        }
+        CONSTANT_POOL_INTEGER => {
+            let bytes: u32 = reader.read_u32()?;
+            Constant::Integer(ConstantInteger { tag, bytes })
+        }
+        CONSTANT_POOL_FLOAT => {
+            let bytes: u32 = reader.read_u32()?;
+            Constant::Float(ConstantFloat { tag, bytes })
+        }
+        CONSTANT_POOL_LONG => {
+            let high_bytes: u32 = reader.read_u32()?;
+            let low_bytes: u32 = reader.read_u32()?;
+            Constant::Long(ConstantLong {
+                tag,
+                high_bytes,
+                low_bytes,
+            })
+        }
+        CONSTANT_POOL_DOUBLE => {
+            let high_bytes: u32 = reader.read_u32()?;
+            let low_bytes: u32 = reader.read_u32()?;
+            Constant::Double(ConstantDouble {
+                tag,
+                high_bytes,
+                low_bytes,
+            })
+        }
+        CONSTANT_POOL_NAME_AND_TYPE => {
+            let name_index: u16 = reader.read_u16()?;
+            let descriptor_index: u16 = reader.read_u16()?;
+            Constant::NameAndType(ConstantNameAndType {
+                tag,
+                name_index,
+                descriptor_index,
+            })
+        }
+        CONSTANT_POOL_UTF8 => {
+            let length = reader.read_u16()?;
+            let bytes_offset = reader.offset();
+            let bytes: Vec<u8> = reader.read_bytes(length.into())?;
+            let utf_str: String = match mutf8::decode(&bytes) {
+                Ok(utf_str) => utf_str,
+                Err(err) => {
+                    reader.recover(
+                        reader.error_at(bytes_offset + err.position, ParseErrorKind::InvalidUtf8),
+                    )?;
+                    mutf8::decode_lossy(&bytes)
                 }
-                let utf_str: String = str::from_utf8(&bytes)
-                    .expect(BYTE_LENGTH_UNAVAILABLE_ERROR)
-                    .into();
-                let utf8 = ConstantUtf8Info {
-                    tag,
-                    utf_str: utf_str.clone(),
-                };
-                constant_pool[index] = Some(Constant::Utf8Info(utf8));
-                println!("utf8 string: {utf_str}, length: {length}");
-            }
-            CONSTANT_POOL_METHOD_HANDLE => {
-                let reference_kind: u8 = reader.read_u8().expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                let reference_index: u16 = reader
-                    .read_u16::<BigEndian>()
-                    .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                let method_handle = ConstantMethodHandle {
-                    tag,
-                    reference_index,
-                    reference_kind,
-                };
-                constant_pool[index] = Some(Constant::MethodHandle(method_handle));
-                println!("method handle. reference kind: {reference_kind}, reference index: {reference_index}");
-            }
-            CONSTANT_POOL_METHOD_TYPE => {
-                let descriptor_index: u16 = reader
-                    .read_u16::<BigEndian>()
-                    .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                let method_type = ConstantMethodType {
-                    tag,
-                    descriptor_index,
-                };
-                constant_pool[index] = Some(Constant::MethodType(method_type));
-                println!("method type. descriptor index: {descriptor_index}");
-            }
-            CONSTANT_POOL_INVOKE_DYNAMIC => {
-                let bootstrap_method_attr_index: u16 = reader
-                    .read_u16::<BigEndian>()
-                    .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                let name_and_type_index: u16 = reader
-                    .read_u16::<BigEndian>()
-                    .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-                let invoke_dynamic = ConstantInvokeDynamic {
-                    tag,
-                    bootstrap_method_attr_index,
-                    name_and_type_index,
-                };
-                constant_pool[index] = Some(Constant::InvokeDynamic(invoke_dynamic));
-                println!("invoke dynamic. bootstrap method attr index: {bootstrap_method_attr_index}, name and type index: {name_and_type_index}");
+            };
+            Constant::Utf8Info(ConstantUtf8Info {
+                tag,
+                bytes,
+                utf_str,
+            })
+        }
+        CONSTANT_POOL_METHOD_HANDLE => {
+            let reference_kind: u8 = reader.read_u8()?;
+            let reference_index: u16 = reader.read_u16()?;
+            Constant::MethodHandle(ConstantMethodHandle {
+                tag,
+                reference_kind,
+                reference_index,
+            })
+        }
+        CONSTANT_POOL_METHOD_TYPE => {
+            let descriptor_index: u16 = reader.read_u16()?;
+            Constant::MethodType(ConstantMethodType {
+                tag,
+                descriptor_index,
+            })
+        }
+        CONSTANT_POOL_DYNAMIC => {
+            let bootstrap_method_attr_index: u16 = reader.read_u16()?;
+            let name_and_type_index: u16 = reader.read_u16()?;
+            Constant::Dynamic(ConstantDynamic {
+                tag,
+                bootstrap_method_attr_index,
+                name_and_type_index,
+            })
+        }
+        CONSTANT_POOL_MODULE => {
+            let name_index: u16 = reader.read_u16()?;
+            Constant::Module(ConstantModule { tag, name_index })
+        }
+        CONSTANT_POOL_PACKAGE => {
+            let name_index: u16 = reader.read_u16()?;
+            Constant::Package(ConstantPackage { tag, name_index })
+        }
+        CONSTANT_POOL_INVOKE_DYNAMIC => {
+            let bootstrap_method_attr_index: u16 = reader.read_u16()?;
+            let name_and_type_index: u16 = reader.read_u16()?;
+            Constant::InvokeDynamic(ConstantInvokeDynamic {
+                tag,
+                bootstrap_method_attr_index,
+                name_and_type_index,
+            })
+        }
+        _ => {
+            return Err(reader.error_at(tag_offset, ParseErrorKind::UnknownConstantTag(tag)));
+        }
+    };
+    if let Some(since) = version::constant_since(tag) {
+        reader.require_version(since, tag_offset, || constant.kind().to_string())?;
+    }
+    reader.describe(|| constant.to_string());
+    Ok(constant)
+}
+
+/// The constant pool, indexed exactly like the class file: entry 0 is always `None`, and
+/// the slot following a `CONSTANT_Long` or `CONSTANT_Double` holds
+/// [`Constant::Unusable`]. The accessors check that an index refers to an entry of the
+/// kind asked for and resolve the entries it refers to in turn.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ConstantPool {
+    entries: Vec<Option<Constant>>,
+}
+
+impl ConstantPool {
+    pub fn new(entries: Vec<Option<Constant>>) -> ConstantPool {
+        ConstantPool { entries }
+    }
+
+    pub fn entries(&self) -> &[Option<Constant>] {
+        &self.entries
+    }
+
+    pub fn into_entries(self) -> Vec<Option<Constant>> {
+        self.entries
+    }
+
+    /// The number of slots, which is the class file's `constant_pool_count`.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Every usable entry with its index.
+    pub fn iter(&self) -> impl Iterator<Item = (u16, &Constant)> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| match entry {
+                Some(Constant::Unusable) | None => None,
+                Some(constant) => Some((index as u16, constant)),
+            })
+    }
+
+    pub fn entry(&self, index: u16) -> Result<&Constant, ConstantPoolError> {
+        match self.entries.get(index as usize) {
+            Some(Some(Constant::Unusable)) | Some(None) | None => {
+                Err(ConstantPoolError::InvalidIndex(index))
             }
+            Some(Some(constant)) => Ok(constant),
+        }
+    }
+
+    fn unexpected(&self, index: u16, expected: &'static str) -> ConstantPoolError {
+        match self.entry(index) {
+            Ok(constant) => ConstantPoolError::UnexpectedKind {
+                index,
+                expected,
+                found: constant.kind(),
+            },
+            Err(err) => err,
+        }
+    }
+
+    pub fn utf8(&self, index: u16) -> Result<&str, ConstantPoolError> {
+        match self.entry(index)? {
+            Constant::Utf8Info(utf8) => Ok(&utf8.utf_str),
+            _ => Err(self.unexpected(index, "CONSTANT_Utf8")),
+        }
+    }
+
+    /// The internal name of a `CONSTANT_Class`, e.g. `java/lang/String`, or an array
+    /// descriptor such as `[I`.
+    pub fn class_name(&self, index: u16) -> Result<&str, ConstantPoolError> {
+        match self.entry(index)? {
+            Constant::Class(class) => self.utf8(class.name_index),
+            _ => Err(self.unexpected(index, "CONSTANT_Class")),
+        }
+    }
+
+    /// The value of a `CONSTANT_String`.
+    pub fn string(&self, index: u16) -> Result<&str, ConstantPoolError> {
+        match self.entry(index)? {
+            Constant::String(string) => self.utf8(string.string_index),
+            _ => Err(self.unexpected(index, "CONSTANT_String")),
+        }
+    }
+
+    /// The name and descriptor of a `CONSTANT_NameAndType`.
+    pub fn name_and_type(&self, index: u16) -> Result<(&str, &str), ConstantPoolError> {
+        match self.entry(index)? {
+            Constant::NameAndType(name_and_type) => Ok((
+                self.utf8(name_and_type.name_index)?,
+                self.utf8(name_and_type.descriptor_index)?,
+            )),
+            _ => Err(self.unexpected(index, "CONSTANT_NameAndType")),
+        }
+    }
+
+    /// Resolves a `CONSTANT_Fieldref`, `CONSTANT_Methodref` or
+    /// `CONSTANT_InterfaceMethodref`.
+    pub fn member_ref(&self, index: u16) -> Result<MemberReference<'_>, ConstantPoolError> {
+        let (kind, class_index, name_and_type_index) = match self.entry(index)? {
+            Constant::FieldRef(field_ref) => (
+                MemberRefKind::Field,
+                field_ref.class_index,
+                field_ref.name_and_type_index,
+            ),
+            Constant::MethodRef(method_ref) => (
+                MemberRefKind::Method,
+                method_ref.class_index,
+                method_ref.name_and_type_index,
+            ),
+            Constant::InterfaceMethodRef(method_ref) => (
+                MemberRefKind::InterfaceMethod,
+                method_ref.class_index,
+                method_ref.name_and_type_index,
+            ),
             _ => {
-                println!("index: {index}, tag: {tag}");
-                panic!("Unexpected tag type for constant pool")
+                return Err(self.unexpected(
+                    index,
+                    "CONSTANT_Fieldref, CONSTANT_Methodref or CONSTANT_InterfaceMethodref",
+                ))
             }
+        };
+        let (name, descriptor) = self.name_and_type(name_and_type_index)?;
+        Ok(MemberReference {
+            kind,
+            owner: self.class_name(class_index)?,
+            name,
+            descriptor,
+        })
+    }
+
+    pub fn integer(&self, index: u16) -> Result<i32, ConstantPoolError> {
+        match self.entry(index)? {
+            Constant::Integer(integer) => Ok(integer.value()),
+            _ => Err(self.unexpected(index, "CONSTANT_Integer")),
+        }
+    }
+
+    pub fn float(&self, index: u16) -> Result<f32, ConstantPoolError> {
+        match self.entry(index)? {
+            Constant::Float(float) => Ok(float.value()),
+            _ => Err(self.unexpected(index, "CONSTANT_Float")),
+        }
+    }
+
+    pub fn long(&self, index: u16) -> Result<i64, ConstantPoolError> {
+        match self.entry(index)? {
+            Constant::Long(long) => Ok(long.value()),
+            _ => Err(self.unexpected(index, "CONSTANT_Long")),
+        }
+    }
+
+    pub fn double(&self, index: u16) -> Result<f64, ConstantPoolError> {
+        match self.entry(index)? {
+            Constant::Double(double) => Ok(double.value()),
+            _ => Err(self.unexpected(index, "CONSTANT_Double")),
         }
     }
-    constant_pool
 }
 
-pub struct ConstantPool {
-    tag: u8,
-    info: Vec<u8>,
-}
-
-const CONSTANT_POOL_CLASS: u8 = 7;
-const CONSTANT_POOL_FIELDREF: u8 = 9;
-const CONSTANT_POOL_METHODREF: u8 = 10;
-const CONSTANT_POOL_INTERFACEMETHODREF: u8 = 11;
-const CONSTANT_POOL_STRING: u8 = 8;
-const CONSTANT_POOL_INTEGER: u8 = 3;
-const CONSTANT_POOL_FLOAT: u8 = 4;
-const CONSTANT_POOL_LONG: u8 = 5;
-const CONSTANT_POOL_DOUBLE: u8 = 6;
-const CONSTANT_POOL_NAME_AND_TYPE: u8 = 12;
-const CONSTANT_POOL_UTF8: u8 = 1;
-const CONSTANT_POOL_METHOD_HANDLE: u8 = 15;
-const CONSTANT_POOL_METHOD_TYPE: u8 = 16;
-const CONSTANT_POOL_INVOKE_DYNAMIC: u8 = 18;
+impl From<Vec<Option<Constant>>> for ConstantPool {
+    fn from(entries: Vec<Option<Constant>>) -> ConstantPool {
+        ConstantPool::new(entries)
+    }
+}
 
-#[derive(Debug, Clone, Copy)]
-pub struct ConstantClass {
-    tag: u8,
-    name_index: u16,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConstantPoolError {
+    /// The index is 0, past the end of the pool, or the slot after a `CONSTANT_Long` or
+    /// `CONSTANT_Double`.
+    InvalidIndex(u16),
+    UnexpectedKind {
+        index: u16,
+        expected: &'static str,
+        found: &'static str,
+    },
 }
 
-#[derive(Debug, Clone)]
-pub struct ConstantRef {
-    tag: u8,
-    class_index: u16,
-    name_and_type_index: u16,
+impl fmt::Display for ConstantPoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConstantPoolError::InvalidIndex(index) => {
+                write!(f, "constant pool index {index} is out of range or unusable")
+            }
+            ConstantPoolError::UnexpectedKind {
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "constant pool entry {index} is a {found}, expected {expected}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConstantPoolError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum MemberRefKind {
+    Field,
+    Method,
+    InterfaceMethod,
+}
+
+/// A resolved field or method reference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemberReference<'a> {
+    pub kind: MemberRefKind,
+    /// Internal name of the class or interface the member is looked up in.
+    pub owner: &'a str,
+    pub name: &'a str,
+    pub descriptor: &'a str,
+}
+
+pub(crate) const CONSTANT_POOL_CLASS: u8 = 7;
+pub(crate) const CONSTANT_POOL_FIELDREF: u8 = 9;
+pub(crate) const CONSTANT_POOL_METHODREF: u8 = 10;
+pub(crate) const CONSTANT_POOL_INTERFACEMETHODREF: u8 = 11;
+pub(crate) const CONSTANT_POOL_STRING: u8 = 8;
+pub(crate) const CONSTANT_POOL_INTEGER: u8 = 3;
+pub(crate) const CONSTANT_POOL_FLOAT: u8 = 4;
+pub(crate) const CONSTANT_POOL_LONG: u8 = 5;
+pub(crate) const CONSTANT_POOL_DOUBLE: u8 = 6;
+pub(crate) const CONSTANT_POOL_NAME_AND_TYPE: u8 = 12;
+pub(crate) const CONSTANT_POOL_UTF8: u8 = 1;
+pub(crate) const CONSTANT_POOL_METHOD_HANDLE: u8 = 15;
+pub(crate) const CONSTANT_POOL_METHOD_TYPE: u8 = 16;
+pub(crate) const CONSTANT_POOL_DYNAMIC: u8 = 17;
+pub(crate) const CONSTANT_POOL_INVOKE_DYNAMIC: u8 = 18;
+pub(crate) const CONSTANT_POOL_MODULE: u8 = 19;
+pub(crate) const CONSTANT_POOL_PACKAGE: u8 = 20;
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ConstantClass {
+    pub tag: u8,
+    pub name_index: u16,
 }
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ConstantFieldRef {
-    tag: u8,
-    class_index: u16,
-    name_and_type_index: u16,
+    pub tag: u8,
+    pub class_index: u16,
+    pub name_and_type_index: u16,
 }
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ConstantMethodRef {
-    tag: u8,
-    class_index: u16,
-    name_and_type_index: u16,
+    pub tag: u8,
+    pub class_index: u16,
+    pub name_and_type_index: u16,
 }
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ConstantInterfaceMethodRef {
-    tag: u8,
-    class_index: u16,
-    name_and_type_index: u16,
+    pub tag: u8,
+    pub class_index: u16,
+    pub name_and_type_index: u16,
 }
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ConstantStringInfo {
-    tag: u8,
-    string_index: u16,
+    pub tag: u8,
+    pub string_index: u16,
 }
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ConstantInteger {
-    tag: u8,
-    bytes: u32,
+    pub tag: u8,
+    pub bytes: u32,
+}
+
+impl ConstantInteger {
+    pub fn value(&self) -> i32 {
+        self.bytes as i32
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ConstantFloat {
-    tag: u8,
-    bytes: u32,
+    pub tag: u8,
+    pub bytes: u32,
+}
+
+impl ConstantFloat {
+    pub fn value(&self) -> f32 {
+        f32::from_bits(self.bytes)
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ConstantLong {
-    tag: u8,
-    high_bytes: u32,
-    low_bytes: u32,
+    pub tag: u8,
+    pub high_bytes: u32,
+    pub low_bytes: u32,
+}
+
+impl ConstantLong {
+    pub fn value(&self) -> i64 {
+        ((self.high_bytes as u64) << 32 | self.low_bytes as u64) as i64
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ConstantDouble {
-    tag: u8,
-    high_bytes: u32,
-    low_bytes: u32,
+    pub tag: u8,
+    pub high_bytes: u32,
+    pub low_bytes: u32,
+}
+
+impl ConstantDouble {
+    pub fn value(&self) -> f64 {
+        f64::from_bits((self.high_bytes as u64) << 32 | self.low_bytes as u64)
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ConstantNameAndType {
-    tag: u8,
-    name_index: u16,
-    descriptor_index: u16,
+    pub tag: u8,
+    pub name_index: u16,
+    pub descriptor_index: u16,
 }
 
+/// `bytes` holds the entry exactly as stored in the class file, in modified UTF-8.
+/// `utf_str` is its decoded form, which is exact unless the entry contains unpaired
+/// surrogates; those are replaced with U+FFFD and [`ConstantUtf8Info::utf16`] gives the
+/// lossless view. Entries that are not valid modified UTF-8 at all are only accepted by a
+/// lenient parse, which decodes them lossily.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ConstantUtf8Info {
     pub tag: u8,
-    // length: u16,
-    // bytes: Vec<u8>,
+    pub bytes: Vec<u8>,
     pub utf_str: String,
 }
 
+impl ConstantUtf8Info {
+    pub fn new(utf_str: &str) -> ConstantUtf8Info {
+        ConstantUtf8Info {
+            tag: CONSTANT_POOL_UTF8,
+            bytes: mutf8::encode(utf_str),
+            utf_str: utf_str.to_string(),
+        }
+    }
+
+    pub fn utf16(&self) -> Vec<u16> {
+        mutf8::decode_utf16(&self.bytes).unwrap_or_else(|_| self.utf_str.encode_utf16().collect())
+    }
+
+    /// True if `utf_str` represents the entry exactly.
+    pub fn is_lossless(&self) -> bool {
+        match mutf8::decode_utf16(&self.bytes) {
+            Ok(units) => !mutf8::has_unpaired_surrogates(&units),
+            Err(_) => false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ConstantMethodHandle {
-    tag: u8,
-    reference_kind: u8,
-    reference_index: u16,
+    pub tag: u8,
+    pub reference_kind: u8,
+    pub reference_index: u16,
 }
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ConstantMethodType {
-    tag: u8,
-    descriptor_index: u16,
+    pub tag: u8,
+    pub descriptor_index: u16,
+}
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ConstantDynamic {
+    pub tag: u8,
+    pub bootstrap_method_attr_index: u16,
+    pub name_and_type_index: u16,
+}
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ConstantModule {
+    pub tag: u8,
+    pub name_index: u16,
+}
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ConstantPackage {
+    pub tag: u8,
+    pub name_index: u16,
 }
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ConstantInvokeDynamic {
-    tag: u8,
-    bootstrap_method_attr_index: u16,
-    name_and_type_index: u16,
+    pub tag: u8,
+    pub bootstrap_method_attr_index: u16,
+    pub name_and_type_index: u16,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Constant {
     Class(ConstantClass),
-    Ref(ConstantRef),
     FieldRef(ConstantFieldRef),
     MethodRef(ConstantMethodRef),
     InterfaceMethodRef(ConstantInterfaceMethodRef),
@@ -321,4 +624,315 @@ pub enum Constant {
     MethodHandle(ConstantMethodHandle),
     MethodType(ConstantMethodType),
     InvokeDynamic(ConstantInvokeDynamic),
+    Dynamic(ConstantDynamic),
+    Module(ConstantModule),
+    Package(ConstantPackage),
+    /// The slot after a `CONSTANT_Long` or `CONSTANT_Double`, which must not be referenced.
+    Unusable,
+}
+
+impl Constant {
+    /// The entry's tag byte, or `None` for [`Constant::Unusable`].
+    pub fn tag(&self) -> Option<u8> {
+        let tag = match self {
+            Constant::Class(class) => class.tag,
+            Constant::FieldRef(field_ref) => field_ref.tag,
+            Constant::MethodRef(method_ref) => method_ref.tag,
+            Constant::InterfaceMethodRef(method_ref) => method_ref.tag,
+            Constant::String(string) => string.tag,
+            Constant::Integer(integer) => integer.tag,
+            Constant::Float(float) => float.tag,
+            Constant::Long(long) => long.tag,
+            Constant::Double(double) => double.tag,
+            Constant::NameAndType(name_and_type) => name_and_type.tag,
+            Constant::Utf8Info(utf8) => utf8.tag,
+            Constant::MethodHandle(method_handle) => method_handle.tag,
+            Constant::MethodType(method_type) => method_type.tag,
+            Constant::InvokeDynamic(invoke_dynamic) => invoke_dynamic.tag,
+            Constant::Dynamic(dynamic) => dynamic.tag,
+            Constant::Module(module) => module.tag,
+            Constant::Package(package) => package.tag,
+            Constant::Unusable => return None,
+        };
+        Some(tag)
+    }
+
+    /// The JVMS name of the entry's kind, e.g. `CONSTANT_Methodref`.
+    pub fn kind(&self) -> &'static str {
+        match self.tag() {
+            Some(CONSTANT_POOL_CLASS) => "CONSTANT_Class",
+            Some(CONSTANT_POOL_FIELDREF) => "CONSTANT_Fieldref",
+            Some(CONSTANT_POOL_METHODREF) => "CONSTANT_Methodref",
+            Some(CONSTANT_POOL_INTERFACEMETHODREF) => "CONSTANT_InterfaceMethodref",
+            Some(CONSTANT_POOL_STRING) => "CONSTANT_String",
+            Some(CONSTANT_POOL_INTEGER) => "CONSTANT_Integer",
+            Some(CONSTANT_POOL_FLOAT) => "CONSTANT_Float",
+            Some(CONSTANT_POOL_LONG) => "CONSTANT_Long",
+            Some(CONSTANT_POOL_DOUBLE) => "CONSTANT_Double",
+            Some(CONSTANT_POOL_NAME_AND_TYPE) => "CONSTANT_NameAndType",
+            Some(CONSTANT_POOL_UTF8) => "CONSTANT_Utf8",
+            Some(CONSTANT_POOL_METHOD_HANDLE) => "CONSTANT_MethodHandle",
+            Some(CONSTANT_POOL_METHOD_TYPE) => "CONSTANT_MethodType",
+            Some(CONSTANT_POOL_DYNAMIC) => "CONSTANT_Dynamic",
+            Some(CONSTANT_POOL_INVOKE_DYNAMIC) => "CONSTANT_InvokeDynamic",
+            Some(CONSTANT_POOL_MODULE) => "CONSTANT_Module",
+            Some(CONSTANT_POOL_PACKAGE) => "CONSTANT_Package",
+            _ => "the unusable slot after a CONSTANT_Long or CONSTANT_Double",
+        }
+    }
+
+    pub fn takes_two_slots(&self) -> bool {
+        matches!(self, Constant::Long(_) | Constant::Double(_))
+    }
+}
+
+impl fmt::Display for Constant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Constant::Class(class) => write!(f, "Class #{}", class.name_index),
+            Constant::FieldRef(field_ref) => write!(
+                f,
+                "Fieldref #{}.#{}",
+                field_ref.class_index, field_ref.name_and_type_index
+            ),
+            Constant::MethodRef(method_ref) => write!(
+                f,
+                "Methodref #{}.#{}",
+                method_ref.class_index, method_ref.name_and_type_index
+            ),
+            Constant::InterfaceMethodRef(method_ref) => write!(
+                f,
+                "InterfaceMethodref #{}.#{}",
+                method_ref.class_index, method_ref.name_and_type_index
+            ),
+            Constant::String(string) => write!(f, "String #{}", string.string_index),
+            Constant::Integer(integer) => write!(f, "Integer {}", integer.value()),
+            Constant::Float(float) => write!(f, "Float {}", float.value()),
+            Constant::Long(long) => write!(f, "Long {}", long.value()),
+            Constant::Double(double) => write!(f, "Double {}", double.value()),
+            Constant::NameAndType(name_and_type) => write!(
+                f,
+                "NameAndType #{}:#{}",
+                name_and_type.name_index, name_and_type.descriptor_index
+            ),
+            Constant::Utf8Info(utf8) => write!(f, "Utf8 {:?}", utf8.utf_str),
+            Constant::MethodHandle(method_handle) => write!(
+                f,
+                "MethodHandle {}:#{}",
+                method_handle.reference_kind, method_handle.reference_index
+            ),
+            Constant::MethodType(method_type) => {
+                write!(f, "MethodType #{}", method_type.descriptor_index)
+            }
+            Constant::InvokeDynamic(invoke_dynamic) => write!(
+                f,
+                "InvokeDynamic #{}:#{}",
+                invoke_dynamic.bootstrap_method_attr_index, invoke_dynamic.name_and_type_index
+            ),
+            Constant::Dynamic(dynamic) => write!(
+                f,
+                "Dynamic #{}:#{}",
+                dynamic.bootstrap_method_attr_index, dynamic.name_and_type_index
+            ),
+            Constant::Module(module) => write!(f, "Module #{}", module.name_index),
+            Constant::Package(package) => write!(f, "Package #{}", package.name_index),
+            Constant::Unusable => write!(f, "(unusable)"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classfile::{testing::ClassBuilder, ClassFile};
+
+    const CLASS_FILE_CHECK: &[u8] = include_bytes!("../../ClassFileCheck.class");
+
+    #[test]
+    fn resolves_the_constants_javac_writes() {
+        let class_file = ClassFile::from_bytes(CLASS_FILE_CHECK).unwrap();
+        let constant_pool = &class_file.constant_pool;
+        assert_eq!(constant_pool.len(), 101);
+        assert_eq!(
+            constant_pool.member_ref(17),
+            Ok(MemberReference {
+                kind: MemberRefKind::InterfaceMethod,
+                owner: "java/util/function/Function",
+                name: "apply",
+                descriptor: "(Ljava/lang/Object;)Ljava/lang/Object;",
+            })
+        );
+        assert_eq!(
+            constant_pool
+                .member_ref(11)
+                .map(|field| (field.kind, field.owner)),
+            Ok((MemberRefKind::Field, "java/lang/System"))
+        );
+        // serialVersionUID = 1L, whose second slot cannot be used.
+        assert_eq!(constant_pool.long(60), Ok(1));
+        assert_eq!(
+            constant_pool.entry(61).unwrap_err(),
+            ConstantPoolError::InvalidIndex(61)
+        );
+        // String concatenation recipes mark each argument with \u0001.
+        assert_eq!(constant_pool.string(93), Ok("MESSAGE: \u{1}"));
+        assert!(matches!(
+            constant_pool.entry(7),
+            Ok(Constant::InvokeDynamic(_))
+        ));
+        assert_eq!(
+            constant_pool
+                .iter()
+                .filter(|(_, constant)| matches!(constant, Constant::MethodHandle(_)))
+                .count(),
+            3
+        );
+    }
+
+    #[test]
+    fn long_and_double_take_two_slots() {
+        let mut builder = ClassBuilder::new("Sample");
+        let long = builder.long(-2);
+        let double = builder.double(0.5);
+        let after = builder.integer(7);
+        assert_eq!((double, after), (long + 2, long + 4));
+        let class_file = ClassFile::from_bytes(&builder.build()).unwrap();
+        let pool = &class_file.constant_pool;
+
+        assert_eq!(pool.len(), after as usize + 1);
+        assert_eq!(pool.long(long), Ok(-2));
+        assert_eq!(pool.double(double), Ok(0.5));
+        assert_eq!(pool.integer(after), Ok(7));
+        assert!(matches!(
+            pool.entries()[long as usize + 1],
+            Some(Constant::Unusable)
+        ));
+        assert!(matches!(
+            pool.entries()[double as usize + 1],
+            Some(Constant::Unusable)
+        ));
+        let indexes: Vec<u16> = pool.iter().map(|(index, _)| index).collect();
+        assert!(!indexes.contains(&(long + 1)) && !indexes.contains(&0));
+        assert!(indexes.contains(&after));
+    }
+
+    #[test]
+    fn unusable_and_out_of_range_indexes_are_invalid() {
+        let mut builder = ClassBuilder::new("Sample");
+        let long = builder.long(1);
+        let class_file = ClassFile::from_bytes(&builder.build()).unwrap();
+        let pool = &class_file.constant_pool;
+
+        for index in [0, long + 1, long + 2, u16::MAX] {
+            assert_eq!(
+                pool.entry(index).unwrap_err(),
+                ConstantPoolError::InvalidIndex(index)
+            );
+            assert_eq!(
+                pool.utf8(index).unwrap_err(),
+                ConstantPoolError::InvalidIndex(index)
+            );
+        }
+    }
+
+    #[test]
+    fn long_in_the_last_slot_is_rejected() {
+        let mut builder = ClassBuilder::new("Sample");
+        builder.long(1);
+        let mut bytes = builder.build();
+        // Claim one slot fewer, so the long's second slot falls off the end.
+        let count = u16::from_be_bytes([bytes[8], bytes[9]]) - 1;
+        bytes[8..10].copy_from_slice(&count.to_be_bytes());
+        let err = ClassFile::from_bytes(&bytes).unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::InvalidConstantIndex(index) if index == count));
+        assert_eq!(err.offset, builder.constant_pool_end() - 9);
+    }
+
+    #[test]
+    fn accessors_check_the_kind() {
+        let mut builder = ClassBuilder::new("Sample");
+        let integer = builder.integer(3);
+        let method_ref = builder.method_ref("java/lang/Object", "hashCode", "()I");
+        let class_file = ClassFile::from_bytes(&builder.build()).unwrap();
+        let pool = &class_file.constant_pool;
+        let this_class = class_file.this_class;
+
+        assert_eq!(
+            pool.utf8(integer),
+            Err(ConstantPoolError::UnexpectedKind {
+                index: integer,
+                expected: "CONSTANT_Utf8",
+                found: "CONSTANT_Integer",
+            })
+        );
+        assert_eq!(
+            pool.class_name(integer).unwrap_err().to_string(),
+            format!("constant pool entry {integer} is a CONSTANT_Integer, expected CONSTANT_Class")
+        );
+        assert!(matches!(
+            pool.member_ref(this_class),
+            Err(ConstantPoolError::UnexpectedKind {
+                found: "CONSTANT_Class",
+                ..
+            })
+        ));
+        assert!(matches!(
+            pool.long(integer),
+            Err(ConstantPoolError::UnexpectedKind {
+                expected: "CONSTANT_Long",
+                ..
+            })
+        ));
+
+        let member = pool.member_ref(method_ref).unwrap();
+        assert_eq!(member.kind, MemberRefKind::Method);
+        assert_eq!(
+            (member.owner, member.name, member.descriptor),
+            ("java/lang/Object", "hashCode", "()I")
+        );
+        assert_eq!(pool.class_name(this_class), Ok("Sample"));
+    }
+
+    #[test]
+    fn references_to_the_wrong_kind_fail_on_resolution() {
+        let mut builder = ClassBuilder::new("Sample");
+        let integer = builder.integer(3);
+        // A Class whose name_index points at the Integer.
+        let class = builder.constant(&[CONSTANT_POOL_CLASS, 0, integer as u8], 1);
+        let class_file = ClassFile::from_bytes(&builder.build()).unwrap();
+        assert!(matches!(
+            class_file.constant_pool.class_name(class),
+            Err(ConstantPoolError::UnexpectedKind { index, expected: "CONSTANT_Utf8", .. }) if index == integer
+        ));
+    }
+
+    #[test]
+    fn modern_constants_are_read() {
+        let mut builder = ClassBuilder::new("Sample");
+        let name = builder.utf8("java.base");
+        let module = builder.constant(&[CONSTANT_POOL_MODULE, 0, name as u8], 1);
+        let package = builder.constant(&[CONSTANT_POOL_PACKAGE, 0, name as u8], 1);
+        let dynamic = builder.constant(&[CONSTANT_POOL_DYNAMIC, 0, 0, 0, name as u8], 1);
+        let class_file = ClassFile::from_bytes(&builder.build()).unwrap();
+        let pool = &class_file.constant_pool;
+
+        let shown = |index: u16| pool.entry(index).unwrap().to_string();
+        assert_eq!(shown(module), format!("Module #{name}"));
+        assert_eq!(shown(package), format!("Package #{name}"));
+        assert_eq!(shown(dynamic), format!("Dynamic #0:#{name}"));
+    }
+
+    #[test]
+    fn modern_constants_need_a_recent_version() {
+        let mut builder = ClassBuilder::new("Sample");
+        builder.major_version = 50;
+        let name = builder.utf8("java.base");
+        let module = builder.constant(&[CONSTANT_POOL_MODULE, 0, name as u8], 1);
+        let err = ClassFile::from_bytes(&builder.build()).unwrap_err();
+        assert!(matches!(
+            err.kind,
+            ParseErrorKind::UnsupportedInVersion { since: 53, .. }
+        ));
+        assert_eq!(err.path, format!("constant_pool[{module}]"));
+    }
 }
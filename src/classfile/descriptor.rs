@@ -0,0 +1,603 @@
+//! Field and method descriptors (JVMS §4.3), and the conversions between the internal
+//! form of class names used in class files (`java/util/Map$Entry`) and binary names as
+//! returned by `Class.getName()` (`java.util.Map$Entry`). Canonical names as written in
+//! Java source (`java.util.Map.Entry`) can be produced but not parsed: a dot in one may
+//! separate packages or nested classes, and only the classes themselves can tell which.
+
+use std::fmt;
+
+/// Arrays may have at most 255 dimensions (JVMS §4.3.2).
+pub const MAX_ARRAY_DIMENSIONS: usize = 255;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum BaseType {
+    Byte,
+    Char,
+    Double,
+    Float,
+    Int,
+    Long,
+    Short,
+    Boolean,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum FieldType {
+    Base(BaseType),
+    /// A class or interface, by internal name.
+    Object(String),
+    /// An array of the component type, which may itself be an array.
+    Array(Box<FieldType>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ReturnType {
+    Void,
+    Value(FieldType),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MethodDescriptor {
+    pub parameters: Vec<FieldType>,
+    pub return_type: ReturnType,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DescriptorError {
+    /// Byte offset in the descriptor where the problem was found.
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for DescriptorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid descriptor at byte {}: {}",
+            self.position, self.message
+        )
+    }
+}
+
+impl std::error::Error for DescriptorError {}
+
+impl BaseType {
+    pub fn from_descriptor(descriptor: u8) -> Option<BaseType> {
+        let base_type = match descriptor {
+            b'B' => BaseType::Byte,
+            b'C' => BaseType::Char,
+            b'D' => BaseType::Double,
+            b'F' => BaseType::Float,
+            b'I' => BaseType::Int,
+            b'J' => BaseType::Long,
+            b'S' => BaseType::Short,
+            b'Z' => BaseType::Boolean,
+            _ => return None,
+        };
+        Some(base_type)
+    }
+
+    pub fn from_keyword(keyword: &str) -> Option<BaseType> {
+        let base_type = match keyword {
+            "byte" => BaseType::Byte,
+            "char" => BaseType::Char,
+            "double" => BaseType::Double,
+            "float" => BaseType::Float,
+            "int" => BaseType::Int,
+            "long" => BaseType::Long,
+            "short" => BaseType::Short,
+            "boolean" => BaseType::Boolean,
+            _ => return None,
+        };
+        Some(base_type)
+    }
+
+    pub fn descriptor(&self) -> char {
+        match self {
+            BaseType::Byte => 'B',
+            BaseType::Char => 'C',
+            BaseType::Double => 'D',
+            BaseType::Float => 'F',
+            BaseType::Int => 'I',
+            BaseType::Long => 'J',
+            BaseType::Short => 'S',
+            BaseType::Boolean => 'Z',
+        }
+    }
+
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            BaseType::Byte => "byte",
+            BaseType::Char => "char",
+            BaseType::Double => "double",
+            BaseType::Float => "float",
+            BaseType::Int => "int",
+            BaseType::Long => "long",
+            BaseType::Short => "short",
+            BaseType::Boolean => "boolean",
+        }
+    }
+
+    /// Local variable and operand stack slots taken by a value of this type.
+    pub fn slots(&self) -> u16 {
+        match self {
+            BaseType::Long | BaseType::Double => 2,
+            _ => 1,
+        }
+    }
+}
+
+impl FieldType {
+    /// Parses a complete field descriptor such as `[Ljava/lang/String;`.
+    pub fn parse(descriptor: &str) -> Result<FieldType, DescriptorError> {
+        let (field_type, end) = parse_field_type(descriptor, 0)?;
+        expect_end(descriptor, end)?;
+        Ok(field_type)
+    }
+
+    /// Parses a binary name as returned by `Class.getName()`: `int`, `java.lang.String`,
+    /// or for arrays a descriptor with dots, `[Ljava.lang.String;`.
+    pub fn from_binary_name(name: &str) -> Result<FieldType, DescriptorError> {
+        if name.starts_with('[') {
+            return FieldType::parse(&binary_to_internal_name(name));
+        }
+        if let Some(base_type) = BaseType::from_keyword(name) {
+            return Ok(FieldType::Base(base_type));
+        }
+        let internal = binary_to_internal_name(name);
+        if !is_class_name(&internal) {
+            return Err(error(0, format!("{name:?} is not a class name")));
+        }
+        Ok(FieldType::Object(internal))
+    }
+
+    /// Parses a keyword or binary name followed by any number of `[]`, e.g. `int[][]` or
+    /// `java.util.Map$Entry[]`. This is not a canonical name: `java.util.Map.Entry` would
+    /// be read as a class `Entry` in package `java.util.Map`.
+    pub fn from_java_name(name: &str) -> Result<FieldType, DescriptorError> {
+        let mut element = name.trim();
+        let mut dimensions = 0;
+        while let Some(rest) = element.strip_suffix("[]") {
+            element = rest.trim_end();
+            dimensions += 1;
+        }
+        if dimensions > MAX_ARRAY_DIMENSIONS {
+            return Err(error(0, "more than 255 array dimensions".to_string()));
+        }
+        let mut field_type = match BaseType::from_keyword(element) {
+            Some(base_type) => FieldType::Base(base_type),
+            None => match FieldType::from_binary_name(element)? {
+                object @ FieldType::Object(_) => object,
+                _ => return Err(error(0, format!("{name:?} is not a Java type"))),
+            },
+        };
+        for _ in 0..dimensions {
+            field_type = FieldType::Array(Box::new(field_type));
+        }
+        Ok(field_type)
+    }
+
+    pub fn descriptor(&self) -> String {
+        let mut descriptor = String::new();
+        self.write_descriptor(&mut descriptor);
+        descriptor
+    }
+
+    fn write_descriptor(&self, descriptor: &mut String) {
+        match self {
+            FieldType::Base(base_type) => descriptor.push(base_type.descriptor()),
+            FieldType::Object(name) => {
+                descriptor.push('L');
+                descriptor.push_str(name);
+                descriptor.push(';');
+            }
+            FieldType::Array(component) => {
+                descriptor.push('[');
+                component.write_descriptor(descriptor);
+            }
+        }
+    }
+
+    /// The number of array dimensions, 0 if this is not an array.
+    pub fn dimensions(&self) -> usize {
+        match self {
+            FieldType::Array(component) => 1 + component.dimensions(),
+            _ => 0,
+        }
+    }
+
+    /// The type left once every array dimension is stripped.
+    pub fn element_type(&self) -> &FieldType {
+        match self {
+            FieldType::Array(component) => component.element_type(),
+            element => element,
+        }
+    }
+
+    pub fn is_array(&self) -> bool {
+        matches!(self, FieldType::Array(_))
+    }
+
+    pub fn is_reference(&self) -> bool {
+        !matches!(self, FieldType::Base(_))
+    }
+
+    /// Local variable and operand stack slots taken by a value of this type: 2 for
+    /// `long` and `double`, 1 for everything else.
+    pub fn slots(&self) -> u16 {
+        match self {
+            FieldType::Base(base_type) => base_type.slots(),
+            _ => 1,
+        }
+    }
+
+    /// The name `Class.getName()` returns for this type.
+    pub fn binary_name(&self) -> String {
+        match self {
+            FieldType::Base(base_type) => base_type.keyword().to_string(),
+            FieldType::Object(name) => internal_to_binary_name(name),
+            FieldType::Array(_) => internal_to_binary_name(&self.descriptor()),
+        }
+    }
+
+    /// The type as written in Java source with fully qualified names, e.g.
+    /// `java.util.Map.Entry[]`.
+    pub fn canonical_name(&self) -> String {
+        self.java_name(internal_to_canonical_name)
+    }
+
+    /// The type as written in Java source with simple names, e.g. `Map.Entry[]`.
+    pub fn simple_name(&self) -> String {
+        self.java_name(|name| {
+            let simple = name.rsplit_once('/').map_or(name, |(_, simple)| simple);
+            internal_to_canonical_name(simple)
+        })
+    }
+
+    fn java_name(&self, class_name: impl Fn(&str) -> String) -> String {
+        let mut java_name = match self.element_type() {
+            FieldType::Base(base_type) => base_type.keyword().to_string(),
+            FieldType::Object(name) => class_name(name),
+            FieldType::Array(_) => unreachable!("the element type is never an array"),
+        };
+        for _ in 0..self.dimensions() {
+            java_name.push_str("[]");
+        }
+        java_name
+    }
+}
+
+/// Writes the canonical name, e.g. `java.lang.String[]`.
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.canonical_name())
+    }
+}
+
+impl ReturnType {
+    pub fn descriptor(&self) -> String {
+        match self {
+            ReturnType::Void => "V".to_string(),
+            ReturnType::Value(field_type) => field_type.descriptor(),
+        }
+    }
+
+    pub fn slots(&self) -> u16 {
+        match self {
+            ReturnType::Void => 0,
+            ReturnType::Value(field_type) => field_type.slots(),
+        }
+    }
+
+    pub fn canonical_name(&self) -> String {
+        match self {
+            ReturnType::Void => "void".to_string(),
+            ReturnType::Value(field_type) => field_type.canonical_name(),
+        }
+    }
+
+    pub fn simple_name(&self) -> String {
+        match self {
+            ReturnType::Void => "void".to_string(),
+            ReturnType::Value(field_type) => field_type.simple_name(),
+        }
+    }
+}
+
+impl fmt::Display for ReturnType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.canonical_name())
+    }
+}
+
+impl MethodDescriptor {
+    /// Parses a complete method descriptor such as `([Ljava/lang/String;I)V`.
+    pub fn parse(descriptor: &str) -> Result<MethodDescriptor, DescriptorError> {
+        if !descriptor.starts_with('(') {
+            return Err(error(0, "expected '('".to_string()));
+        }
+        let mut position = 1;
+        let mut parameters: Vec<FieldType> = vec![];
+        while descriptor.as_bytes().get(position) != Some(&b')') {
+            let (parameter, end) = parse_field_type(descriptor, position)?;
+            parameters.push(parameter);
+            position = end;
+        }
+        position += 1;
+        let return_type = if descriptor.as_bytes().get(position) == Some(&b'V') {
+            position += 1;
+            ReturnType::Void
+        } else {
+            let (return_type, end) = parse_field_type(descriptor, position)?;
+            position = end;
+            ReturnType::Value(return_type)
+        };
+        expect_end(descriptor, position)?;
+        Ok(MethodDescriptor {
+            parameters,
+            return_type,
+        })
+    }
+
+    pub fn descriptor(&self) -> String {
+        let mut descriptor = String::from("(");
+        for parameter in &self.parameters {
+            parameter.write_descriptor(&mut descriptor);
+        }
+        descriptor.push(')');
+        descriptor.push_str(&self.return_type.descriptor());
+        descriptor
+    }
+
+    /// Local variable slots taken by the parameters, counting `long` and `double` as two.
+    /// An instance method receives `this` in one more slot, and the JVM limits the total
+    /// to 255 (JVMS §4.3.3).
+    pub fn parameter_slots(&self) -> u16 {
+        self.parameters.iter().map(FieldType::slots).sum()
+    }
+
+    /// Renders the method as declared in Java source with simple names, e.g.
+    /// `void main(String[], int)`.
+    pub fn to_java(&self, name: &str) -> String {
+        self.render(name, self.return_type.simple_name(), FieldType::simple_name)
+    }
+
+    /// Like [`MethodDescriptor::to_java`] with fully qualified names, e.g.
+    /// `void main(java.lang.String[], int)`.
+    pub fn to_java_qualified(&self, name: &str) -> String {
+        self.render(
+            name,
+            self.return_type.canonical_name(),
+            FieldType::canonical_name,
+        )
+    }
+
+    fn render(
+        &self,
+        name: &str,
+        return_type: String,
+        parameter_name: impl Fn(&FieldType) -> String,
+    ) -> String {
+        let parameters: Vec<String> = self.parameters.iter().map(parameter_name).collect();
+        format!("{return_type} {name}({})", parameters.join(", "))
+    }
+}
+
+/// Parses the field type starting at byte `start` and returns it with the offset just
+/// past it.
+fn parse_field_type(descriptor: &str, start: usize) -> Result<(FieldType, usize), DescriptorError> {
+    let bytes = descriptor.as_bytes();
+    let dimensions = bytes[start.min(bytes.len())..]
+        .iter()
+        .take_while(|&&byte| byte == b'[')
+        .count();
+    if dimensions > MAX_ARRAY_DIMENSIONS {
+        return Err(error(start, "more than 255 array dimensions".to_string()));
+    }
+    let position = start + dimensions;
+    let (mut field_type, end) = match bytes.get(position) {
+        None => return Err(error(position, "unexpected end of descriptor".to_string())),
+        Some(b'L') => {
+            let name_start = position + 1;
+            let name_end = descriptor[name_start..]
+                .find(';')
+                .map(|length| name_start + length)
+                .ok_or_else(|| error(position, "class name without ';'".to_string()))?;
+            let name = &descriptor[name_start..name_end];
+            if !is_class_name(name) {
+                return Err(error(name_start, format!("{name:?} is not a class name")));
+            }
+            (FieldType::Object(name.to_string()), name_end + 1)
+        }
+        Some(&byte) => match BaseType::from_descriptor(byte) {
+            Some(base_type) => (FieldType::Base(base_type), position + 1),
+            None => {
+                let found = descriptor[position..]
+                    .chars()
+                    .next()
+                    .unwrap_or(byte as char);
+                return Err(error(position, format!("unexpected {found:?}")));
+            }
+        },
+    };
+    for _ in 0..dimensions {
+        field_type = FieldType::Array(Box::new(field_type));
+    }
+    Ok((field_type, end))
+}
+
+fn expect_end(descriptor: &str, position: usize) -> Result<(), DescriptorError> {
+    if position == descriptor.len() {
+        Ok(())
+    } else {
+        Err(error(position, "trailing characters".to_string()))
+    }
+}
+
+fn error(position: usize, message: String) -> DescriptorError {
+    DescriptorError { position, message }
+}
+
+/// An unqualified name (JVMS §4.2.2), as used for fields, methods and local variables.
+pub fn is_unqualified_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['.', ';', '[', '/'])
+}
+
+/// A class or interface name in internal form (JVMS §4.2.1), e.g. `java/lang/Object`.
+pub fn is_class_name(name: &str) -> bool {
+    name.split('/').all(is_unqualified_name)
+}
+
+/// `java/util/Map$Entry` to `java.util.Map$Entry`.
+pub fn internal_to_binary_name(name: &str) -> String {
+    name.replace('/', ".")
+}
+
+/// `java.util.Map$Entry` to `java/util/Map$Entry`.
+pub fn binary_to_internal_name(name: &str) -> String {
+    name.replace('.', "/")
+}
+
+/// `java/util/Map$Entry` to `java.util.Map.Entry`. This reads every `$` as a nesting
+/// separator; a class whose own name contains `$` only shows up in the InnerClasses
+/// attribute, and anonymous and local classes have no canonical name at all.
+pub fn internal_to_canonical_name(name: &str) -> String {
+    name.replace(['/', '$'], ".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(name: &str) -> FieldType {
+        FieldType::Object(name.to_string())
+    }
+
+    fn array(component: FieldType) -> FieldType {
+        FieldType::Array(Box::new(component))
+    }
+
+    #[test]
+    fn field_descriptors_round_trip() {
+        let cases = [
+            ("I", FieldType::Base(BaseType::Int)),
+            ("Ljava/lang/String;", object("java/lang/String")),
+            ("[[J", array(array(FieldType::Base(BaseType::Long)))),
+            (
+                "[Ljava/util/Map$Entry;",
+                array(object("java/util/Map$Entry")),
+            ),
+        ];
+        for (descriptor, field_type) in cases {
+            assert_eq!(FieldType::parse(descriptor).unwrap(), field_type);
+            assert_eq!(field_type.descriptor(), descriptor);
+        }
+    }
+
+    #[test]
+    fn method_descriptors_round_trip() {
+        let descriptor = "(IJ[Ljava/lang/String;D)Ljava/lang/Object;";
+        let method = MethodDescriptor::parse(descriptor).unwrap();
+        assert_eq!(method.parameters.len(), 4);
+        assert_eq!(
+            method.return_type,
+            ReturnType::Value(object("java/lang/Object"))
+        );
+        assert_eq!(method.descriptor(), descriptor);
+        assert_eq!(MethodDescriptor::parse("()V").unwrap().descriptor(), "()V");
+    }
+
+    #[test]
+    fn long_and_double_take_two_slots() {
+        let method = MethodDescriptor::parse("(IJ[JDLjava/lang/Long;)J").unwrap();
+        assert_eq!(method.parameter_slots(), 1 + 2 + 1 + 2 + 1);
+        assert_eq!(method.return_type.slots(), 2);
+        assert_eq!(ReturnType::Void.slots(), 0);
+        assert_eq!(FieldType::parse("D").unwrap().slots(), 2);
+        assert_eq!(FieldType::parse("[D").unwrap().slots(), 1);
+    }
+
+    #[test]
+    fn arrays_have_at_most_255_dimensions() {
+        let descriptor = format!("{}I", "[".repeat(255));
+        let field_type = FieldType::parse(&descriptor).unwrap();
+        assert_eq!(field_type.dimensions(), 255);
+        assert_eq!(field_type.element_type(), &FieldType::Base(BaseType::Int));
+
+        let err = FieldType::parse(&format!("[{descriptor}")).unwrap_err();
+        assert_eq!(err.position, 0);
+        assert_eq!(err.message, "more than 255 array dimensions");
+        let err = MethodDescriptor::parse(&format!("(I[{descriptor})V")).unwrap_err();
+        assert_eq!(err.position, 2);
+
+        assert_eq!(
+            FieldType::from_java_name(&format!("int{}", "[]".repeat(255)))
+                .unwrap()
+                .dimensions(),
+            255
+        );
+        assert!(FieldType::from_java_name(&format!("int{}", "[]".repeat(256))).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_descriptors() {
+        let cases = [
+            ("", 0, "unexpected end of descriptor"),
+            ("V", 0, "unexpected 'V'"),
+            ("Ljava/lang/String", 0, "class name without ';'"),
+            (
+                "Ljava.lang.String;",
+                1,
+                "\"java.lang.String\" is not a class name",
+            ),
+            ("L;", 1, "\"\" is not a class name"),
+            ("II", 1, "trailing characters"),
+        ];
+        for (descriptor, position, message) in cases {
+            let err = FieldType::parse(descriptor).unwrap_err();
+            assert_eq!((err.position, err.message.as_str()), (position, message));
+        }
+        assert_eq!(MethodDescriptor::parse("I)V").unwrap_err().position, 0);
+        assert_eq!(MethodDescriptor::parse("(I").unwrap_err().position, 2);
+        assert_eq!(MethodDescriptor::parse("(I)VV").unwrap_err().position, 4);
+    }
+
+    #[test]
+    fn names_convert_between_forms() {
+        let field_type = FieldType::parse("[[Ljava/util/Map$Entry;").unwrap();
+        assert_eq!(field_type.binary_name(), "[[Ljava.util.Map$Entry;");
+        assert_eq!(field_type.canonical_name(), "java.util.Map.Entry[][]");
+        assert_eq!(field_type.simple_name(), "Map.Entry[][]");
+        assert_eq!(field_type.to_string(), "java.util.Map.Entry[][]");
+        assert_eq!(
+            FieldType::from_binary_name(&field_type.binary_name()).unwrap(),
+            field_type
+        );
+        assert_eq!(
+            FieldType::from_java_name("java.util.Map$Entry[][]").unwrap(),
+            field_type
+        );
+        assert_eq!(
+            FieldType::from_binary_name("long").unwrap(),
+            FieldType::Base(BaseType::Long)
+        );
+        assert!(FieldType::from_binary_name("java..lang").is_err());
+        assert!(FieldType::from_java_name("[I").is_err());
+    }
+
+    #[test]
+    fn renders_methods_as_java() {
+        let method = MethodDescriptor::parse("([Ljava/lang/String;I)V").unwrap();
+        assert_eq!(method.to_java("main"), "void main(String[], int)");
+        assert_eq!(
+            method.to_java_qualified("main"),
+            "void main(java.lang.String[], int)"
+        );
+        let method = MethodDescriptor::parse("(Ljava/util/Map$Entry;)[[D").unwrap();
+        assert_eq!(method.to_java("get"), "double[][] get(Map.Entry)");
+    }
+}
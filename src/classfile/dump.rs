@@ -0,0 +1,232 @@
+use std::fmt::Write;
+
+use crate::classfile::{
+    options::ParseOptions,
+    reader::{ClassReader, Trace, TraceSpan},
+    ClassFile,
+};
+
+const BYTES_PER_ROW: usize = 16;
+
+/// Produces an xxd-style dump of `bytes` where every row is labelled with the class file
+/// structure it belongs to, e.g. `cp[3] Utf8 "java/lang/Object"` or
+/// `methods[1].attributes[0].Code.code[0..16]`. Bytes left over after the class file
+/// ends, or after a parse error, are marked as unconsumed. The class file is parsed
+/// leniently, so an attribute that cannot be parsed is flagged and shown as raw bytes
+/// rather than ending the dump.
+pub fn hex_dump(bytes: &[u8]) -> String {
+    let options = ParseOptions {
+        lenient: true,
+        ..ParseOptions::default()
+    };
+    let mut reader = ClassReader::traced(bytes, options);
+    let result = ClassFile::read(&mut reader);
+    let consumed = reader.offset();
+    let trace = reader.take_trace().unwrap_or_default();
+
+    let mut output = String::new();
+    let mut notes = trace.notes.iter().peekable();
+    for group in group_spans(&trace.spans) {
+        while let Some(note) = notes.next_if(|note| note.offset <= group.start) {
+            let _ = writeln!(output, "!! {}: {}", note.path, note.message);
+        }
+        write_group(&mut output, bytes, &trace, &group);
+    }
+    for note in notes {
+        let _ = writeln!(output, "!! {}: {}", note.path, note.message);
+    }
+
+    if let Err(err) = &result {
+        let _ = writeln!(output, "!! parse error: {err}");
+    }
+    if consumed < bytes.len() {
+        let label = if result.is_ok() {
+            "trailing bytes after end of class file"
+        } else {
+            "unconsumed bytes"
+        };
+        let _ = writeln!(output, "!! {} {label}", bytes.len() - consumed);
+        for row_start in (consumed..bytes.len()).step_by(BYTES_PER_ROW) {
+            let row_end = (row_start + BYTES_PER_ROW).min(bytes.len());
+            write_row(&mut output, bytes, row_start, row_end, label);
+        }
+    }
+    output
+}
+
+/// Consecutive spans read at the same path, e.g. the tag, length and bytes of one
+/// constant pool entry.
+struct SpanGroup<'t> {
+    start: usize,
+    end: usize,
+    path: &'t str,
+    is_bytes: bool,
+}
+
+fn group_spans(spans: &[TraceSpan]) -> Vec<SpanGroup<'_>> {
+    let mut groups: Vec<SpanGroup> = vec![];
+    for span in spans {
+        match groups.last_mut() {
+            Some(group) if group.path == span.path && group.end == span.start => {
+                group.end = span.end;
+                group.is_bytes = false;
+            }
+            _ => groups.push(SpanGroup {
+                start: span.start,
+                end: span.end,
+                path: &span.path,
+                is_bytes: span.is_bytes,
+            }),
+        }
+    }
+    groups
+}
+
+fn write_group(output: &mut String, bytes: &[u8], trace: &Trace, group: &SpanGroup) {
+    let label = match trace.descriptions.get(group.path) {
+        Some(description) => format!("{} {description}", display_path(group.path)),
+        None => display_path(group.path),
+    };
+    for row_start in (group.start..group.end).step_by(BYTES_PER_ROW) {
+        let row_end = (row_start + BYTES_PER_ROW).min(group.end);
+        let row_label = if group.is_bytes {
+            format!(
+                "{label}[{}..{}]",
+                row_start - group.start,
+                row_end - group.start
+            )
+        } else if row_start == group.start {
+            label.clone()
+        } else {
+            format!("{label} (cont.)")
+        };
+        write_row(output, bytes, row_start, row_end, &row_label);
+    }
+}
+
+fn write_row(output: &mut String, bytes: &[u8], start: usize, end: usize, label: &str) {
+    let mut hex = String::new();
+    let mut ascii = String::new();
+    for (column, byte) in bytes[start..end].iter().enumerate() {
+        if column == BYTES_PER_ROW / 2 {
+            hex.push(' ');
+        }
+        let _ = write!(hex, "{byte:02x} ");
+        ascii.push(if byte.is_ascii_graphic() || *byte == b' ' {
+            *byte as char
+        } else {
+            '.'
+        });
+    }
+    let _ = writeln!(output, "0x{start:04X}  {hex:<49} |{ascii:<16}|  {label}");
+}
+
+fn display_path(path: &str) -> String {
+    match path.strip_prefix("constant_pool[") {
+        Some(rest) => format!("cp[{rest}"),
+        None => path.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLASS_FILE_CHECK: &[u8] = include_bytes!("../../ClassFileCheck.class");
+
+    /// The rows labelled `label`, as (offset, hex) pairs.
+    fn rows<'d>(dump: &'d str, label: &str) -> Vec<(&'d str, &'d str)> {
+        dump.lines()
+            .filter_map(|line| {
+                let (row, row_label) = line.rsplit_once("|  ")?;
+                let (offset, hex) = row.split_once("  ")?;
+                (row_label == label).then(|| (offset, hex.split('|').next().unwrap().trim_end()))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn labels_rows_with_the_structure_they_belong_to() {
+        let dump = hex_dump(CLASS_FILE_CHECK);
+        assert_eq!(rows(&dump, "magic"), [("0x0000", "ca fe ba be")]);
+        assert_eq!(
+            rows(&dump, "cp[1] Methodref #2.#3"),
+            [("0x000A", "0a 00 02 00 03")]
+        );
+        assert_eq!(
+            rows(&dump, "cp[4] Utf8 \"BaseCheckClass\" (cont.)"),
+            [("0x0027", "73")]
+        );
+        assert_eq!(
+            rows(&dump, "methods[0].attributes[0] Code attribute_length=63"),
+            [("0x0629", "00 41 00 00 00 3f")]
+        );
+    }
+
+    #[test]
+    fn byte_arrays_are_labelled_with_the_range_each_row_covers() {
+        let dump = hex_dump(CLASS_FILE_CHECK);
+        assert_eq!(
+            rows(&dump, "methods[0].attributes[0].Code.code[0..16]"),
+            [("0x0637", "2a b7 00 01 ba 00 07 00  00 4d b2 00 0b 2c 2b b9")]
+        );
+        assert_eq!(
+            rows(&dump, "methods[0].attributes[0].Code.code[16..27]"),
+            [("0x0647", "00 11 02 00 c0 00 16 b6  00 18 b1")]
+        );
+    }
+
+    #[test]
+    fn an_attribute_longer_than_its_contents_is_noted_and_dumped_raw() {
+        // Pad SourceFile, whose attribute_length is at 0x075C, with a byte its index
+        // does not account for.
+        let mut bytes = CLASS_FILE_CHECK.to_vec();
+        bytes[0x075F] = 3;
+        bytes.insert(0x0762, 0xFF);
+        let dump = hex_dump(&bytes);
+        assert_eq!(
+            rows(&dump, "attributes[0].SourceFile[0..3]"),
+            [("0x0760", "00 47 ff")]
+        );
+        let lines: Vec<&str> = dump.lines().collect();
+        let note = lines
+            .iter()
+            .position(|line| {
+                *line
+                    == "!! attributes[0].SourceFile: \
+                        attribute_length is 3 but its contents take 2 bytes"
+            })
+            .expect("no note for the attribute");
+        assert!(lines[note - 1].ends_with("attributes[0].SourceFile[0..3]"));
+        assert!(lines[note + 1].ends_with("attributes[1] BootstrapMethods attribute_length=24"));
+        assert!(!dump.contains("parse error"));
+    }
+
+    #[test]
+    fn marks_the_bytes_after_the_end_of_the_class_file() {
+        let bytes = [CLASS_FILE_CHECK, &[0xDE, 0xAD]].concat();
+        let dump = hex_dump(&bytes);
+        assert!(dump.contains("\n!! 2 trailing bytes after end of class file\n"));
+        assert_eq!(
+            rows(&dump, "trailing bytes after end of class file"),
+            [("0x0790", "de ad")]
+        );
+        assert!(!dump.contains("parse error"));
+    }
+
+    #[test]
+    fn marks_what_a_truncated_class_file_leaves_unread() {
+        let dump = hex_dump(&CLASS_FILE_CHECK[..0x0640]);
+        assert!(dump.contains(
+            "\n!! parse error: unexpected end of class file at offset 0x062F \
+             (methods[0].attributes[0].Code)\n!! 17 unconsumed bytes\n"
+        ));
+        assert_eq!(
+            rows(&dump, "unconsumed bytes"),
+            [
+                ("0x062F", "00 03 00 03 00 00 00 1b  2a b7 00 01 ba 00 07 00"),
+                ("0x063F", "00"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use std::{error::Error, fmt, io};
+
+use crate::classfile::version::ClassVersion;
+
+#[derive(Debug)]
+pub enum ParseErrorKind {
+    Io(io::Error),
+    UnexpectedEof,
+    UnsupportedInVersion {
+        feature: String,
+        since: u16,
+        version: ClassVersion,
+    },
+    AttributeOverrun {
+        attribute_end: usize,
+    },
+    AttributeLengthMismatch {
+        declared: usize,
+        consumed: usize,
+    },
+    InvalidMagic(u32),
+    UnknownConstantTag(u8),
+    InvalidConstantIndex(u16),
+    ExpectedUtf8(u16),
+    ExpectedClass(u16),
+    InvalidUtf8,
+    UnknownElementValueTag(u8),
+    ElementValueTooDeep {
+        limit: usize,
+    },
+    UnknownTypeAnnotationTarget(u8),
+    CustomAttribute {
+        name: String,
+        message: String,
+    },
+    InvalidStackMapFrameType(u8),
+    InvalidVerificationTypeTag(u8),
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::Io(err) => write!(f, "I/O error: {err}"),
+            ParseErrorKind::UnexpectedEof => write!(f, "unexpected end of class file"),
+            ParseErrorKind::UnsupportedInVersion {
+                feature,
+                since,
+                version,
+            } => write!(
+                f,
+                "{feature} requires class file version {since} or later, but this class is version {version}"
+            ),
+            ParseErrorKind::AttributeOverrun { attribute_end } => write!(
+                f,
+                "attribute contents run past attribute_length (attribute ends at 0x{attribute_end:04X})"
+            ),
+            ParseErrorKind::AttributeLengthMismatch { declared, consumed } => write!(
+                f,
+                "attribute_length is {declared} but its contents take {consumed} bytes"
+            ),
+            ParseErrorKind::InvalidMagic(magic) => {
+                write!(f, "invalid magic number 0x{magic:08X}, expected 0xCAFEBABE")
+            }
+            ParseErrorKind::UnknownConstantTag(tag) => {
+                write!(f, "unknown constant pool tag {tag}")
+            }
+            ParseErrorKind::InvalidConstantIndex(index) => {
+                write!(f, "constant pool index {index} is out of range or unusable")
+            }
+            ParseErrorKind::ExpectedUtf8(index) => {
+                write!(f, "constant pool entry {index} is not a CONSTANT_Utf8")
+            }
+            ParseErrorKind::ExpectedClass(index) => {
+                write!(f, "constant pool entry {index} is not a CONSTANT_Class")
+            }
+            ParseErrorKind::InvalidUtf8 => {
+                write!(f, "invalid modified UTF-8 in CONSTANT_Utf8 entry")
+            }
+            ParseErrorKind::UnknownElementValueTag(tag) => {
+                write!(f, "unknown annotation element_value tag 0x{tag:02X}")
+            }
+            ParseErrorKind::ElementValueTooDeep { limit } => {
+                write!(f, "annotation element_value nested more than {limit} deep")
+            }
+            ParseErrorKind::UnknownTypeAnnotationTarget(target_type) => {
+                write!(f, "unknown type annotation target_type 0x{target_type:02X}")
+            }
+            ParseErrorKind::CustomAttribute { name, message } => {
+                write!(f, "custom parser for attribute {name} failed: {message}")
+            }
+            ParseErrorKind::InvalidStackMapFrameType(frame_type) => {
+                write!(f, "reserved stack map frame type {frame_type}")
+            }
+            ParseErrorKind::InvalidVerificationTypeTag(tag) => {
+                write!(f, "unknown verification_type_info tag {tag}")
+            }
+        }
+    }
+}
+
+/// An error raised while parsing a class file. `offset` is the byte offset into the
+/// class file at which the failing read started and `path` locates the structure being
+/// parsed, e.g. `methods[3].attributes[1].Code.exception_table[0]`.
+#[derive(Debug)]
+pub struct ClassParseError {
+    pub kind: ParseErrorKind,
+    pub offset: usize,
+    pub path: String,
+}
+
+impl fmt::Display for ClassParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{} at offset 0x{:04X}", self.kind, self.offset)
+        } else {
+            write!(
+                f,
+                "{} at offset 0x{:04X} ({})",
+                self.kind, self.offset, self.path
+            )
+        }
+    }
+}
+
+impl Error for ClassParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            ParseErrorKind::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_includes_offset_and_path() {
+        let err = ClassParseError {
+            kind: ParseErrorKind::UnknownConstantTag(99),
+            offset: 0x1F,
+            path: "constant_pool[3]".to_string(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "unknown constant pool tag 99 at offset 0x001F (constant_pool[3])"
+        );
+    }
+
+    #[test]
+    fn display_omits_an_empty_path() {
+        let err = ClassParseError {
+            kind: ParseErrorKind::InvalidMagic(0xDEADBEEF),
+            offset: 0,
+            path: String::new(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "invalid magic number 0xDEADBEEF, expected 0xCAFEBABE at offset 0x0000"
+        );
+    }
+
+    #[test]
+    fn only_io_errors_have_a_source() {
+        let io = ClassParseError {
+            kind: ParseErrorKind::Io(io::Error::other("disk on fire")),
+            offset: 0,
+            path: String::new(),
+        };
+        assert_eq!(io.source().unwrap().to_string(), "disk on fire");
+        let eof = ClassParseError {
+            kind: ParseErrorKind::UnexpectedEof,
+            offset: 0,
+            path: String::new(),
+        };
+        assert!(eof.source().is_none());
+    }
+}
@@ -1,20 +1,14 @@
-use byteorder::{BigEndian, ReadBytesExt};
-use std::{fs::File, io::BufReader};
+use std::io::Read;
 
-use crate::classfile::BYTE_LENGTH_UNAVAILABLE_ERROR;
+use crate::classfile::{error::ClassParseError, reader::ClassReader};
 
-pub fn generate_interfaces(interfaces_count: u16, reader: &mut BufReader<File>) -> Vec<u16> {
+pub fn generate_interfaces<R: Read>(
+    interfaces_count: u16,
+    reader: &mut ClassReader<R>,
+) -> Result<Vec<u16>, ClassParseError> {
     let mut interfaces: Vec<u16> = vec![];
-    for _ in 0..interfaces_count {
-        interfaces.push(
-            reader
-                .read_u16::<BigEndian>()
-                .expect(BYTE_LENGTH_UNAVAILABLE_ERROR),
-        );
+    for index in 0..interfaces_count {
+        interfaces.push(reader.scoped(format!("interfaces[{index}]"), |reader| reader.read_u16())?);
     }
-    println!(
-        "interface count: {interfaces_count}, interfaces: {:?}",
-        interfaces
-    );
-    interfaces
+    Ok(interfaces)
 }
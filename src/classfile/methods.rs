@@ -1,57 +1,43 @@
-use crate::classfile::{attributes::*, BYTE_LENGTH_UNAVAILABLE_ERROR, constant_pool::Constant};
-use byteorder::{BigEndian, ReadBytesExt};
-use std::{
-    collections::HashMap,
-    fs::File,
-    io::{BufRead, BufReader, Read},
-    str,
+use crate::classfile::{
+    access_flags::MethodAccessFlags, attributes::*, constant_pool::ConstantPool,
+    error::ClassParseError, reader::ClassReader,
 };
+use std::io::Read;
 
-pub fn generate_methods(
+pub fn generate_methods<R: Read>(
     methods_count: u16,
-    constant_pool: &Vec<Option<Constant>>,
-    reader: &mut BufReader<File>,
-) -> Vec<Method> {
-    // let methods_count: u16 = reader.read_u16::<BigEndian>().expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
+    constant_pool: &ConstantPool,
+    reader: &mut ClassReader<R>,
+) -> Result<Vec<Method>, ClassParseError> {
     let mut methods: Vec<Method> = vec![];
 
-    println!("methods count: {methods_count}");
-
-    for _ in 0..methods_count {
-        let access_flags: u16 = reader
-            .read_u16::<BigEndian>()
-            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-        let name_index: u16 = reader
-            .read_u16::<BigEndian>()
-            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-        let descriptor_index: u16 = reader
-            .read_u16::<BigEndian>()
-            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-        let attributes_count: u16 = reader
-            .read_u16::<BigEndian>()
-            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-
-        println!("method attributes count: {attributes_count}");
-        let attributes = generate_attributes(attributes_count, constant_pool, reader);
-
-        let method = Method {
-            access_flags,
-            name_index,
-            descriptor_index,
-            attributes_count,
-            attributes,
-        };
+    for index in 0..methods_count {
+        let method = reader.scoped(format!("methods[{index}]"), |reader| {
+            let access_flags = MethodAccessFlags::from_bits(reader.read_u16()?);
+            let name_index: u16 = reader.read_u16()?;
+            let descriptor_index: u16 = reader.read_u16()?;
+            let attributes_count: u16 = reader.read_u16()?;
+            let attributes = generate_attributes(attributes_count, constant_pool, reader)?;
+            Ok(Method {
+                access_flags,
+                name_index,
+                descriptor_index,
+                attributes_count,
+                attributes,
+            })
+        })?;
         methods.push(method);
     }
 
-    methods
+    Ok(methods)
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Method {
-    access_flags: u16,
-    name_index: u16,
-    descriptor_index: u16,
-    attributes_count: u16,
-    attributes: Vec<AttributeType>,
+    pub access_flags: MethodAccessFlags,
+    pub name_index: u16,
+    pub descriptor_index: u16,
+    pub attributes_count: u16,
+    pub attributes: Vec<AttributeType>,
 }
@@ -1,36 +1,63 @@
-use std::{fs::File, io::BufReader};
-
-use byteorder::{BigEndian, ReadBytesExt};
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+};
 
-use crate::{
-    classfile::{
-        attributes::generate_attributes, constant_pool::generate_constant_pool,
-        interfaces::generate_interfaces, methods::{generate_methods, Method},
-        constant_pool::Constant, 
-    },
-    generate_fields, FieldInfo,
+use crate::classfile::{
+    access_flags::ClassAccessFlags,
+    attributes::generate_attributes,
+    constant_pool::generate_constant_pool,
+    constant_pool::ConstantPool,
+    error::{ClassParseError, ParseErrorKind},
+    fields::{generate_fields, FieldInfo},
+    interfaces::generate_interfaces,
+    methods::{generate_methods, Method},
+    options::ParseOptions,
+    reader::ClassReader,
+    version::ClassVersion,
 };
 
 use self::attributes::AttributeType;
 
+pub mod access_flags;
 pub mod annotations;
+#[cfg(feature = "archives")]
+pub mod archive;
 pub mod attributes;
+pub mod borrowed;
+#[cfg(feature = "archives")]
+pub mod bulk;
 pub mod constant_pool;
+pub mod descriptor;
+pub mod dump;
+pub mod error;
 pub mod fields;
 pub mod interfaces;
 pub mod methods;
+pub mod mutf8;
+pub mod options;
+pub mod reader;
+pub mod scan;
+pub mod signature;
+pub mod smap;
+pub mod stack_map;
+#[cfg(test)]
+pub(crate) mod testing;
+pub mod validate;
+pub mod version;
+pub mod view;
 
-pub const BYTE_LENGTH_UNAVAILABLE_ERROR: &str =
-    "Could not allocate the required bytes from the class file";
+pub const CLASS_FILE_MAGIC: u32 = 0xCAFEBABE;
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ClassFile {
     pub magic: u32,
     pub minor_version: u16,
     pub major_version: u16,
     pub constant_pool_count: u16,
-    pub constant_pool: Vec<Option<Constant>>,
-    pub access_flags: u16,
+    pub constant_pool: ConstantPool,
+    pub access_flags: ClassAccessFlags,
     pub this_class: u16,
     pub super_class: u16,
     pub interfaces_count: u16,
@@ -44,83 +71,111 @@ pub struct ClassFile {
 }
 
 impl ClassFile {
-    pub fn new(class_file_path: &str) -> ClassFile {
-        let file = File::open(class_file_path).expect("Class file unavailable");
-        let mut reader = BufReader::new(file);
-
-        let magic = reader
-            .read_u32::<BigEndian>()
-            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-        let minor_version = reader
-            .read_u16::<BigEndian>()
-            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-        let major_version = reader
-            .read_u16::<BigEndian>()
-            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-        let constant_pool_count: usize = reader
-            .read_u16::<BigEndian>()
-            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR)
-            as usize;
-        println!(
-            "{:?}, {:?}, {:?}, {:?}",
-            magic, minor_version, major_version, constant_pool_count
-        );
+    pub fn parse(class_file_path: &str) -> Result<ClassFile, ClassParseError> {
+        let file = File::open(class_file_path).map_err(|err| ClassParseError {
+            kind: ParseErrorKind::Io(err),
+            offset: 0,
+            path: String::new(),
+        })?;
+        ClassFile::from_reader(BufReader::new(file))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<ClassFile, ClassParseError> {
+        ClassFile::from_reader(bytes)
+    }
 
-        let constant_pool: Vec<Option<Constant>> =
-            generate_constant_pool(&mut reader, constant_pool_count - 1);
+    /// Parses a class file from any byte source. Reading stops at the end of the class
+    /// file's attributes table. Reads are small, so wrap unbuffered sources in a `BufReader`.
+    pub fn from_reader(reader: impl Read) -> Result<ClassFile, ClassParseError> {
+        ClassFile::read(&mut ClassReader::new(reader))
+    }
 
-        println!("constant pool: {:?}", constant_pool);
+    pub fn from_bytes_with(
+        bytes: &[u8],
+        options: &ParseOptions,
+    ) -> Result<ClassFile, ClassParseError> {
+        ClassFile::from_reader_with(bytes, options)
+    }
 
-        let access_flags: u16 = reader
-            .read_u16::<BigEndian>()
-            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-        let this_class: u16 = reader
-            .read_u16::<BigEndian>()
-            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-        let super_class: u16 = reader
-            .read_u16::<BigEndian>()
-            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
+    pub fn from_reader_with(
+        reader: impl Read,
+        options: &ParseOptions,
+    ) -> Result<ClassFile, ClassParseError> {
+        ClassFile::read(&mut ClassReader::with_options(reader, options.clone()))
+    }
 
-        println!(
-            "access flags: {access_flags}, this_class: {this_class}, super_class: {super_class}"
-        );
-        let interfaces_count: u16 = reader
-            .read_u16::<BigEndian>()
-            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-        let interfaces = generate_interfaces(interfaces_count, &mut reader);
-
-        for interface_index in interfaces.iter() {
-            let index: usize = (*interface_index).into();
-            println!(
-                "interface constant: {:?}",
-                constant_pool
-                    .get(index)
-                    .expect(BYTE_LENGTH_UNAVAILABLE_ERROR)
-            );
+    /// Parses with [`ParseOptions::lenient`] set, returning the partial model together
+    /// with every problem that was skipped.
+    pub fn from_bytes_lenient(
+        bytes: &[u8],
+    ) -> Result<(ClassFile, Vec<ClassParseError>), ClassParseError> {
+        ClassFile::from_reader_lenient(bytes, &ParseOptions::default())
+    }
+
+    pub fn from_reader_lenient(
+        reader: impl Read,
+        options: &ParseOptions,
+    ) -> Result<(ClassFile, Vec<ClassParseError>), ClassParseError> {
+        let options = ParseOptions {
+            lenient: true,
+            ..options.clone()
+        };
+        ClassFile::from_reader_with_diagnostics(reader, &options)
+    }
+
+    /// Parses with `options`, also returning the problems that were recorded instead of
+    /// failing the parse: those skipped by a lenient parse, and version mismatches under
+    /// [`VersionPolicy::Warn`](crate::classfile::options::VersionPolicy::Warn).
+    pub fn from_reader_with_diagnostics(
+        reader: impl Read,
+        options: &ParseOptions,
+    ) -> Result<(ClassFile, Vec<ClassParseError>), ClassParseError> {
+        let mut reader = ClassReader::with_options(reader, options.clone());
+        let class_file = ClassFile::read(&mut reader)?;
+        Ok((class_file, reader.take_diagnostics()))
+    }
+
+    pub fn version(&self) -> ClassVersion {
+        ClassVersion::new(self.major_version, self.minor_version)
+    }
+
+    pub fn read<R: Read>(reader: &mut ClassReader<R>) -> Result<ClassFile, ClassParseError> {
+        let magic = reader.scoped("magic", |reader| reader.read_u32())?;
+        if magic != CLASS_FILE_MAGIC {
+            return Err(reader.error_at(0, ParseErrorKind::InvalidMagic(magic)));
         }
+        let minor_version = reader.scoped("minor_version", |reader| reader.read_u16())?;
+        let major_version = reader.scoped("major_version", |reader| reader.read_u16())?;
+        reader.set_version(ClassVersion::new(major_version, minor_version));
+        let constant_pool_count =
+            reader.scoped("constant_pool_count", |reader| reader.read_u16())?;
+
+        let constant_pool: ConstantPool = generate_constant_pool(reader, constant_pool_count)?;
+
+        let access_flags =
+            ClassAccessFlags::from_bits(reader.scoped("access_flags", |reader| reader.read_u16())?);
+        let this_class: u16 = reader.scoped("this_class", |reader| reader.read_u16())?;
+        let super_class: u16 = reader.scoped("super_class", |reader| reader.read_u16())?;
 
-        let fields_count: u16 = reader
-            .read_u16::<BigEndian>()
-            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-        let fields = generate_fields(fields_count, &constant_pool, &mut reader);
+        let interfaces_count: u16 =
+            reader.scoped("interfaces_count", |reader| reader.read_u16())?;
+        let interfaces = generate_interfaces(interfaces_count, reader)?;
 
-        let methods_count: u16 = reader
-            .read_u16::<BigEndian>()
-            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-        let methods: Vec<Method> = generate_methods(methods_count, &constant_pool, &mut reader);
+        let fields_count: u16 = reader.scoped("fields_count", |reader| reader.read_u16())?;
+        let fields = generate_fields(fields_count, &constant_pool, reader)?;
 
-        let attributes_count: u16 = reader
-            .read_u16::<BigEndian>()
-            .expect(BYTE_LENGTH_UNAVAILABLE_ERROR);
-        let attributes = generate_attributes(attributes_count, &constant_pool, &mut reader);
+        let methods_count: u16 = reader.scoped("methods_count", |reader| reader.read_u16())?;
+        let methods: Vec<Method> = generate_methods(methods_count, &constant_pool, reader)?;
 
-        ClassFile {
+        let attributes_count: u16 =
+            reader.scoped("attributes_count", |reader| reader.read_u16())?;
+        let attributes = generate_attributes(attributes_count, &constant_pool, reader)?;
+
+        Ok(ClassFile {
             magic,
             minor_version,
             major_version,
-            constant_pool_count: constant_pool_count
-                .try_into()
-                .expect(BYTE_LENGTH_UNAVAILABLE_ERROR),
+            constant_pool_count,
             constant_pool,
             access_flags,
             this_class,
@@ -133,8 +188,110 @@ impl ClassFile {
             methods,
             attributes_count,
             attributes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLASS_FILE_CHECK: &[u8] = include_bytes!("../../ClassFileCheck.class");
+
+    /// The magic number and version 61.0 (Java 17), then a constant_pool_count of 3.
+    const HEADER: [u8; 10] = [0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 61, 0, 3];
+
+    #[test]
+    fn parses_a_class_compiled_by_javac() {
+        let class_file = ClassFile::from_bytes(CLASS_FILE_CHECK).unwrap();
+        assert_eq!(class_file.magic, CLASS_FILE_MAGIC);
+        assert_eq!(class_file.major_version, 61);
+        assert_eq!(class_file.interfaces.len(), 1);
+        assert_eq!(class_file.fields.len(), 1);
+        assert_eq!(class_file.methods.len(), 5);
+        assert_eq!(class_file.attributes.len(), 3);
+        assert_eq!(
+            class_file.constant_pool.class_name(class_file.this_class),
+            Ok("ClassFileCheck")
+        );
+        assert_eq!(
+            class_file.constant_pool.class_name(class_file.super_class),
+            Ok("BaseCheckClass")
+        );
+    }
+
+    #[test]
+    fn reading_stops_after_the_attributes_table() {
+        let bytes = [CLASS_FILE_CHECK, b"rest"].concat();
+        let mut reader = &bytes[..];
+        ClassFile::from_reader(&mut reader).unwrap();
+        assert_eq!(reader, b"rest");
+    }
+
+    #[test]
+    fn every_truncation_is_an_error() {
+        for length in 0..CLASS_FILE_CHECK.len() {
+            let err = ClassFile::from_bytes(&CLASS_FILE_CHECK[..length]).unwrap_err();
+            assert!(
+                matches!(err.kind, ParseErrorKind::UnexpectedEof),
+                "truncated to {length} bytes: {err}"
+            );
+            assert!(err.offset <= length, "truncated to {length} bytes: {err}");
         }
+    }
+
+    #[test]
+    fn truncated_header_reports_offset_and_path() {
+        let err = ClassFile::from_bytes(&HEADER[..7]).unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::UnexpectedEof));
+        assert_eq!(err.offset, 6);
+        assert_eq!(err.path, "major_version");
+    }
+
+    #[test]
+    fn truncated_constant_reports_its_index() {
+        // A Utf8 entry that promises six bytes and holds two.
+        let bytes = [&HEADER[..], &[1, 0, 6, b'S', b'a']].concat();
+        let err = ClassFile::from_bytes(&bytes).unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::UnexpectedEof));
+        assert_eq!(err.offset, 13);
+        assert_eq!(err.path, "constant_pool[1]");
+        assert_eq!(
+            err.to_string(),
+            "unexpected end of class file at offset 0x000D (constant_pool[1])"
+        );
+    }
+
+    #[test]
+    fn truncated_code_reports_the_nested_path() {
+        // Cut into the constructor's Code attribute, whose 63 bytes start at 0x062F.
+        let err = ClassFile::from_bytes(&CLASS_FILE_CHECK[..0x0640]).unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::UnexpectedEof));
+        assert_eq!(err.offset, 0x062F);
+        assert_eq!(err.path, "methods[0].attributes[0].Code");
+    }
+
+    #[test]
+    fn bad_magic_is_reported_at_offset_zero() {
+        // The start of a ZIP archive, as when a jar is passed in by mistake.
+        let err = ClassFile::from_bytes(b"PK\x03\x04\x14\x00\x08\x00").unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::InvalidMagic(0x504B0304)));
+        assert_eq!(err.offset, 0);
+    }
+
+    #[test]
+    fn unknown_constant_tag_is_reported_at_the_tag() {
+        let bytes = [&HEADER[..], &[99, 0, 0]].concat();
+        let err = ClassFile::from_bytes(&bytes).unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::UnknownConstantTag(99)));
+        assert_eq!(err.offset, 10);
+        assert_eq!(err.path, "constant_pool[1]");
+    }
 
-        // println!("class file \n: {:?}", class_file);
+    #[test]
+    fn missing_file_is_an_io_error() {
+        let err = ClassFile::parse("/nonexistent/Missing.class").unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::Io(_)));
+        assert!(std::error::Error::source(&err).is_some());
     }
 }
@@ -0,0 +1,231 @@
+//! The "modified UTF-8" encoding used by `CONSTANT_Utf8` entries (JVMS §4.4.7).
+//!
+//! It differs from standard UTF-8 in two ways: NUL is written as the two bytes `C0 80`
+//! so that no entry contains a zero byte, and characters outside the Basic Multilingual
+//! Plane are written as a UTF-16 surrogate pair with each surrogate encoded separately
+//! in three bytes (also known as CESU-8). Because the format is defined over UTF-16 code
+//! units, unpaired surrogates are legal and show up in obfuscated class files. Other
+//! overlong forms, such as `C1 81` for `A`, are not: each code unit has exactly one
+//! encoding.
+
+use std::{borrow::Cow, char, fmt};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mutf8Error {
+    /// Index of the first byte of the malformed sequence.
+    pub position: usize,
+}
+
+impl fmt::Display for Mutf8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "malformed modified UTF-8 at byte {}", self.position)
+    }
+}
+
+/// Decodes modified UTF-8 into UTF-16 code units. This never loses information, unpaired
+/// surrogates included.
+pub fn decode_utf16(bytes: &[u8]) -> Result<Vec<u16>, Mutf8Error> {
+    let mut units: Vec<u16> = Vec::with_capacity(bytes.len());
+    let mut position = 0;
+    while position < bytes.len() {
+        let (unit, length) = decode_unit(bytes, position).ok_or(Mutf8Error { position })?;
+        units.push(unit);
+        position += length;
+    }
+    Ok(units)
+}
+
+/// Decodes the code unit starting at `position`, returning it with its encoded length.
+/// Overlong forms are rejected, except `C0 80` which is how NUL must be written.
+fn decode_unit(bytes: &[u8], position: usize) -> Option<(u16, usize)> {
+    let first = bytes[position];
+    let continuation = |offset: usize| match bytes.get(position + offset) {
+        Some(byte) if byte & 0xC0 == 0x80 => Some((byte & 0x3F) as u16),
+        _ => None,
+    };
+    match first {
+        0x01..=0x7F => Some((first as u16, 1)),
+        0xC0..=0xDF => {
+            let unit = ((first & 0x1F) as u16) << 6 | continuation(1)?;
+            (unit == 0 || unit >= 0x80).then_some((unit, 2))
+        }
+        0xE0..=0xEF => {
+            let unit = ((first & 0x0F) as u16) << 12 | continuation(1)? << 6 | continuation(2)?;
+            (unit >= 0x800).then_some((unit, 3))
+        }
+        _ => None,
+    }
+}
+
+/// Decodes modified UTF-8 into a `String`. Surrogate pairs are combined into their
+/// supplementary character. Unpaired surrogates cannot be held by a Rust string and are
+/// replaced with U+FFFD; use [`decode_utf16`] when they must be preserved.
+pub fn decode(bytes: &[u8]) -> Result<String, Mutf8Error> {
+    decode_borrowed(bytes).map(Cow::into_owned)
+}
+
+/// Like [`decode`], but borrows from `bytes` when they need no conversion: text without
+/// NUL or supplementary characters is encoded identically in both forms.
+pub fn decode_borrowed(bytes: &[u8]) -> Result<Cow<'_, str>, Mutf8Error> {
+    // Standard UTF-8 rejects the two-byte NUL and encoded surrogates, and modified UTF-8
+    // has no four-byte sequences, so anything accepted here means the same in both.
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        if !bytes.iter().any(|&byte| byte == 0 || byte >= 0xF0) {
+            return Ok(Cow::Borrowed(text));
+        }
+    }
+    let units = decode_utf16(bytes)?;
+    Ok(Cow::Owned(
+        char::decode_utf16(units)
+            .map(|unit| unit.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect(),
+    ))
+}
+
+/// Like [`decode`], but each byte that does not start a well formed sequence becomes
+/// U+FFFD instead of failing.
+pub fn decode_lossy(bytes: &[u8]) -> String {
+    let mut units: Vec<u16> = Vec::with_capacity(bytes.len());
+    let mut position = 0;
+    while position < bytes.len() {
+        match decode_unit(bytes, position) {
+            Some((unit, length)) => {
+                units.push(unit);
+                position += length;
+            }
+            None => {
+                units.push(char::REPLACEMENT_CHARACTER as u16);
+                position += 1;
+            }
+        }
+    }
+    char::decode_utf16(units)
+        .map(|unit| unit.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+/// Returns true if the UTF-16 code units contain a surrogate without its partner.
+pub fn has_unpaired_surrogates(units: &[u16]) -> bool {
+    char::decode_utf16(units.iter().copied()).any(|unit| unit.is_err())
+}
+
+/// Encodes a string as modified UTF-8.
+pub fn encode(string: &str) -> Vec<u8> {
+    let units: Vec<u16> = string.encode_utf16().collect();
+    encode_utf16(&units)
+}
+
+/// Encodes UTF-16 code units as modified UTF-8, the inverse of [`decode_utf16`].
+pub fn encode_utf16(units: &[u16]) -> Vec<u8> {
+    let mut bytes: Vec<u8> = Vec::with_capacity(units.len());
+    for &unit in units {
+        match unit {
+            0x0001..=0x007F => bytes.push(unit as u8),
+            0x0000 | 0x0080..=0x07FF => {
+                bytes.push(0xC0 | (unit >> 6) as u8);
+                bytes.push(0x80 | (unit & 0x3F) as u8);
+            }
+            _ => {
+                bytes.push(0xE0 | (unit >> 12) as u8);
+                bytes.push(0x80 | ((unit >> 6) & 0x3F) as u8);
+                bytes.push(0x80 | (unit & 0x3F) as u8);
+            }
+        }
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nul_is_two_bytes() {
+        assert_eq!(encode("a\0b"), [b'a', 0xC0, 0x80, b'b']);
+        assert_eq!(decode(&[b'a', 0xC0, 0x80, b'b']).unwrap(), "a\0b");
+        assert_eq!(decode(&[b'a', 0, b'b']), Err(Mutf8Error { position: 1 }));
+    }
+
+    #[test]
+    fn supplementary_characters_are_surrogate_pairs() {
+        // U+1F600 is the pair D83D DE00, each surrogate in three bytes.
+        let bytes = [0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80];
+        assert_eq!(encode("\u{1F600}"), bytes);
+        assert_eq!(decode(&bytes).unwrap(), "\u{1F600}");
+        assert_eq!(decode_utf16(&bytes).unwrap(), [0xD83D, 0xDE00]);
+        // Standard UTF-8's four-byte form is not modified UTF-8.
+        assert_eq!(
+            decode("\u{1F600}".as_bytes()),
+            Err(Mutf8Error { position: 0 })
+        );
+    }
+
+    #[test]
+    fn lone_surrogates_survive_as_utf16() {
+        let bytes = [b'x', 0xED, 0xA0, 0xBD, b'y'];
+        let units = decode_utf16(&bytes).unwrap();
+        assert_eq!(units, [0x78, 0xD83D, 0x79]);
+        assert!(has_unpaired_surrogates(&units));
+        assert_eq!(decode(&bytes).unwrap(), "x\u{FFFD}y");
+        assert_eq!(encode_utf16(&units), bytes);
+    }
+
+    #[test]
+    fn overlong_forms_are_rejected() {
+        assert_eq!(decode(&[0xC1, 0x81]), Err(Mutf8Error { position: 0 }));
+        assert_eq!(decode(&[b'a', 0xC0, 0x81]), Err(Mutf8Error { position: 1 }));
+        assert_eq!(decode(&[0xE0, 0x81, 0x81]), Err(Mutf8Error { position: 0 }));
+        assert_eq!(decode(&[0xE0, 0x80, 0x80]), Err(Mutf8Error { position: 0 }));
+    }
+
+    #[test]
+    fn truncated_and_stray_bytes_are_rejected() {
+        assert_eq!(decode(&[b'a', 0xE4, 0xB8]), Err(Mutf8Error { position: 1 }));
+        assert_eq!(decode(&[0x80]), Err(Mutf8Error { position: 0 }));
+        assert_eq!(
+            decode(&[0xF0, 0x9F, 0x98, 0x80]),
+            Err(Mutf8Error { position: 0 })
+        );
+    }
+
+    #[test]
+    fn decode_lossy_replaces_each_bad_byte() {
+        assert_eq!(decode_lossy(&[b'a', 0xFF, b'b']), "a\u{FFFD}b");
+        assert_eq!(decode_lossy(&[b'a', 0xE4, 0xB8]), "a\u{FFFD}\u{FFFD}");
+        assert_eq!(decode_lossy(&[0xC0, 0x80, 0x00]), "\0\u{FFFD}");
+    }
+
+    #[test]
+    fn decode_borrowed_only_copies_when_needed() {
+        assert!(matches!(
+            decode_borrowed(b"plain").unwrap(),
+            Cow::Borrowed("plain")
+        ));
+        assert!(matches!(
+            decode_borrowed("caf\u{E9}".as_bytes()).unwrap(),
+            Cow::Borrowed(_)
+        ));
+        assert!(matches!(
+            decode_borrowed(&[0xC0, 0x80]).unwrap(),
+            Cow::Owned(_)
+        ));
+    }
+
+    #[test]
+    fn round_trips() {
+        for text in [
+            "",
+            "java/lang/Object",
+            "\0",
+            "caf\u{E9}",
+            "\u{20AC}\u{FFFF}",
+            "a\u{10FFFF}\0z",
+        ] {
+            let bytes = encode(text);
+            assert!(!bytes.contains(&0), "{text:?}");
+            assert_eq!(decode(&bytes).unwrap(), text);
+        }
+        let units = [0xDC00, 0x0000, 0x07FF, 0x0800, 0xD800];
+        assert_eq!(decode_utf16(&encode_utf16(&units)).unwrap(), units);
+    }
+}
@@ -0,0 +1,102 @@
+use std::{any::Any, collections::HashMap, error::Error, fmt, sync::Arc};
+
+use crate::classfile::constant_pool::ConstantPool;
+
+/// Settings that control how a class file is parsed.
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    pub attribute_registry: AttributeRegistry,
+    /// Instead of failing on the first problem, record it as a diagnostic and skip the
+    /// damaged structure: an attribute that cannot be parsed within its declared
+    /// `attribute_length` is kept as [`AttributeType::Invalid`](crate::classfile::attributes::AttributeType::Invalid),
+    /// and a `CONSTANT_Utf8` entry that is not valid modified UTF-8 is decoded lossily.
+    /// Problems that leave no way to find the next structure, such as an unknown
+    /// constant pool tag or a truncated file, still fail the parse.
+    pub lenient: bool,
+    pub version_policy: VersionPolicy,
+}
+
+/// What to do with a constant or attribute that is newer than the class file containing
+/// it, such as a `CONSTANT_Dynamic` in a version 52 class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VersionPolicy {
+    /// Behave like the JVM: such a constant is an error, and such an attribute is not
+    /// recognised and is kept as [`AttributeType::Unknown`](crate::classfile::attributes::AttributeType::Unknown).
+    #[default]
+    Strict,
+    /// Parse it anyway, recording a diagnostic.
+    Warn,
+    /// Parse it anyway.
+    Ignore,
+}
+
+/// A value produced by a custom attribute parser. Implemented for every `Debug` type that
+/// can be shared between threads; use [`CustomAttribute::as_any`] to get the concrete
+/// type back.
+pub trait CustomAttribute: fmt::Debug + Send + Sync + 'static {
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: fmt::Debug + Send + Sync + 'static> CustomAttribute for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+pub type CustomAttributeError = Box<dyn Error + Send + Sync>;
+
+type AttributeParser = dyn Fn(&[u8], &ConstantPool) -> Result<Arc<dyn CustomAttribute>, CustomAttributeError>
+    + Send
+    + Sync;
+
+/// Decoders for attributes this crate does not know about, keyed by attribute name.
+/// A decoder receives the attribute's `info` bytes and the class's constant pool; its
+/// result shows up as [`AttributeType::Custom`](crate::classfile::attributes::AttributeType::Custom).
+#[derive(Clone, Default)]
+pub struct AttributeRegistry {
+    parsers: HashMap<String, Arc<AttributeParser>>,
+}
+
+impl AttributeRegistry {
+    pub fn new() -> AttributeRegistry {
+        AttributeRegistry::default()
+    }
+
+    /// Registers `parser` for attributes called `name`, replacing any earlier parser for
+    /// that name. Attributes defined by the JVM specification are always parsed by this
+    /// crate and never reach a registered parser.
+    pub fn register<T, F>(&mut self, name: &str, parser: F)
+    where
+        T: CustomAttribute,
+        F: Fn(&[u8], &ConstantPool) -> Result<T, CustomAttributeError> + Send + Sync + 'static,
+    {
+        self.parsers.insert(
+            name.to_string(),
+            Arc::new(move |bytes, constant_pool| {
+                parser(bytes, constant_pool)
+                    .map(|value| Arc::new(value) as Arc<dyn CustomAttribute>)
+            }),
+        );
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.parsers.contains_key(name)
+    }
+
+    pub fn parse(
+        &self,
+        name: &str,
+        bytes: &[u8],
+        constant_pool: &ConstantPool,
+    ) -> Option<Result<Arc<dyn CustomAttribute>, CustomAttributeError>> {
+        self.parsers
+            .get(name)
+            .map(|parser| parser(bytes, constant_pool))
+    }
+}
+
+impl fmt::Debug for AttributeRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.parsers.keys()).finish()
+    }
+}
@@ -0,0 +1,601 @@
+use std::{
+    collections::HashMap,
+    io::{self, Read},
+    mem,
+    sync::Arc,
+};
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+use crate::classfile::{
+    error::{ClassParseError, ParseErrorKind},
+    options::{ParseOptions, VersionPolicy},
+    version::ClassVersion,
+};
+
+/// Wraps the underlying input and keeps track of the current byte offset and the
+/// structural path being parsed, so that failures can be reported precisely.
+pub struct ClassReader<R> {
+    inner: R,
+    offset: usize,
+    path: Vec<String>,
+    trace: Option<Trace>,
+    options: Arc<ParseOptions>,
+    /// Set on readers created by [`ClassReader::bounded`]: the offset where the enclosing
+    /// attribute ends.
+    limit: Option<usize>,
+    diagnostics: Vec<ClassParseError>,
+    /// The version of the class file being read, once its header has been.
+    version: Option<ClassVersion>,
+}
+
+/// Record of every read made by a tracing [`ClassReader`], used by the hex dump.
+#[derive(Debug, Default)]
+pub struct Trace {
+    pub spans: Vec<TraceSpan>,
+    pub notes: Vec<TraceNote>,
+    pub descriptions: HashMap<String, String>,
+}
+
+/// A single primitive read. `is_bytes` marks raw byte arrays such as `code`, whose
+/// contents are addressed by index rather than as one value.
+#[derive(Debug)]
+pub struct TraceSpan {
+    pub start: usize,
+    pub end: usize,
+    pub path: String,
+    pub is_bytes: bool,
+}
+
+#[derive(Debug)]
+pub struct TraceNote {
+    pub offset: usize,
+    pub path: String,
+    pub message: String,
+}
+
+impl<R: Read> ClassReader<R> {
+    pub fn new(inner: R) -> ClassReader<R> {
+        ClassReader {
+            inner,
+            offset: 0,
+            path: vec![],
+            trace: None,
+            options: Arc::new(ParseOptions::default()),
+            limit: None,
+            diagnostics: vec![],
+            version: None,
+        }
+    }
+
+    pub fn with_options(inner: R, options: ParseOptions) -> ClassReader<R> {
+        ClassReader {
+            options: Arc::new(options),
+            ..ClassReader::new(inner)
+        }
+    }
+
+    /// Creates a reader over input that starts `offset` bytes into the class file, inside
+    /// the structure at `path`, such as an attribute that is decoded on demand.
+    pub(crate) fn resume(
+        inner: R,
+        options: Arc<ParseOptions>,
+        offset: usize,
+        path: Vec<String>,
+    ) -> ClassReader<R> {
+        ClassReader {
+            offset,
+            path,
+            options,
+            ..ClassReader::new(inner)
+        }
+    }
+
+    pub fn options(&self) -> &ParseOptions {
+        &self.options
+    }
+
+    /// Creates a reader that records the offset and path of every read it makes.
+    pub fn traced(inner: R, options: ParseOptions) -> ClassReader<R> {
+        ClassReader {
+            trace: Some(Trace::default()),
+            ..ClassReader::with_options(inner, options)
+        }
+    }
+
+    pub fn take_trace(&mut self) -> Option<Trace> {
+        self.trace.take()
+    }
+
+    /// Attaches a human readable description to the structure at the current path.
+    /// Only kept when tracing.
+    pub fn describe(&mut self, description: impl FnOnce() -> String) {
+        let path = self.path();
+        if let Some(trace) = self.trace.as_mut() {
+            trace.descriptions.insert(path, description());
+        }
+    }
+
+    /// Records an observation about the structure at the current path, such as a
+    /// length that disagrees with what was parsed. Only kept when tracing.
+    pub fn note(&mut self, message: impl FnOnce() -> String) {
+        let path = self.path();
+        let offset = self.offset;
+        if let Some(trace) = self.trace.as_mut() {
+            trace.notes.push(TraceNote {
+                offset,
+                path,
+                message: message(),
+            });
+        }
+    }
+
+    /// Handles a problem the caller knows how to step over. In lenient mode it is kept as
+    /// a diagnostic (and as a note when tracing) and parsing continues; otherwise it is
+    /// returned as the error.
+    pub fn recover(&mut self, err: ClassParseError) -> Result<(), ClassParseError> {
+        if !self.options.lenient {
+            return Err(err);
+        }
+        self.warn(err);
+        Ok(())
+    }
+
+    /// Records a problem that does not stop parsing as a diagnostic (and as a note when
+    /// tracing).
+    pub fn warn(&mut self, err: ClassParseError) {
+        if let Some(trace) = self.trace.as_mut() {
+            trace.notes.push(TraceNote {
+                offset: err.offset,
+                path: err.path.clone(),
+                message: err.kind.to_string(),
+            });
+        }
+        self.diagnostics.push(err);
+    }
+
+    pub fn version(&self) -> Option<ClassVersion> {
+        self.version
+    }
+
+    pub fn set_version(&mut self, version: ClassVersion) {
+        self.version = Some(version);
+    }
+
+    /// The class file's version if it is older than `since`.
+    fn predates(&self, since: u16) -> Option<ClassVersion> {
+        self.version.filter(|version| !version.at_least(since))
+    }
+
+    /// Applies [`ParseOptions::version_policy`] to a constant at `offset` that needs class
+    /// file version `since`. Under the strict policy a constant that is too new is an
+    /// error.
+    pub fn require_version(
+        &mut self,
+        since: u16,
+        offset: usize,
+        feature: impl FnOnce() -> String,
+    ) -> Result<(), ClassParseError> {
+        let Some(version) = self.predates(since) else {
+            return Ok(());
+        };
+        let err = self.error_at(
+            offset,
+            ParseErrorKind::UnsupportedInVersion {
+                feature: feature(),
+                since,
+                version,
+            },
+        );
+        match self.options.version_policy {
+            VersionPolicy::Strict => self.recover(err),
+            VersionPolicy::Warn => {
+                self.warn(err);
+                Ok(())
+            }
+            VersionPolicy::Ignore => Ok(()),
+        }
+    }
+
+    /// Applies [`ParseOptions::version_policy`] to an attribute that needs class file
+    /// version `since`, returning whether it should be parsed. Under the strict policy an
+    /// attribute that is too new is left unparsed, as the JVM does not recognise it.
+    pub fn recognizes(&mut self, since: u16, feature: impl FnOnce() -> String) -> bool {
+        let Some(version) = self.predates(since) else {
+            return true;
+        };
+        match self.options.version_policy {
+            VersionPolicy::Strict => false,
+            VersionPolicy::Warn => {
+                let err = self.error(ParseErrorKind::UnsupportedInVersion {
+                    feature: feature(),
+                    since,
+                    version,
+                });
+                self.warn(err);
+                true
+            }
+            VersionPolicy::Ignore => true,
+        }
+    }
+
+    /// Returns the problems skipped over so far by a lenient parse.
+    pub fn take_diagnostics(&mut self) -> Vec<ClassParseError> {
+        mem::take(&mut self.diagnostics)
+    }
+
+    fn record(&mut self, length: usize, is_bytes: bool) {
+        let start = self.offset;
+        self.offset += length;
+        if self.trace.is_some() {
+            let path = self.path();
+            if let Some(trace) = self.trace.as_mut() {
+                trace.spans.push(TraceSpan {
+                    start,
+                    end: start + length,
+                    path,
+                    is_bytes,
+                });
+            }
+        }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn path(&self) -> String {
+        self.path.join(".")
+    }
+
+    /// Runs `parse` with `segment` appended to the current path. The segment is removed
+    /// again afterwards whether or not `parse` succeeded.
+    pub fn scoped<T>(
+        &mut self,
+        segment: impl Into<String>,
+        parse: impl FnOnce(&mut Self) -> Result<T, ClassParseError>,
+    ) -> Result<T, ClassParseError> {
+        self.path.push(segment.into());
+        let result = parse(self);
+        self.path.pop();
+        result
+    }
+
+    pub fn error(&self, kind: ParseErrorKind) -> ClassParseError {
+        self.error_at(self.offset, kind)
+    }
+
+    pub fn error_at(&self, offset: usize, kind: ParseErrorKind) -> ClassParseError {
+        ClassParseError {
+            kind,
+            offset,
+            path: self.path(),
+        }
+    }
+
+    fn io_error(&self, err: io::Error) -> ClassParseError {
+        match (err.kind(), self.limit) {
+            (io::ErrorKind::UnexpectedEof, Some(limit)) => {
+                self.error(ParseErrorKind::AttributeOverrun {
+                    attribute_end: limit,
+                })
+            }
+            (io::ErrorKind::UnexpectedEof, None) => self.error(ParseErrorKind::UnexpectedEof),
+            _ => self.error(ParseErrorKind::Io(err)),
+        }
+    }
+
+    /// Reads `length` bytes without recording them in the trace. The buffer grows as
+    /// data arrives, so a bogus length fails with end of input rather than exhausting
+    /// memory.
+    fn read_raw(&mut self, length: usize) -> Result<Vec<u8>, ClassParseError> {
+        let mut bytes: Vec<u8> = vec![];
+        let read = (&mut self.inner)
+            .take(length as u64)
+            .read_to_end(&mut bytes)
+            .map_err(|err| self.io_error(err))?;
+        if read < length {
+            return Err(self.io_error(io::ErrorKind::UnexpectedEof.into()));
+        }
+        self.offset += length;
+        Ok(bytes)
+    }
+
+    /// Reads the next `length` bytes and runs `parse` over them with a reader that cannot
+    /// see past their end. Fails if `parse` reads beyond the bytes or leaves any unread,
+    /// so a broken attribute parser cannot desynchronise the rest of the class file.
+    /// In lenient mode the failure is recorded instead and `salvage` turns the raw bytes
+    /// into the result.
+    pub fn bounded<T>(
+        &mut self,
+        length: usize,
+        parse: impl FnOnce(&mut ClassReader<&[u8]>) -> Result<T, ClassParseError>,
+        salvage: impl FnOnce(Vec<u8>) -> T,
+    ) -> Result<T, ClassParseError> {
+        let start = self.offset;
+        let bytes = self.read_raw(length)?;
+        let mut bounded = ClassReader {
+            inner: bytes.as_slice(),
+            offset: start,
+            path: mem::take(&mut self.path),
+            trace: self.trace.take(),
+            options: Arc::clone(&self.options),
+            limit: Some(start + length),
+            diagnostics: mem::take(&mut self.diagnostics),
+            version: self.version,
+        };
+        let result = parse(&mut bounded).and_then(|value| {
+            let consumed = bounded.offset - start;
+            if consumed == length {
+                return Ok(value);
+            }
+            Err(bounded.error_at(
+                start + consumed,
+                ParseErrorKind::AttributeLengthMismatch {
+                    declared: length,
+                    consumed,
+                },
+            ))
+        });
+        self.path = bounded.path;
+        self.trace = bounded.trace;
+        self.diagnostics = bounded.diagnostics;
+
+        match result {
+            Ok(value) => Ok(value),
+            Err(err) => {
+                self.recover(err)?;
+                // Replace whatever the failed parse traced with one span of raw bytes.
+                if let Some(trace) = self.trace.as_mut() {
+                    trace.spans.retain(|span| span.start < start);
+                }
+                self.offset = start;
+                self.record(length, true);
+                Ok(salvage(bytes))
+            }
+        }
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, ClassParseError> {
+        let value = self.inner.read_u8().map_err(|err| self.io_error(err))?;
+        self.record(1, false);
+        Ok(value)
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, ClassParseError> {
+        let value = self
+            .inner
+            .read_u16::<BigEndian>()
+            .map_err(|err| self.io_error(err))?;
+        self.record(2, false);
+        Ok(value)
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, ClassParseError> {
+        let value = self
+            .inner
+            .read_u32::<BigEndian>()
+            .map_err(|err| self.io_error(err))?;
+        self.record(4, false);
+        Ok(value)
+    }
+
+    pub fn read_bytes(&mut self, length: usize) -> Result<Vec<u8>, ClassParseError> {
+        let bytes = self.read_raw(length)?;
+        self.offset -= length;
+        self.record(length, true);
+        Ok(bytes)
+    }
+}
+
+impl<'a> ClassReader<&'a [u8]> {
+    /// Reads the next `length` bytes as a slice of the input rather than a copy.
+    pub fn read_slice(&mut self, length: usize) -> Result<&'a [u8], ClassParseError> {
+        if self.inner.len() < length {
+            return Err(self.io_error(io::ErrorKind::UnexpectedEof.into()));
+        }
+        let (bytes, rest) = self.inner.split_at(length);
+        self.inner = rest;
+        self.record(length, true);
+        Ok(bytes)
+    }
+
+    pub fn peek_u8(&self) -> Option<u8> {
+        self.inner.first().copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classfile::{
+        attributes::AttributeType,
+        testing::{u16_bytes, ClassBuilder},
+        ClassFile,
+    };
+
+    #[test]
+    fn reads_are_big_endian_and_advance_the_offset() {
+        let mut reader = ClassReader::new(&[0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC][..]);
+        assert_eq!(reader.read_u8().unwrap(), 0x12);
+        assert_eq!(reader.read_u16().unwrap(), 0x3456);
+        assert_eq!(reader.read_u32().map_err(|err| err.offset), Err(3));
+        assert_eq!(reader.offset(), 3);
+    }
+
+    #[test]
+    fn end_of_input_is_reported_where_the_read_started() {
+        let mut reader = ClassReader::new(&[0, 1, 2][..]);
+        reader.read_u16().unwrap();
+        let err = reader
+            .scoped("outer", |reader| {
+                reader.scoped("inner", |reader| reader.read_u16())
+            })
+            .unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::UnexpectedEof));
+        assert_eq!(err.offset, 2);
+        assert_eq!(err.path, "outer.inner");
+        assert_eq!(reader.path(), "");
+    }
+
+    #[test]
+    fn read_bytes_fails_on_a_length_past_the_end() {
+        let mut reader = ClassReader::new(&[0; 4][..]);
+        let err = reader.read_bytes(usize::MAX / 2).unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::UnexpectedEof));
+        assert_eq!(err.offset, 0);
+    }
+
+    #[test]
+    fn bounded_reads_cannot_overrun() {
+        let mut reader = ClassReader::new(&[0, 0, 0, 0, 0][..]);
+        reader.read_u8().unwrap();
+        let err = reader
+            .bounded(2, |reader| reader.read_u32(), |_| 0)
+            .unwrap_err();
+        assert!(matches!(
+            err.kind,
+            ParseErrorKind::AttributeOverrun { attribute_end: 3 }
+        ));
+        assert_eq!(err.offset, 1);
+    }
+
+    #[test]
+    fn bounded_reads_must_consume_everything() {
+        let mut reader = ClassReader::new(&[0, 0, 0, 0][..]);
+        let err = reader
+            .bounded(3, |reader| reader.read_u16(), |_| 0)
+            .unwrap_err();
+        assert!(matches!(
+            err.kind,
+            ParseErrorKind::AttributeLengthMismatch {
+                declared: 3,
+                consumed: 2
+            }
+        ));
+        assert_eq!(err.offset, 2);
+    }
+
+    #[test]
+    fn bounded_reads_resume_after_the_bound() {
+        let mut reader = ClassReader::new(&[0, 1, 0, 2, 0, 3][..]);
+        let value = reader
+            .bounded(4, |reader| reader.read_u32(), |_| 0)
+            .unwrap();
+        assert_eq!(value, 0x0001_0002);
+        assert_eq!(reader.read_u16().unwrap(), 3);
+        assert_eq!(reader.offset(), 6);
+    }
+
+    /// A class whose only field has a ConstantValue attribute claiming 3 bytes instead of
+    /// 2, followed by a well formed SourceFile attribute.
+    fn class_with_bad_attribute_length() -> (Vec<u8>, usize) {
+        let mut builder = ClassBuilder::new("Sample");
+        let value = builder.integer(1);
+        let mut constant_value = builder.attribute("ConstantValue", &u16_bytes(&[value]));
+        constant_value[5] = 3;
+        constant_value.push(0);
+        builder.field(0x0018, "ONE", "I", &[constant_value]);
+        let source_file = builder.utf8("Sample.java");
+        let source_file = builder.attribute("SourceFile", &u16_bytes(&[source_file]));
+        builder.class_attribute(source_file);
+        // Past the constant pool: the class header and interface and field counts, the
+        // field's header and attribute count, then the attribute's name and length.
+        let body = builder.constant_pool_end() + 10 + 8 + 6;
+        (builder.build(), body)
+    }
+
+    #[test]
+    fn lenient_parse_keeps_an_attribute_with_a_bad_length() {
+        let (bytes, body) = class_with_bad_attribute_length();
+
+        let err = ClassFile::from_bytes(&bytes).unwrap_err();
+        assert!(matches!(
+            err.kind,
+            ParseErrorKind::AttributeLengthMismatch {
+                declared: 3,
+                consumed: 2
+            }
+        ));
+        assert_eq!(err.offset, body + 2);
+        assert_eq!(err.path, "fields[0].attributes[0].ConstantValue");
+
+        let (class_file, diagnostics) = ClassFile::from_bytes_lenient(&bytes).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].offset, err.offset);
+        assert_eq!(diagnostics[0].path, err.path);
+        match &class_file.fields[0].attributes[0] {
+            AttributeType::Invalid { name, bytes } => {
+                assert_eq!(name.as_deref(), Some("ConstantValue"));
+                assert_eq!(bytes.len(), 3);
+            }
+            attribute => panic!("expected the raw attribute, got {attribute:?}"),
+        }
+        // Parsing carried on past the damaged attribute.
+        assert!(matches!(
+            class_file.attributes[..],
+            [AttributeType::SourceFile(_)]
+        ));
+    }
+
+    #[test]
+    fn lenient_parse_keeps_an_attribute_with_an_unresolvable_name() {
+        let mut builder = ClassBuilder::new("Sample");
+        let mut attribute = builder.attribute("Whatever", &[1, 2, 3]);
+        attribute[0..2].copy_from_slice(&builder.this_class.to_be_bytes());
+        builder.class_attribute(attribute);
+        let bytes = builder.build();
+        let attribute_offset = bytes.len() - 9;
+
+        let err = ClassFile::from_bytes(&bytes).unwrap_err();
+        assert!(
+            matches!(err.kind, ParseErrorKind::ExpectedUtf8(index) if index == builder.this_class)
+        );
+        assert_eq!(err.offset, attribute_offset);
+
+        let (class_file, diagnostics) = ClassFile::from_bytes_lenient(&bytes).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].offset, attribute_offset);
+        assert!(matches!(
+            &class_file.attributes[..],
+            [AttributeType::Invalid { name: None, bytes }] if bytes == &[1, 2, 3]
+        ));
+    }
+
+    #[test]
+    fn lenient_parse_decodes_bad_utf8_lossily() {
+        let mut builder = ClassBuilder::new("Sample");
+        let junk = builder.constant(&[1, 0, 3, b'a', 0xFF, b'b'], 1);
+        let bytes = builder.build();
+        let junk_bytes = builder.constant_pool_end() - 3;
+
+        let err = ClassFile::from_bytes(&bytes).unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::InvalidUtf8));
+        assert_eq!(err.offset, junk_bytes + 1);
+
+        let (class_file, diagnostics) = ClassFile::from_bytes_lenient(&bytes).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].offset, err.offset);
+        assert_eq!(diagnostics[0].path, format!("constant_pool[{junk}]"));
+        assert_eq!(class_file.constant_pool.utf8(junk), Ok("a\u{FFFD}b"));
+    }
+
+    #[test]
+    fn lenient_parse_cannot_skip_an_unknown_constant_tag() {
+        // Nothing says how long an entry with an unknown tag is, so there is no way to
+        // find the next one.
+        let mut builder = ClassBuilder::new("Sample");
+        builder.constant(&[2, 0, 0], 1);
+        let bytes = builder.build();
+
+        let strict = ClassFile::from_bytes(&bytes).unwrap_err();
+        let lenient = ClassFile::from_bytes_lenient(&bytes).unwrap_err();
+        assert!(matches!(strict.kind, ParseErrorKind::UnknownConstantTag(2)));
+        assert!(matches!(
+            lenient.kind,
+            ParseErrorKind::UnknownConstantTag(2)
+        ));
+        assert_eq!(strict.offset, builder.constant_pool_end() - 3);
+        assert_eq!(lenient.offset, strict.offset);
+    }
+}
@@ -0,0 +1,392 @@
+//! A header-only pass for indexing many classes at once.
+//!
+//! [`ClassSummary::scan`] reads the constant pool and the member tables straight from a
+//! byte slice and steps over every attribute by its length, resolving only the names it
+//! returns. It checks less than a full parse: attribute contents are never looked at,
+//! and neither are constants the summary does not refer to.
+
+use crate::classfile::{
+    access_flags::{ClassAccessFlags, FieldAccessFlags, MethodAccessFlags},
+    constant_pool::{
+        CONSTANT_POOL_CLASS, CONSTANT_POOL_DOUBLE, CONSTANT_POOL_DYNAMIC, CONSTANT_POOL_FIELDREF,
+        CONSTANT_POOL_FLOAT, CONSTANT_POOL_INTEGER, CONSTANT_POOL_INTERFACEMETHODREF,
+        CONSTANT_POOL_INVOKE_DYNAMIC, CONSTANT_POOL_LONG, CONSTANT_POOL_METHODREF,
+        CONSTANT_POOL_METHOD_HANDLE, CONSTANT_POOL_METHOD_TYPE, CONSTANT_POOL_MODULE,
+        CONSTANT_POOL_NAME_AND_TYPE, CONSTANT_POOL_PACKAGE, CONSTANT_POOL_STRING,
+        CONSTANT_POOL_UTF8,
+    },
+    error::{ClassParseError, ParseErrorKind},
+    mutf8,
+    version::ClassVersion,
+    CLASS_FILE_MAGIC,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ClassSummary {
+    pub version: ClassVersion,
+    pub access_flags: ClassAccessFlags,
+    /// Internal name, e.g. `java/util/ArrayList`.
+    pub this_class: String,
+    /// `None` only for `java/lang/Object` and `module-info`.
+    pub super_class: Option<String>,
+    pub interfaces: Vec<String>,
+    pub fields: Vec<FieldSummary>,
+    pub methods: Vec<MethodSummary>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MemberSummary<F> {
+    pub access_flags: F,
+    pub name: String,
+    pub descriptor: String,
+}
+
+pub type FieldSummary = MemberSummary<FieldAccessFlags>;
+pub type MethodSummary = MemberSummary<MethodAccessFlags>;
+
+impl ClassSummary {
+    pub fn scan(bytes: &[u8]) -> Result<ClassSummary, ClassParseError> {
+        let scanner = &mut Scanner {
+            bytes,
+            offset: 0,
+            section: "magic",
+            index: None,
+        };
+        let magic = scanner.u32()?;
+        if magic != CLASS_FILE_MAGIC {
+            return Err(scanner.error_at(0, ParseErrorKind::InvalidMagic(magic)));
+        }
+        scanner.enter("minor_version", None);
+        let minor_version = scanner.u16()?;
+        scanner.enter("major_version", None);
+        let major_version = scanner.u16()?;
+        scanner.enter("constant_pool_count", None);
+        let constant_pool_count = scanner.u16()?;
+        let constant_pool = scan_constant_pool(scanner, constant_pool_count)?;
+
+        scanner.enter("access_flags", None);
+        let access_flags = ClassAccessFlags::from_bits(scanner.u16()?);
+        scanner.enter("this_class", None);
+        let this_class = constant_pool.class_name(scanner)?;
+        scanner.enter("super_class", None);
+        let super_class = constant_pool.optional_class_name(scanner)?;
+
+        scanner.enter("interfaces_count", None);
+        let interfaces_count = scanner.u16()?;
+        let mut interfaces: Vec<String> = Vec::with_capacity(interfaces_count as usize);
+        for index in 0..interfaces_count {
+            scanner.enter("interfaces", Some(index));
+            interfaces.push(constant_pool.class_name(scanner)?);
+        }
+
+        scanner.enter("fields_count", None);
+        let fields_count = scanner.u16()?;
+        let fields = scan_members(scanner, "fields", fields_count, &constant_pool)?;
+        scanner.enter("methods_count", None);
+        let methods_count = scanner.u16()?;
+        let methods = scan_members(scanner, "methods", methods_count, &constant_pool)?;
+        scanner.enter("attributes", None);
+        skip_attributes(scanner)?;
+
+        Ok(ClassSummary {
+            version: ClassVersion::new(major_version, minor_version),
+            access_flags,
+            this_class,
+            super_class,
+            interfaces,
+            fields,
+            methods,
+        })
+    }
+}
+
+/// A cursor over the class bytes. Rather than a full path it keeps the name and index of
+/// the table being read, which is enough to place an error and costs nothing to update.
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    section: &'static str,
+    index: Option<u16>,
+}
+
+impl Scanner<'_> {
+    fn enter(&mut self, section: &'static str, index: Option<u16>) {
+        self.section = section;
+        self.index = index;
+    }
+
+    fn error_at(&self, offset: usize, kind: ParseErrorKind) -> ClassParseError {
+        let path = match self.index {
+            Some(index) => format!("{}[{index}]", self.section),
+            None => self.section.to_string(),
+        };
+        ClassParseError { kind, offset, path }
+    }
+
+    fn take(&mut self, length: usize) -> Result<&[u8], ClassParseError> {
+        let bytes = self
+            .bytes
+            .get(self.offset..self.offset + length)
+            .ok_or_else(|| self.error_at(self.offset, ParseErrorKind::UnexpectedEof))?;
+        self.offset += length;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, ClassParseError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, ClassParseError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, ClassParseError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+/// The offset of each constant pool entry's tag, so entries can be resolved on demand.
+/// Unused slots hold 0, which is never a valid offset.
+struct ConstantOffsets<'a> {
+    bytes: &'a [u8],
+    offsets: Vec<usize>,
+}
+
+fn scan_constant_pool<'a>(
+    scanner: &mut Scanner<'a>,
+    constant_pool_count: u16,
+) -> Result<ConstantOffsets<'a>, ClassParseError> {
+    let constant_pool_count = constant_pool_count as usize;
+    let mut offsets: Vec<usize> = vec![0; constant_pool_count];
+    let mut index = 1;
+    while index < constant_pool_count {
+        scanner.enter("constant_pool", Some(index as u16));
+        offsets[index] = scanner.offset;
+        let tag = scanner.u8()?;
+        let length = match tag {
+            CONSTANT_POOL_UTF8 => scanner.u16()? as usize,
+            CONSTANT_POOL_CLASS
+            | CONSTANT_POOL_STRING
+            | CONSTANT_POOL_METHOD_TYPE
+            | CONSTANT_POOL_MODULE
+            | CONSTANT_POOL_PACKAGE => 2,
+            CONSTANT_POOL_METHOD_HANDLE => 3,
+            CONSTANT_POOL_INTEGER
+            | CONSTANT_POOL_FLOAT
+            | CONSTANT_POOL_FIELDREF
+            | CONSTANT_POOL_METHODREF
+            | CONSTANT_POOL_INTERFACEMETHODREF
+            | CONSTANT_POOL_NAME_AND_TYPE
+            | CONSTANT_POOL_DYNAMIC
+            | CONSTANT_POOL_INVOKE_DYNAMIC => 4,
+            CONSTANT_POOL_LONG | CONSTANT_POOL_DOUBLE => {
+                if index + 1 == constant_pool_count {
+                    return Err(scanner.error_at(
+                        offsets[index],
+                        ParseErrorKind::InvalidConstantIndex(constant_pool_count as u16),
+                    ));
+                }
+                index += 1;
+                8
+            }
+            _ => {
+                return Err(
+                    scanner.error_at(offsets[index], ParseErrorKind::UnknownConstantTag(tag))
+                );
+            }
+        };
+        scanner.take(length)?;
+        index += 1;
+    }
+    Ok(ConstantOffsets {
+        bytes: scanner.bytes,
+        offsets,
+    })
+}
+
+impl ConstantOffsets<'_> {
+    /// The tag offset of the entry at `index`, which was read at `index_offset`, if that
+    /// entry has tag `tag`.
+    fn entry(
+        &self,
+        scanner: &Scanner,
+        index: u16,
+        index_offset: usize,
+        tag: u8,
+    ) -> Result<usize, ClassParseError> {
+        let offset = self.offsets.get(index as usize).copied().unwrap_or(0);
+        if offset == 0 {
+            return Err(scanner.error_at(index_offset, ParseErrorKind::InvalidConstantIndex(index)));
+        }
+        if self.bytes[offset] != tag {
+            let kind = match tag {
+                CONSTANT_POOL_UTF8 => ParseErrorKind::ExpectedUtf8(index),
+                _ => ParseErrorKind::ExpectedClass(index),
+            };
+            return Err(scanner.error_at(index_offset, kind));
+        }
+        Ok(offset)
+    }
+
+    fn u16_at(&self, offset: usize) -> u16 {
+        u16::from_be_bytes([self.bytes[offset], self.bytes[offset + 1]])
+    }
+
+    fn utf8_at(
+        &self,
+        scanner: &Scanner,
+        index: u16,
+        index_offset: usize,
+    ) -> Result<String, ClassParseError> {
+        let offset = self.entry(scanner, index, index_offset, CONSTANT_POOL_UTF8)?;
+        let start = offset + 3;
+        let bytes = &self.bytes[start..start + self.u16_at(offset + 1) as usize];
+        mutf8::decode_borrowed(bytes)
+            .map(|text| text.into_owned())
+            .map_err(|err| scanner.error_at(start + err.position, ParseErrorKind::InvalidUtf8))
+    }
+
+    fn class_name_at(
+        &self,
+        scanner: &Scanner,
+        index: u16,
+        index_offset: usize,
+    ) -> Result<String, ClassParseError> {
+        let offset = self.entry(scanner, index, index_offset, CONSTANT_POOL_CLASS)?;
+        self.utf8_at(scanner, self.u16_at(offset + 1), offset + 1)
+    }
+
+    /// Reads a constant pool index and resolves it to a `CONSTANT_Utf8`.
+    fn utf8(&self, scanner: &mut Scanner) -> Result<String, ClassParseError> {
+        let index_offset = scanner.offset;
+        let index = scanner.u16()?;
+        self.utf8_at(scanner, index, index_offset)
+    }
+
+    /// Reads a constant pool index and resolves it to the name of a `CONSTANT_Class`,
+    /// or to `None` if the index is 0.
+    fn optional_class_name(
+        &self,
+        scanner: &mut Scanner,
+    ) -> Result<Option<String>, ClassParseError> {
+        let index_offset = scanner.offset;
+        match scanner.u16()? {
+            0 => Ok(None),
+            index => self.class_name_at(scanner, index, index_offset).map(Some),
+        }
+    }
+
+    fn class_name(&self, scanner: &mut Scanner) -> Result<String, ClassParseError> {
+        let index_offset = scanner.offset;
+        let index = scanner.u16()?;
+        self.class_name_at(scanner, index, index_offset)
+    }
+}
+
+fn scan_members<F: From<u16>>(
+    scanner: &mut Scanner,
+    table: &'static str,
+    count: u16,
+    constant_pool: &ConstantOffsets,
+) -> Result<Vec<MemberSummary<F>>, ClassParseError> {
+    let mut members: Vec<MemberSummary<F>> = Vec::with_capacity(count as usize);
+    for index in 0..count {
+        scanner.enter(table, Some(index));
+        let access_flags = F::from(scanner.u16()?);
+        let name = constant_pool.utf8(scanner)?;
+        let descriptor = constant_pool.utf8(scanner)?;
+        skip_attributes(scanner)?;
+        members.push(MemberSummary {
+            access_flags,
+            name,
+            descriptor,
+        });
+    }
+    Ok(members)
+}
+
+fn skip_attributes(scanner: &mut Scanner) -> Result<(), ClassParseError> {
+    let attributes_count = scanner.u16()?;
+    for _ in 0..attributes_count {
+        scanner.take(2)?;
+        let attribute_length = scanner.u32()?;
+        scanner.take(attribute_length as usize)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classfile::testing::{u16_bytes, ClassBuilder};
+
+    #[test]
+    fn summarizes_names_and_typed_flags() {
+        let mut builder = ClassBuilder::new("pkg/Sample");
+        builder.interface("java/lang/Runnable");
+        builder.field(0x0042, "count", "I", &[]);
+        let code = builder.code(0, 1, &[0xB1], &[], &[]);
+        builder.method(0x0021, "run", "()V", &[code]);
+        let source_file = builder.utf8("Sample.java");
+        let source_file = builder.attribute("SourceFile", &u16_bytes(&[source_file]));
+        builder.class_attribute(source_file);
+
+        let summary = ClassSummary::scan(&builder.build()).unwrap();
+        assert_eq!(summary.this_class, "pkg/Sample");
+        assert_eq!(summary.super_class.as_deref(), Some("java/lang/Object"));
+        assert_eq!(summary.interfaces, ["java/lang/Runnable"]);
+        let field = &summary.fields[0];
+        assert_eq!(
+            (field.name.as_str(), field.descriptor.as_str()),
+            ("count", "I")
+        );
+        assert!(field.access_flags.is_volatile());
+        assert_eq!(field.access_flags.to_string(), "private volatile");
+        let method = &summary.methods[0];
+        assert!(method.access_flags.is_synchronized());
+        assert_eq!(method.access_flags.to_string(), "public synchronized");
+    }
+
+    #[test]
+    fn summarizes_a_class_compiled_by_javac() {
+        let bytes = include_bytes!("../../testdata/Members.class");
+        let summary = ClassSummary::scan(bytes).unwrap();
+        assert_eq!(summary.version, ClassVersion::new(61, 0));
+        assert_eq!(summary.this_class, "Members");
+        assert_eq!(summary.interfaces, ["java/lang/Runnable"]);
+        let fields: Vec<(&str, &str)> = summary
+            .fields
+            .iter()
+            .map(|field| (field.name.as_str(), field.descriptor.as_str()))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                ("INITIALISER", "Ljava/lang/Runnable;"),
+                ("index", "Ljava/util/Map;")
+            ]
+        );
+        let methods: Vec<&str> = summary
+            .methods
+            .iter()
+            .map(|method| method.name.as_str())
+            .collect();
+        assert_eq!(methods, ["<init>", "names", "parse", "first", "<clinit>"]);
+    }
+
+    #[test]
+    fn truncation_is_reported_in_the_member_table() {
+        let mut builder = ClassBuilder::new("Sample");
+        builder.field(0x0002, "count", "I", &[]);
+        let bytes = builder.build();
+        let field = builder.constant_pool_end() + 8;
+        let err = ClassSummary::scan(&bytes[..field + 3]).unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::UnexpectedEof));
+        assert_eq!(err.offset, field + 2);
+        assert_eq!(err.path, "fields[0]");
+    }
+}
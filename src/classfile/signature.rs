@@ -0,0 +1,790 @@
+//! Generic signatures from the Signature attribute (JVMS §4.7.9.1). They record the
+//! generic types that descriptors erase: `<K:Ljava/lang/Comparable<TK;>;V:Ljava/lang/Object;>`
+//! is `<K extends Comparable<K>, V>`.
+
+use std::fmt::{self, Write};
+
+use crate::classfile::descriptor::{internal_to_binary_name, BaseType, MAX_ARRAY_DIMENSIONS};
+
+const JAVA_LANG_OBJECT: &str = "java/lang/Object";
+
+/// How deeply type arguments may nest, e.g. 2 for `List<List<String>>`. Well beyond what
+/// source code produces, and low enough that parsing and rendering cannot exhaust the
+/// stack.
+pub const MAX_TYPE_ARGUMENT_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum JavaTypeSignature {
+    Base(BaseType),
+    Reference(ReferenceTypeSignature),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ReferenceTypeSignature {
+    Class(ClassTypeSignature),
+    /// A type variable by name, e.g. `T` for `TT;`.
+    TypeVariable(String),
+    Array(Box<JavaTypeSignature>),
+}
+
+/// A possibly parameterized class type. A type nested in a parameterized type keeps
+/// each level separate: `Outer<String>.Inner<Integer>` has two entries in `classes`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ClassTypeSignature {
+    /// In internal form without the trailing `/`, e.g. `java/util`; empty for the
+    /// unnamed package.
+    pub package: String,
+    pub classes: Vec<SimpleClassTypeSignature>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SimpleClassTypeSignature {
+    pub name: String,
+    pub type_arguments: Vec<TypeArgument>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum TypeArgument {
+    /// `?`
+    Any,
+    Exact(ReferenceTypeSignature),
+    /// `? extends`
+    Extends(ReferenceTypeSignature),
+    /// `? super`
+    Super(ReferenceTypeSignature),
+}
+
+/// A type parameter with its bounds. The class bound is absent when only interface
+/// bounds are given, as in `<T extends Comparable<T>>`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TypeParameter {
+    pub name: String,
+    pub class_bound: Option<ReferenceTypeSignature>,
+    pub interface_bounds: Vec<ReferenceTypeSignature>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ClassSignature {
+    pub type_parameters: Vec<TypeParameter>,
+    pub superclass: ClassTypeSignature,
+    pub interfaces: Vec<ClassTypeSignature>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ResultSignature {
+    Void,
+    Value(JavaTypeSignature),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MethodSignature {
+    pub type_parameters: Vec<TypeParameter>,
+    pub parameters: Vec<JavaTypeSignature>,
+    pub result: ResultSignature,
+    /// Only present when a thrown type is a type variable or parameterized; otherwise
+    /// the Exceptions attribute is all there is.
+    pub throws: Vec<ReferenceTypeSignature>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FieldSignature {
+    pub field_type: ReferenceTypeSignature,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureError {
+    /// Byte offset in the signature where the problem was found.
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid signature at byte {}: {}",
+            self.position, self.message
+        )
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+impl ClassSignature {
+    pub fn parse(signature: &str) -> Result<ClassSignature, SignatureError> {
+        let mut parser = Parser::new(signature);
+        let type_parameters = parser.type_parameters()?;
+        let superclass = parser.class_type()?;
+        let mut interfaces: Vec<ClassTypeSignature> = vec![];
+        while !parser.at_end() {
+            interfaces.push(parser.class_type()?);
+        }
+        Ok(ClassSignature {
+            type_parameters,
+            superclass,
+            interfaces,
+        })
+    }
+
+    /// Renders the class header as in Java source with simple names, e.g.
+    /// `HashMap<K, V> extends AbstractMap<K, V> implements Map<K, V>`. An
+    /// `extends Object` is left out. Interfaces list their superinterfaces here too, so
+    /// callers that know they have an interface should word it themselves.
+    pub fn to_java(&self, name: &str) -> String {
+        self.render(name, false)
+    }
+
+    /// Like [`ClassSignature::to_java`] with fully qualified names.
+    pub fn to_java_qualified(&self, name: &str) -> String {
+        self.render(name, true)
+    }
+
+    fn render(&self, name: &str, qualified: bool) -> String {
+        let mut java = String::from(name);
+        write_type_parameters(&mut java, &self.type_parameters, qualified);
+        if !self.superclass.is_object() {
+            java.push_str(" extends ");
+            self.superclass.write(&mut java, qualified);
+        }
+        for (index, interface) in self.interfaces.iter().enumerate() {
+            java.push_str(if index == 0 { " implements " } else { ", " });
+            interface.write(&mut java, qualified);
+        }
+        java
+    }
+}
+
+impl MethodSignature {
+    pub fn parse(signature: &str) -> Result<MethodSignature, SignatureError> {
+        let mut parser = Parser::new(signature);
+        let type_parameters = parser.type_parameters()?;
+        parser.expect(b'(')?;
+        let mut parameters: Vec<JavaTypeSignature> = vec![];
+        while !parser.eat(b')') {
+            parameters.push(parser.java_type()?);
+        }
+        let result = if parser.eat(b'V') {
+            ResultSignature::Void
+        } else {
+            ResultSignature::Value(parser.java_type()?)
+        };
+        let mut throws: Vec<ReferenceTypeSignature> = vec![];
+        while parser.eat(b'^') {
+            let thrown = match parser.peek() {
+                Some(b'L') => ReferenceTypeSignature::Class(parser.class_type()?),
+                Some(b'T') => parser.type_variable()?,
+                _ => return Err(parser.unexpected("a class type or type variable")),
+            };
+            throws.push(thrown);
+        }
+        parser.expect_end()?;
+        Ok(MethodSignature {
+            type_parameters,
+            parameters,
+            result,
+            throws,
+        })
+    }
+
+    /// Renders the method as declared in Java source with simple names, e.g.
+    /// `<T extends Comparable<? super T>> void sort(List<T>)`.
+    pub fn to_java(&self, name: &str) -> String {
+        self.render(name, false)
+    }
+
+    /// Like [`MethodSignature::to_java`] with fully qualified names.
+    pub fn to_java_qualified(&self, name: &str) -> String {
+        self.render(name, true)
+    }
+
+    fn render(&self, name: &str, qualified: bool) -> String {
+        let mut java = String::new();
+        if !self.type_parameters.is_empty() {
+            write_type_parameters(&mut java, &self.type_parameters, qualified);
+            java.push(' ');
+        }
+        match &self.result {
+            ResultSignature::Void => java.push_str("void"),
+            ResultSignature::Value(result) => result.write(&mut java, qualified),
+        }
+        let _ = write!(java, " {name}(");
+        for (index, parameter) in self.parameters.iter().enumerate() {
+            if index > 0 {
+                java.push_str(", ");
+            }
+            parameter.write(&mut java, qualified);
+        }
+        java.push(')');
+        for (index, thrown) in self.throws.iter().enumerate() {
+            java.push_str(if index == 0 { " throws " } else { ", " });
+            thrown.write(&mut java, qualified);
+        }
+        java
+    }
+}
+
+impl FieldSignature {
+    pub fn parse(signature: &str) -> Result<FieldSignature, SignatureError> {
+        let mut parser = Parser::new(signature);
+        let field_type = parser.reference_type()?;
+        parser.expect_end()?;
+        Ok(FieldSignature { field_type })
+    }
+
+    /// The field type as written in Java source with simple names, e.g.
+    /// `Map<K, List<? super V>>`.
+    pub fn to_java(&self) -> String {
+        self.field_type.to_java()
+    }
+}
+
+impl JavaTypeSignature {
+    fn write(&self, java: &mut String, qualified: bool) {
+        match self {
+            JavaTypeSignature::Base(base_type) => java.push_str(base_type.keyword()),
+            JavaTypeSignature::Reference(reference) => reference.write(java, qualified),
+        }
+    }
+
+    pub fn to_java(&self) -> String {
+        let mut java = String::new();
+        self.write(&mut java, false);
+        java
+    }
+}
+
+impl ReferenceTypeSignature {
+    fn write(&self, java: &mut String, qualified: bool) {
+        match self {
+            ReferenceTypeSignature::Class(class) => class.write(java, qualified),
+            ReferenceTypeSignature::TypeVariable(name) => java.push_str(name),
+            ReferenceTypeSignature::Array(component) => {
+                component.write(java, qualified);
+                java.push_str("[]");
+            }
+        }
+    }
+
+    pub fn to_java(&self) -> String {
+        let mut java = String::new();
+        self.write(&mut java, false);
+        java
+    }
+}
+
+impl ClassTypeSignature {
+    /// The erased class in internal form, e.g. `java/util/Map$Entry` for
+    /// `java.util.Map<K, V>.Entry`.
+    pub fn internal_name(&self) -> String {
+        let classes: Vec<&str> = self
+            .classes
+            .iter()
+            .map(|class| class.name.as_str())
+            .collect();
+        let classes = classes.join("$");
+        if self.package.is_empty() {
+            classes
+        } else {
+            format!("{}/{classes}", self.package)
+        }
+    }
+
+    fn is_object(&self) -> bool {
+        matches!(self.classes.as_slice(), [class] if class.type_arguments.is_empty())
+            && self.internal_name() == JAVA_LANG_OBJECT
+    }
+
+    fn write(&self, java: &mut String, qualified: bool) {
+        if qualified && !self.package.is_empty() {
+            java.push_str(&internal_to_binary_name(&self.package));
+            java.push('.');
+        }
+        for (index, class) in self.classes.iter().enumerate() {
+            if index > 0 {
+                java.push('.');
+            }
+            java.push_str(&class.name);
+            if !class.type_arguments.is_empty() {
+                java.push('<');
+                for (index, argument) in class.type_arguments.iter().enumerate() {
+                    if index > 0 {
+                        java.push_str(", ");
+                    }
+                    argument.write(java, qualified);
+                }
+                java.push('>');
+            }
+        }
+    }
+
+    pub fn to_java(&self) -> String {
+        let mut java = String::new();
+        self.write(&mut java, false);
+        java
+    }
+}
+
+impl TypeArgument {
+    fn write(&self, java: &mut String, qualified: bool) {
+        match self {
+            TypeArgument::Any => java.push('?'),
+            TypeArgument::Exact(bound) => bound.write(java, qualified),
+            TypeArgument::Extends(bound) => {
+                java.push_str("? extends ");
+                bound.write(java, qualified);
+            }
+            TypeArgument::Super(bound) => {
+                java.push_str("? super ");
+                bound.write(java, qualified);
+            }
+        }
+    }
+}
+
+impl TypeParameter {
+    /// The bounds in declaration order: the class bound, if any, then the interfaces.
+    pub fn bounds(&self) -> impl Iterator<Item = &ReferenceTypeSignature> {
+        self.class_bound.iter().chain(&self.interface_bounds)
+    }
+
+    fn write(&self, java: &mut String, qualified: bool) {
+        java.push_str(&self.name);
+        // A lone `extends Object` is what javac writes for an unbounded parameter.
+        let unbounded = self.interface_bounds.is_empty()
+            && matches!(&self.class_bound, Some(ReferenceTypeSignature::Class(class)) if class.is_object());
+        if unbounded {
+            return;
+        }
+        for (index, bound) in self.bounds().enumerate() {
+            java.push_str(if index == 0 { " extends " } else { " & " });
+            bound.write(java, qualified);
+        }
+    }
+}
+
+fn write_type_parameters(java: &mut String, type_parameters: &[TypeParameter], qualified: bool) {
+    if type_parameters.is_empty() {
+        return;
+    }
+    java.push('<');
+    for (index, type_parameter) in type_parameters.iter().enumerate() {
+        if index > 0 {
+            java.push_str(", ");
+        }
+        type_parameter.write(java, qualified);
+    }
+    java.push('>');
+}
+
+macro_rules! display_as_java {
+    ($($name:ident),*) => {
+        $(
+            /// Writes the type as in Java source with fully qualified names.
+            impl fmt::Display for $name {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    let mut java = String::new();
+                    self.write(&mut java, true);
+                    f.write_str(&java)
+                }
+            }
+        )*
+    };
+}
+
+display_as_java!(
+    JavaTypeSignature,
+    ReferenceTypeSignature,
+    ClassTypeSignature,
+    TypeArgument,
+    TypeParameter
+);
+
+impl fmt::Display for FieldSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.field_type)
+    }
+}
+
+struct Parser<'s> {
+    signature: &'s str,
+    position: usize,
+    /// Type argument lists entered and not yet closed.
+    depth: usize,
+}
+
+impl<'s> Parser<'s> {
+    fn new(signature: &'s str) -> Parser<'s> {
+        Parser {
+            signature,
+            position: 0,
+            depth: 0,
+        }
+    }
+
+    fn error(&self, message: String) -> SignatureError {
+        SignatureError {
+            position: self.position,
+            message,
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> SignatureError {
+        match self.signature[self.position..].chars().next() {
+            Some(found) => self.error(format!("expected {expected}, found {found:?}")),
+            None => self.error(format!("expected {expected}, found the end")),
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.signature.as_bytes().get(self.position).copied()
+    }
+
+    fn at_end(&self) -> bool {
+        self.position == self.signature.len()
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let matches = self.peek() == Some(byte);
+        if matches {
+            self.position += 1;
+        }
+        matches
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), SignatureError> {
+        if self.eat(byte) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("{:?}", byte as char)))
+        }
+    }
+
+    fn expect_end(&self) -> Result<(), SignatureError> {
+        if self.at_end() {
+            Ok(())
+        } else {
+            Err(self.unexpected("the end"))
+        }
+    }
+
+    /// An identifier, which may contain any character that is not part of the
+    /// signature syntax. `allow_slash` admits the package prefix of a class type.
+    fn identifier(&mut self, allow_slash: bool) -> Result<&'s str, SignatureError> {
+        let start = self.position;
+        let rest = &self.signature[start..];
+        let length = rest
+            .find(|c: char| {
+                matches!(c, '.' | ';' | '[' | '<' | '>' | ':') || c == '/' && !allow_slash
+            })
+            .unwrap_or(rest.len());
+        if length == 0 {
+            return Err(self.unexpected("an identifier"));
+        }
+        self.position += length;
+        Ok(&rest[..length])
+    }
+
+    fn type_parameters(&mut self) -> Result<Vec<TypeParameter>, SignatureError> {
+        let mut type_parameters: Vec<TypeParameter> = vec![];
+        if !self.eat(b'<') {
+            return Ok(type_parameters);
+        }
+        loop {
+            let name = self.identifier(false)?.to_string();
+            self.expect(b':')?;
+            let class_bound = match self.peek() {
+                Some(b':') => None,
+                _ => Some(self.reference_type()?),
+            };
+            let mut interface_bounds: Vec<ReferenceTypeSignature> = vec![];
+            while self.eat(b':') {
+                interface_bounds.push(self.reference_type()?);
+            }
+            type_parameters.push(TypeParameter {
+                name,
+                class_bound,
+                interface_bounds,
+            });
+            if self.eat(b'>') {
+                return Ok(type_parameters);
+            }
+        }
+    }
+
+    fn java_type(&mut self) -> Result<JavaTypeSignature, SignatureError> {
+        if let Some(base_type) = self.peek().and_then(BaseType::from_descriptor) {
+            self.position += 1;
+            return Ok(JavaTypeSignature::Base(base_type));
+        }
+        self.reference_type().map(JavaTypeSignature::Reference)
+    }
+
+    fn reference_type(&mut self) -> Result<ReferenceTypeSignature, SignatureError> {
+        match self.peek() {
+            Some(b'L') => self.class_type().map(ReferenceTypeSignature::Class),
+            Some(b'T') => self.type_variable(),
+            Some(b'[') => {
+                let start = self.position;
+                let dimensions = self.signature[start..]
+                    .bytes()
+                    .take_while(|&byte| byte == b'[')
+                    .count();
+                if dimensions > MAX_ARRAY_DIMENSIONS {
+                    return Err(self.error("more than 255 array dimensions".to_string()));
+                }
+                self.position += dimensions;
+                let mut component = self.java_type()?;
+                for _ in 1..dimensions {
+                    component = JavaTypeSignature::Reference(ReferenceTypeSignature::Array(
+                        Box::new(component),
+                    ));
+                }
+                Ok(ReferenceTypeSignature::Array(Box::new(component)))
+            }
+            _ => Err(self.unexpected("a reference type")),
+        }
+    }
+
+    fn type_variable(&mut self) -> Result<ReferenceTypeSignature, SignatureError> {
+        self.expect(b'T')?;
+        let name = self.identifier(false)?.to_string();
+        self.expect(b';')?;
+        Ok(ReferenceTypeSignature::TypeVariable(name))
+    }
+
+    fn class_type(&mut self) -> Result<ClassTypeSignature, SignatureError> {
+        self.expect(b'L')?;
+        let qualified_name = self.identifier(true)?;
+        let (package, name) = match qualified_name.rsplit_once('/') {
+            Some((package, name)) => (package, name),
+            None => ("", qualified_name),
+        };
+        if name.is_empty() || !package.is_empty() && package.split('/').any(str::is_empty) {
+            return Err(self.error(format!("malformed class name {qualified_name:?}")));
+        }
+        let mut classes = vec![SimpleClassTypeSignature {
+            name: name.to_string(),
+            type_arguments: self.type_arguments()?,
+        }];
+        while self.eat(b'.') {
+            let name = self.identifier(false)?.to_string();
+            classes.push(SimpleClassTypeSignature {
+                name,
+                type_arguments: self.type_arguments()?,
+            });
+        }
+        self.expect(b';')?;
+        Ok(ClassTypeSignature {
+            package: package.to_string(),
+            classes,
+        })
+    }
+
+    fn type_arguments(&mut self) -> Result<Vec<TypeArgument>, SignatureError> {
+        let mut type_arguments: Vec<TypeArgument> = vec![];
+        if self.peek() != Some(b'<') {
+            return Ok(type_arguments);
+        }
+        if self.depth == MAX_TYPE_ARGUMENT_DEPTH {
+            return Err(self.error(format!(
+                "type arguments nested more than {MAX_TYPE_ARGUMENT_DEPTH} deep"
+            )));
+        }
+        self.position += 1;
+        self.depth += 1;
+        loop {
+            let type_argument = match self.peek() {
+                Some(b'*') => {
+                    self.position += 1;
+                    TypeArgument::Any
+                }
+                Some(b'+') => {
+                    self.position += 1;
+                    TypeArgument::Extends(self.reference_type()?)
+                }
+                Some(b'-') => {
+                    self.position += 1;
+                    TypeArgument::Super(self.reference_type()?)
+                }
+                _ => TypeArgument::Exact(self.reference_type()?),
+            };
+            type_arguments.push(type_argument);
+            if self.eat(b'>') {
+                self.depth -= 1;
+                return Ok(type_arguments);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn class_signatures_parse_and_render() {
+        let signature = ClassSignature::parse(
+            "<K:Ljava/lang/Object;V:Ljava/lang/Object;>Ljava/util/AbstractMap<TK;TV;>;\
+             Ljava/util/Map<TK;TV;>;Ljava/io/Serializable;",
+        )
+        .unwrap();
+        assert_eq!(signature.type_parameters.len(), 2);
+        assert_eq!(
+            signature.superclass.internal_name(),
+            "java/util/AbstractMap"
+        );
+        assert_eq!(
+            signature.to_java("HashMap"),
+            "HashMap<K, V> extends AbstractMap<K, V> implements Map<K, V>, Serializable"
+        );
+        assert_eq!(
+            signature.to_java_qualified("HashMap"),
+            "HashMap<K, V> extends java.util.AbstractMap<K, V> \
+             implements java.util.Map<K, V>, java.io.Serializable"
+        );
+
+        let signature = ClassSignature::parse(
+            "<T::Ljava/lang/Comparable<-TT;>;:Ljava/io/Serializable;>Ljava/lang/Object;",
+        )
+        .unwrap();
+        let type_parameter = &signature.type_parameters[0];
+        assert_eq!(type_parameter.class_bound, None);
+        assert_eq!(type_parameter.bounds().count(), 2);
+        assert_eq!(
+            signature.to_java("Sorted"),
+            "Sorted<T extends Comparable<? super T> & Serializable>"
+        );
+    }
+
+    #[test]
+    fn method_signatures_parse_and_render() {
+        let signature =
+            MethodSignature::parse("<T::Ljava/lang/Comparable<-TT;>;>(Ljava/util/List<TT;>;[I)V")
+                .unwrap();
+        assert_eq!(signature.parameters.len(), 2);
+        assert_eq!(signature.result, ResultSignature::Void);
+        assert_eq!(
+            signature.to_java("sort"),
+            "<T extends Comparable<? super T>> void sort(List<T>, int[])"
+        );
+
+        let signature = MethodSignature::parse(
+            "<E:Ljava/lang/Exception;>()Ljava/util/List<+Ljava/lang/Number;>;^TE;^Ljava/io/IOException;",
+        )
+        .unwrap();
+        assert_eq!(
+            signature.throws[0],
+            ReferenceTypeSignature::TypeVariable("E".to_string())
+        );
+        assert_eq!(
+            signature.to_java("load"),
+            "<E extends Exception> List<? extends Number> load() throws E, IOException"
+        );
+        assert_eq!(
+            signature.to_java_qualified("load"),
+            "<E extends java.lang.Exception> java.util.List<? extends java.lang.Number> load() \
+             throws E, java.io.IOException"
+        );
+    }
+
+    #[test]
+    fn field_signatures_parse_and_render() {
+        let signature =
+            FieldSignature::parse("Ljava/util/Map<TK;Ljava/util/List<*>;>.Entry<TK;[[TV;>;")
+                .unwrap();
+        let ReferenceTypeSignature::Class(class) = &signature.field_type else {
+            panic!("expected a class type");
+        };
+        assert_eq!(class.package, "java/util");
+        assert_eq!(class.classes.len(), 2);
+        assert_eq!(class.internal_name(), "java/util/Map$Entry");
+        assert_eq!(signature.to_java(), "Map<K, List<?>>.Entry<K, V[][]>");
+        assert_eq!(
+            signature.to_string(),
+            "java.util.Map<K, java.util.List<?>>.Entry<K, V[][]>"
+        );
+
+        let signature = FieldSignature::parse("TT;").unwrap();
+        assert_eq!(signature.to_java(), "T");
+        let signature = FieldSignature::parse("LOuter$Inner;").unwrap();
+        assert_eq!(signature.to_string(), "Outer$Inner");
+    }
+
+    #[test]
+    fn array_dimensions_are_limited() {
+        let signature = format!("{}I", "[".repeat(255));
+        let field_type = FieldSignature::parse(&signature).unwrap().field_type;
+        assert_eq!(field_type.to_java(), format!("int{}", "[]".repeat(255)));
+
+        let err = FieldSignature::parse(&format!("[{signature}")).unwrap_err();
+        assert_eq!(err.position, 0);
+        assert_eq!(err.message, "more than 255 array dimensions");
+        let err = FieldSignature::parse(&format!("{}I", "[".repeat(2_000_000))).unwrap_err();
+        assert_eq!(err.message, "more than 255 array dimensions");
+        let err = MethodSignature::parse(&format!("(I[{signature})V")).unwrap_err();
+        assert_eq!(err.position, 2);
+    }
+
+    #[test]
+    fn type_argument_nesting_is_limited() {
+        let nested = |depth: usize| {
+            format!(
+                "{}Ljava/lang/String;{}",
+                "Ljava/util/List<".repeat(depth),
+                ">;".repeat(depth)
+            )
+        };
+        let signature = FieldSignature::parse(&nested(MAX_TYPE_ARGUMENT_DEPTH)).unwrap();
+        assert!(signature.to_java().starts_with("List<List<"));
+
+        let err = FieldSignature::parse(&nested(MAX_TYPE_ARGUMENT_DEPTH + 1)).unwrap_err();
+        assert_eq!(err.message, "type arguments nested more than 64 deep");
+        assert_eq!(
+            err.position,
+            MAX_TYPE_ARGUMENT_DEPTH * "<Ljava/util/List".len() + "Ljava/util/List".len()
+        );
+        let err = FieldSignature::parse(&nested(1_000_000)).unwrap_err();
+        assert_eq!(err.message, "type arguments nested more than 64 deep");
+
+        // Closed lists do not count towards the depth of the ones that follow.
+        let siblings = format!(
+            "Ljava/util/Map<{}{}>;",
+            nested(MAX_TYPE_ARGUMENT_DEPTH - 1),
+            nested(MAX_TYPE_ARGUMENT_DEPTH - 1)
+        );
+        assert!(FieldSignature::parse(&siblings).is_ok());
+    }
+
+    #[test]
+    fn rejects_malformed_signatures() {
+        let cases = [
+            ("", 0, "expected a reference type, found the end"),
+            ("I", 0, "expected a reference type, found 'I'"),
+            (
+                "Ljava/util/List<>;",
+                16,
+                "expected a reference type, found '>'",
+            ),
+            ("Ljava//List;", 11, "malformed class name \"java//List\""),
+            ("TT", 2, "expected ';', found the end"),
+            ("TT;TU;", 3, "expected the end, found 'T'"),
+        ];
+        for (signature, position, message) in cases {
+            let err = FieldSignature::parse(signature).unwrap_err();
+            assert_eq!((err.position, err.message.as_str()), (position, message));
+        }
+        let err = MethodSignature::parse("()V^I").unwrap_err();
+        assert_eq!(err.position, 4);
+        assert!(ClassSignature::parse("<T>Ljava/lang/Object;").is_err());
+    }
+}
@@ -0,0 +1,471 @@
+//! Source maps (SMAPs) from JSR-45, "Debugging Support for Other Languages", as stored in
+//! the SourceDebugExtension attribute by JSP compilers and by Kotlin for inline functions.
+//!
+//! An SMAP maps lines of the generated class back to lines of the original sources, once
+//! for each *stratum* (source language level):
+//!
+//! ```text
+//! SMAP
+//! Foo.kt                      output file
+//! Kotlin                      default stratum
+//! *S Kotlin                   stratum section
+//! *F                          file section: `[+ ]<id> <name>`, `+` adds a path line
+//! + 1 Foo.kt
+//! com/example/Foo.kt
+//! *L                          line section
+//! 1#1,20:1                    `<input>[#<file>][,<repeat>]:<output>[,<increment>]`
+//! *E
+//! ```
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Smap {
+    pub output_file: String,
+    pub default_stratum: String,
+    pub strata: Vec<Stratum>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Stratum {
+    pub id: String,
+    pub files: Vec<SourceFileInfo>,
+    pub lines: Vec<LineInfo>,
+}
+
+/// An entry of a file section. `path` is the optional full path given on the line
+/// following a `+` entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SourceFileInfo {
+    pub id: u32,
+    pub name: String,
+    pub path: Option<String>,
+}
+
+/// One line section entry: `repeat_count` consecutive input lines starting at
+/// `input_start_line`, where input line `input_start_line + i` produced the output lines
+/// `output_start_line + i * output_line_increment` up to (but excluding)
+/// `output_start_line + (i + 1) * output_line_increment`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LineInfo {
+    pub input_start_line: u32,
+    pub file_id: u32,
+    pub repeat_count: u32,
+    pub output_start_line: u32,
+    pub output_line_increment: u32,
+}
+
+/// Where an output line came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceLocation<'s> {
+    pub file: &'s SourceFileInfo,
+    pub line: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmapError {
+    /// 1-based line of the SMAP text where the problem was found.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SmapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid SMAP at line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for SmapError {}
+
+impl Smap {
+    /// Parses SMAP text. Vendor sections and other unknown sections are skipped; embedded
+    /// SMAPs (`*O` ... `*C`), which only exist before a compiler resolves them, are
+    /// rejected.
+    pub fn parse(text: &str) -> Result<Smap, SmapError> {
+        let mut lines = text
+            .lines()
+            .map(|line| line.trim_end_matches('\r'))
+            .enumerate()
+            .map(|(index, line)| (index + 1, line));
+        let mut next_header = |line: usize, what: &str| match lines.next() {
+            Some((_, text)) => Ok(text.to_string()),
+            None => Err(SmapError {
+                line,
+                message: format!("missing {what}"),
+            }),
+        };
+        let header = next_header(1, "SMAP header")?;
+        if header != "SMAP" {
+            return Err(SmapError {
+                line: 1,
+                message: format!("expected \"SMAP\", found {header:?}"),
+            });
+        }
+        let output_file = next_header(2, "output file name")?;
+        let default_stratum = next_header(3, "default stratum")?;
+
+        let mut strata: Vec<Stratum> = vec![];
+        let mut section = Section::Other;
+        let mut file_id: u32 = 0;
+        while let Some((line_number, line)) = lines.next() {
+            let error = |message: String| SmapError {
+                line: line_number,
+                message,
+            };
+            if let Some(marker) = line.strip_prefix('*') {
+                let mut parts = marker.split_whitespace();
+                section = match parts.next() {
+                    Some("S") => {
+                        let id = parts
+                            .next()
+                            .ok_or_else(|| error("stratum section without an id".to_string()))?;
+                        strata.push(Stratum {
+                            id: id.to_string(),
+                            files: vec![],
+                            lines: vec![],
+                        });
+                        file_id = 0;
+                        Section::Other
+                    }
+                    Some("F") => Section::Files,
+                    Some("L") => Section::Lines,
+                    Some("E") => break,
+                    Some("O") | Some("C") => {
+                        return Err(error("embedded SMAPs are not supported".to_string()));
+                    }
+                    _ => Section::Other,
+                };
+                if matches!(section, Section::Files | Section::Lines) && strata.is_empty() {
+                    return Err(error("section outside of a stratum".to_string()));
+                }
+                continue;
+            }
+            let Some(stratum) = strata.last_mut() else {
+                continue;
+            };
+            match section {
+                Section::Files => {
+                    let (has_path, entry) = match line.strip_prefix('+') {
+                        Some(entry) => (true, entry.trim_start()),
+                        None => (false, line),
+                    };
+                    let (id, name) = entry
+                        .split_once(' ')
+                        .ok_or_else(|| error(format!("malformed file entry {line:?}")))?;
+                    let id =
+                        parse_number(id).ok_or_else(|| error(format!("bad file id {id:?}")))?;
+                    let path = if has_path {
+                        let (_, path) = lines
+                            .next()
+                            .ok_or_else(|| error(format!("missing path for file {id}")))?;
+                        Some(path.to_string())
+                    } else {
+                        None
+                    };
+                    stratum.files.push(SourceFileInfo {
+                        id,
+                        name: name.to_string(),
+                        path,
+                    });
+                }
+                Section::Lines => {
+                    let line_info = parse_line_info(line, &mut file_id)
+                        .ok_or_else(|| error(format!("malformed line entry {line:?}")))?;
+                    stratum.lines.push(line_info);
+                }
+                Section::Other => {}
+            }
+        }
+        Ok(Smap {
+            output_file,
+            default_stratum,
+            strata,
+        })
+    }
+
+    pub fn stratum(&self, id: &str) -> Option<&Stratum> {
+        self.strata.iter().find(|stratum| stratum.id == id)
+    }
+
+    /// Maps a line of the class (as found in its LineNumberTable) back to its source in
+    /// the given stratum.
+    pub fn map_line(&self, stratum: &str, output_line: u32) -> Option<SourceLocation<'_>> {
+        self.stratum(stratum)?.map_line(output_line)
+    }
+}
+
+impl Stratum {
+    pub fn file(&self, id: u32) -> Option<&SourceFileInfo> {
+        self.files.iter().find(|file| file.id == id)
+    }
+
+    /// Maps an output line to the input line that produced it, using the first matching
+    /// line section entry.
+    pub fn map_line(&self, output_line: u32) -> Option<SourceLocation<'_>> {
+        self.lines.iter().find_map(|line_info| {
+            let line = line_info.input_line(output_line)?;
+            Some(SourceLocation {
+                file: self.file(line_info.file_id)?,
+                line,
+            })
+        })
+    }
+}
+
+impl LineInfo {
+    /// The input line that produced `output_line`, if this entry covers it.
+    pub fn input_line(&self, output_line: u32) -> Option<u32> {
+        let offset = output_line.checked_sub(self.output_start_line)?;
+        if self.output_line_increment == 0 {
+            // Every input line maps onto the single output line.
+            return (offset == 0).then_some(self.input_start_line);
+        }
+        let repeat = offset / self.output_line_increment;
+        if repeat >= self.repeat_count {
+            return None;
+        }
+        self.input_start_line.checked_add(repeat)
+    }
+}
+
+enum Section {
+    Files,
+    Lines,
+    Other,
+}
+
+fn parse_number(text: &str) -> Option<u32> {
+    text.trim().parse().ok()
+}
+
+/// Parses `<input>[#<file>][,<repeat>]:<output>[,<increment>]`. An omitted file id
+/// continues the previous entry's, so `file_id` is carried between calls.
+fn parse_line_info(line: &str, file_id: &mut u32) -> Option<LineInfo> {
+    let (input, output) = line.split_once(':')?;
+    let (input, repeat_count) = match input.split_once(',') {
+        Some((input, repeat_count)) => (input, parse_number(repeat_count)?),
+        None => (input, 1),
+    };
+    let input_start_line = match input.split_once('#') {
+        Some((input, id)) => {
+            *file_id = parse_number(id)?;
+            parse_number(input)?
+        }
+        None => parse_number(input)?,
+    };
+    let (output_start_line, output_line_increment) = match output.split_once(',') {
+        Some((output, increment)) => (parse_number(output)?, parse_number(increment)?),
+        None => (parse_number(output)?, 1),
+    };
+    // The input lines an entry covers must all be representable.
+    input_start_line.checked_add(repeat_count.saturating_sub(1))?;
+    Some(LineInfo {
+        input_start_line,
+        file_id: *file_id,
+        repeat_count,
+        output_start_line,
+        output_line_increment,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KOTLIN: &str = "SMAP\n\
+        Foo.kt\n\
+        Kotlin\n\
+        *S Kotlin\n\
+        *F\n\
+        + 1 Foo.kt\n\
+        com/example/Foo.kt\n\
+        + 2 Inline.kt\n\
+        com/example/Inline.kt\n\
+        *L\n\
+        1#1,20:1\n\
+        5#2,3:40,2\n\
+        9:50\n\
+        *E\n";
+
+    #[test]
+    fn parses_files_and_lines() {
+        let smap = Smap::parse(KOTLIN).unwrap();
+        assert_eq!(smap.output_file, "Foo.kt");
+        assert_eq!(smap.default_stratum, "Kotlin");
+        let stratum = smap.stratum("Kotlin").unwrap();
+        assert_eq!(
+            stratum.files,
+            [
+                SourceFileInfo {
+                    id: 1,
+                    name: "Foo.kt".to_string(),
+                    path: Some("com/example/Foo.kt".to_string()),
+                },
+                SourceFileInfo {
+                    id: 2,
+                    name: "Inline.kt".to_string(),
+                    path: Some("com/example/Inline.kt".to_string()),
+                },
+            ]
+        );
+        assert_eq!(
+            stratum.lines[1],
+            LineInfo {
+                input_start_line: 5,
+                file_id: 2,
+                repeat_count: 3,
+                output_start_line: 40,
+                output_line_increment: 2,
+            }
+        );
+        // `9:50` names no file, so it carries over file 2 from the entry before.
+        assert_eq!(stratum.lines[2].file_id, 2);
+        assert_eq!(stratum.lines[2].repeat_count, 1);
+    }
+
+    #[test]
+    fn maps_lines_with_repeat_and_increment() {
+        let smap = Smap::parse(KOTLIN).unwrap();
+        let map = |line| {
+            smap.map_line("Kotlin", line)
+                .map(|location| (location.file.name.as_str(), location.line))
+        };
+        assert_eq!(map(1), Some(("Foo.kt", 1)));
+        assert_eq!(map(20), Some(("Foo.kt", 20)));
+        // 5#2,3:40,2 covers 40..46, two output lines for each of inputs 5, 6 and 7.
+        assert_eq!(map(40), Some(("Inline.kt", 5)));
+        assert_eq!(map(41), Some(("Inline.kt", 5)));
+        assert_eq!(map(44), Some(("Inline.kt", 7)));
+        assert_eq!(map(45), Some(("Inline.kt", 7)));
+        assert_eq!(map(46), None);
+        assert_eq!(map(50), Some(("Inline.kt", 9)));
+        assert_eq!(map(21), None);
+        assert_eq!(smap.map_line("Java", 1), None);
+    }
+
+    #[test]
+    fn files_without_a_path_and_several_strata() {
+        let smap = Smap::parse(
+            "SMAP\nindex_jsp.java\nJSP\n*S JSP\n*F\n0 index.jsp\n*L\n1,2:10,3\n*S Other\n*F\n1 x\n*E",
+        )
+        .unwrap();
+        assert_eq!(smap.strata.len(), 2);
+        let jsp = smap.stratum("JSP").unwrap();
+        assert_eq!(jsp.files[0].path, None);
+        assert_eq!(jsp.lines[0].file_id, 0);
+        assert_eq!(smap.map_line("JSP", 15).unwrap().line, 2);
+        assert!(smap.stratum("Other").unwrap().lines.is_empty());
+    }
+
+    #[test]
+    fn zero_increment_maps_onto_one_line() {
+        let line_info = LineInfo {
+            input_start_line: 3,
+            file_id: 0,
+            repeat_count: 4,
+            output_start_line: 7,
+            output_line_increment: 0,
+        };
+        assert_eq!(line_info.input_line(7), Some(3));
+        assert_eq!(line_info.input_line(8), None);
+    }
+
+    #[test]
+    fn input_lines_past_u32_do_not_overflow() {
+        let line_info = LineInfo {
+            input_start_line: u32::MAX,
+            file_id: 1,
+            repeat_count: 2,
+            output_start_line: 1,
+            output_line_increment: 1,
+        };
+        assert_eq!(line_info.input_line(1), Some(u32::MAX));
+        assert_eq!(line_info.input_line(2), None);
+
+        let text = "SMAP\nA.kt\nKotlin\n*S Kotlin\n*F\n1 A.kt\n*L\n4294967295#1,2:1\n*E";
+        assert_eq!(
+            Smap::parse(text).unwrap_err().to_string(),
+            "invalid SMAP at line 8: malformed line entry \"4294967295#1,2:1\""
+        );
+        let text = "SMAP\nA.kt\nKotlin\n*S Kotlin\n*F\n1 A.kt\n*L\n4294967295#1:1\n*E";
+        assert_eq!(
+            Smap::parse(text)
+                .unwrap()
+                .map_line("Kotlin", 1)
+                .unwrap()
+                .line,
+            u32::MAX
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        let cases = [
+            ("", 1, "missing SMAP header"),
+            ("SMAP\nA.kt", 3, "missing default stratum"),
+            ("PAMS\nA.kt\nKotlin", 1, "expected \"SMAP\", found \"PAMS\""),
+            (
+                "SMAP\nA.kt\nKotlin\n*F\n1 A.kt",
+                4,
+                "section outside of a stratum",
+            ),
+            (
+                "SMAP\nA.kt\nKotlin\n*S\n",
+                4,
+                "stratum section without an id",
+            ),
+            (
+                "SMAP\nA.kt\nKotlin\n*S K\n*F\nA.kt",
+                6,
+                "malformed file entry \"A.kt\"",
+            ),
+            (
+                "SMAP\nA.kt\nKotlin\n*S K\n*F\nx A.kt",
+                6,
+                "bad file id \"x\"",
+            ),
+            (
+                "SMAP\nA.kt\nKotlin\n*S K\n*F\n+ 1 A.kt",
+                6,
+                "missing path for file 1",
+            ),
+            (
+                "SMAP\nA.kt\nKotlin\n*S K\n*L\n1-2",
+                6,
+                "malformed line entry \"1-2\"",
+            ),
+            (
+                "SMAP\nA.kt\nKotlin\n*S K\n*L\n1#:2",
+                6,
+                "malformed line entry \"1#:2\"",
+            ),
+            (
+                "SMAP\nA.kt\nKotlin\n*O K\n",
+                4,
+                "embedded SMAPs are not supported",
+            ),
+        ];
+        for (text, line, message) in cases {
+            assert_eq!(
+                Smap::parse(text),
+                Err(SmapError {
+                    line,
+                    message: message.to_string()
+                }),
+                "{text:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn skips_vendor_sections_and_crlf() {
+        let smap = Smap::parse("SMAP\r\nA.kt\r\nKotlin\r\n*S Kotlin\r\n*V\r\nvendor data\r\n*F\r\n1 A.kt\r\n*L\r\n1#1:1\r\n*E\r\n")
+            .unwrap();
+        assert_eq!(smap.output_file, "A.kt");
+        assert_eq!(smap.map_line("Kotlin", 1).unwrap().file.name, "A.kt");
+    }
+}
@@ -0,0 +1,341 @@
+use std::io::Read;
+
+use crate::classfile::{
+    error::{ClassParseError, ParseErrorKind},
+    reader::ClassReader,
+};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StackMapFrame {
+    pub frame_type: u8,
+    pub offset_delta: u16,
+    /// The bytecode offset the frame applies to, accumulated from the offset deltas of
+    /// this and all preceding frames (JVMS §4.7.4).
+    pub bytecode_offset: u32,
+    pub kind: StackMapFrameKind,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum StackMapFrameKind {
+    Same,
+    SameLocals1StackItem {
+        stack: VerificationTypeInfo,
+    },
+    SameLocals1StackItemExtended {
+        stack: VerificationTypeInfo,
+    },
+    Chop {
+        absent_locals: u8,
+    },
+    SameExtended,
+    Append {
+        locals: Vec<VerificationTypeInfo>,
+    },
+    Full {
+        number_of_locals: u16,
+        locals: Vec<VerificationTypeInfo>,
+        number_of_stack_items: u16,
+        stack: Vec<VerificationTypeInfo>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum VerificationTypeInfo {
+    Top,
+    Integer,
+    Float,
+    Double,
+    Long,
+    Null,
+    UninitializedThis,
+    Object { cpool_index: u16 },
+    Uninitialized { offset: u16 },
+}
+
+const SAME_FRAME_MAX: u8 = 63;
+const SAME_LOCALS_1_STACK_ITEM_MAX: u8 = 127;
+const SAME_LOCALS_1_STACK_ITEM_EXTENDED: u8 = 247;
+const CHOP_FRAME_MIN: u8 = 248;
+const CHOP_FRAME_MAX: u8 = 250;
+const SAME_FRAME_EXTENDED: u8 = 251;
+const APPEND_FRAME_MAX: u8 = 254;
+const FULL_FRAME: u8 = 255;
+
+const ITEM_TOP: u8 = 0;
+const ITEM_INTEGER: u8 = 1;
+const ITEM_FLOAT: u8 = 2;
+const ITEM_DOUBLE: u8 = 3;
+const ITEM_LONG: u8 = 4;
+const ITEM_NULL: u8 = 5;
+const ITEM_UNINITIALIZED_THIS: u8 = 6;
+const ITEM_OBJECT: u8 = 7;
+const ITEM_UNINITIALIZED: u8 = 8;
+
+pub fn generate_stack_map_frames<R: Read>(
+    number_of_entries: u16,
+    reader: &mut ClassReader<R>,
+) -> Result<Vec<StackMapFrame>, ClassParseError> {
+    let mut frames: Vec<StackMapFrame> = vec![];
+    let mut previous_offset: Option<u32> = None;
+    for entry_index in 0..number_of_entries {
+        let frame = reader.scoped(format!("entries[{entry_index}]"), |reader| {
+            generate_stack_map_frame(previous_offset, reader)
+        })?;
+        previous_offset = Some(frame.bytecode_offset);
+        frames.push(frame);
+    }
+    Ok(frames)
+}
+
+fn generate_stack_map_frame<R: Read>(
+    previous_offset: Option<u32>,
+    reader: &mut ClassReader<R>,
+) -> Result<StackMapFrame, ClassParseError> {
+    let frame_type_offset = reader.offset();
+    let frame_type: u8 = reader.read_u8()?;
+    let (offset_delta, kind) = match frame_type {
+        0..=SAME_FRAME_MAX => (frame_type as u16, StackMapFrameKind::Same),
+        64..=SAME_LOCALS_1_STACK_ITEM_MAX => {
+            let stack = reader.scoped("stack[0]", generate_verification_type_info)?;
+            (
+                (frame_type - 64) as u16,
+                StackMapFrameKind::SameLocals1StackItem { stack },
+            )
+        }
+        SAME_LOCALS_1_STACK_ITEM_EXTENDED => {
+            let offset_delta: u16 = reader.read_u16()?;
+            let stack = reader.scoped("stack[0]", generate_verification_type_info)?;
+            (
+                offset_delta,
+                StackMapFrameKind::SameLocals1StackItemExtended { stack },
+            )
+        }
+        CHOP_FRAME_MIN..=CHOP_FRAME_MAX => {
+            let offset_delta: u16 = reader.read_u16()?;
+            let absent_locals = SAME_FRAME_EXTENDED - frame_type;
+            (offset_delta, StackMapFrameKind::Chop { absent_locals })
+        }
+        SAME_FRAME_EXTENDED => {
+            let offset_delta: u16 = reader.read_u16()?;
+            (offset_delta, StackMapFrameKind::SameExtended)
+        }
+        252..=APPEND_FRAME_MAX => {
+            let offset_delta: u16 = reader.read_u16()?;
+            let locals = generate_verification_types(
+                "locals",
+                (frame_type - SAME_FRAME_EXTENDED) as u16,
+                reader,
+            )?;
+            (offset_delta, StackMapFrameKind::Append { locals })
+        }
+        FULL_FRAME => {
+            let offset_delta: u16 = reader.read_u16()?;
+            let number_of_locals: u16 = reader.read_u16()?;
+            let locals = generate_verification_types("locals", number_of_locals, reader)?;
+            let number_of_stack_items: u16 = reader.read_u16()?;
+            let stack = generate_verification_types("stack", number_of_stack_items, reader)?;
+            (
+                offset_delta,
+                StackMapFrameKind::Full {
+                    number_of_locals,
+                    locals,
+                    number_of_stack_items,
+                    stack,
+                },
+            )
+        }
+        _ => {
+            return Err(reader.error_at(
+                frame_type_offset,
+                ParseErrorKind::InvalidStackMapFrameType(frame_type),
+            ));
+        }
+    };
+    let bytecode_offset = match previous_offset {
+        Some(previous_offset) => previous_offset + offset_delta as u32 + 1,
+        None => offset_delta as u32,
+    };
+    Ok(StackMapFrame {
+        frame_type,
+        offset_delta,
+        bytecode_offset,
+        kind,
+    })
+}
+
+fn generate_verification_types<R: Read>(
+    name: &str,
+    count: u16,
+    reader: &mut ClassReader<R>,
+) -> Result<Vec<VerificationTypeInfo>, ClassParseError> {
+    let mut types: Vec<VerificationTypeInfo> = vec![];
+    for type_index in 0..count {
+        types.push(reader.scoped(
+            format!("{name}[{type_index}]"),
+            generate_verification_type_info,
+        )?);
+    }
+    Ok(types)
+}
+
+fn generate_verification_type_info<R: Read>(
+    reader: &mut ClassReader<R>,
+) -> Result<VerificationTypeInfo, ClassParseError> {
+    let tag_offset = reader.offset();
+    let tag: u8 = reader.read_u8()?;
+    let verification_type = match tag {
+        ITEM_TOP => VerificationTypeInfo::Top,
+        ITEM_INTEGER => VerificationTypeInfo::Integer,
+        ITEM_FLOAT => VerificationTypeInfo::Float,
+        ITEM_DOUBLE => VerificationTypeInfo::Double,
+        ITEM_LONG => VerificationTypeInfo::Long,
+        ITEM_NULL => VerificationTypeInfo::Null,
+        ITEM_UNINITIALIZED_THIS => VerificationTypeInfo::UninitializedThis,
+        ITEM_OBJECT => VerificationTypeInfo::Object {
+            cpool_index: reader.read_u16()?,
+        },
+        ITEM_UNINITIALIZED => VerificationTypeInfo::Uninitialized {
+            offset: reader.read_u16()?,
+        },
+        _ => {
+            return Err(
+                reader.error_at(tag_offset, ParseErrorKind::InvalidVerificationTypeTag(tag))
+            );
+        }
+    };
+    Ok(verification_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(bytes: &[u8], number_of_entries: u16) -> Result<Vec<StackMapFrame>, ClassParseError> {
+        generate_stack_map_frames(number_of_entries, &mut ClassReader::new(bytes))
+    }
+
+    #[test]
+    fn offsets_accumulate_after_the_first_frame() {
+        let bytes = [&[5][..], &[3], &[SAME_FRAME_EXTENDED, 0x01, 0x00], &[0]].concat();
+        let offsets: Vec<(u16, u32)> = frames(&bytes, 4)
+            .unwrap()
+            .iter()
+            .map(|frame| (frame.offset_delta, frame.bytecode_offset))
+            .collect();
+        assert_eq!(offsets, [(5, 5), (3, 9), (256, 266), (0, 267)]);
+    }
+
+    #[test]
+    fn decodes_each_frame_type_range() {
+        let bytes = [
+            &[63][..],                         // same
+            &[64, 1],                          // same_locals_1_stack_item, Integer
+            &[127, 7, 0, 9],                   // same_locals_1_stack_item, Object #9
+            &[247, 1, 0, 8, 0, 4],             // same_locals_1_stack_item_extended, new at 4
+            &[248, 0, 1],                      // chop 3
+            &[250, 0, 2],                      // chop 1
+            &[251, 0, 3],                      // same_frame_extended
+            &[252, 0, 4, 4],                   // append Long
+            &[254, 0, 5, 0, 2, 5],             // append Top, Float, Null
+            &[255, 0, 6, 0, 2, 6, 3, 0, 1, 1], // full: this, Double; Integer
+        ]
+        .concat();
+        let frames = frames(&bytes, 10).unwrap();
+        let kinds: Vec<&StackMapFrameKind> = frames.iter().map(|frame| &frame.kind).collect();
+
+        assert!(matches!(kinds[0], StackMapFrameKind::Same));
+        assert_eq!(frames[0].offset_delta, 63);
+        assert!(matches!(
+            kinds[1],
+            StackMapFrameKind::SameLocals1StackItem {
+                stack: VerificationTypeInfo::Integer
+            }
+        ));
+        assert_eq!(frames[1].offset_delta, 0);
+        assert!(matches!(
+            kinds[2],
+            StackMapFrameKind::SameLocals1StackItem {
+                stack: VerificationTypeInfo::Object { cpool_index: 9 }
+            }
+        ));
+        assert_eq!(frames[2].offset_delta, 63);
+        assert!(matches!(
+            kinds[3],
+            StackMapFrameKind::SameLocals1StackItemExtended {
+                stack: VerificationTypeInfo::Uninitialized { offset: 4 }
+            }
+        ));
+        assert_eq!(frames[3].offset_delta, 256);
+        assert!(matches!(
+            kinds[4],
+            StackMapFrameKind::Chop { absent_locals: 3 }
+        ));
+        assert!(matches!(
+            kinds[5],
+            StackMapFrameKind::Chop { absent_locals: 1 }
+        ));
+        assert!(matches!(kinds[6], StackMapFrameKind::SameExtended));
+        match kinds[7] {
+            StackMapFrameKind::Append { locals } => {
+                assert_eq!(locals, &[VerificationTypeInfo::Long])
+            }
+            kind => panic!("expected an append frame, got {kind:?}"),
+        }
+        match kinds[8] {
+            StackMapFrameKind::Append { locals } => assert_eq!(
+                locals,
+                &[
+                    VerificationTypeInfo::Top,
+                    VerificationTypeInfo::Float,
+                    VerificationTypeInfo::Null
+                ]
+            ),
+            kind => panic!("expected an append frame, got {kind:?}"),
+        }
+        match kinds[9] {
+            StackMapFrameKind::Full { locals, stack, .. } => {
+                assert_eq!(
+                    locals,
+                    &[
+                        VerificationTypeInfo::UninitializedThis,
+                        VerificationTypeInfo::Double
+                    ]
+                );
+                assert_eq!(stack, &[VerificationTypeInfo::Integer]);
+            }
+            kind => panic!("expected a full frame, got {kind:?}"),
+        }
+        let deltas: Vec<u16> = frames.iter().map(|frame| frame.offset_delta).collect();
+        assert_eq!(deltas, [63, 0, 63, 256, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(
+            frames[9].bytecode_offset,
+            63 + 1 + 64 + 257 + 2 + 3 + 4 + 5 + 6 + 7
+        );
+    }
+
+    #[test]
+    fn reserved_frame_types_are_rejected() {
+        for frame_type in [128, 200, 246] {
+            let err = frames(&[0, frame_type], 2).unwrap_err();
+            assert!(
+                matches!(err.kind, ParseErrorKind::InvalidStackMapFrameType(found) if found == frame_type)
+            );
+            assert_eq!(err.offset, 1);
+            assert_eq!(err.path, "entries[1]");
+        }
+    }
+
+    #[test]
+    fn unknown_verification_tags_are_rejected() {
+        let err = frames(&[252, 0x00, 0x00, 9], 1).unwrap_err();
+        assert!(matches!(
+            err.kind,
+            ParseErrorKind::InvalidVerificationTypeTag(9)
+        ));
+        assert_eq!(err.offset, 3);
+        assert_eq!(err.path, "entries[0].locals[0]");
+    }
+}
@@ -0,0 +1,231 @@
+//! Builds class files byte by byte for the tests, so each one can lay out exactly the
+//! structures it needs, including broken ones.
+
+use std::collections::HashMap;
+
+use crate::classfile::constant_pool::{
+    CONSTANT_POOL_CLASS, CONSTANT_POOL_DOUBLE, CONSTANT_POOL_INTEGER, CONSTANT_POOL_LONG,
+    CONSTANT_POOL_METHODREF, CONSTANT_POOL_NAME_AND_TYPE, CONSTANT_POOL_STRING, CONSTANT_POOL_UTF8,
+};
+
+pub(crate) fn u16_bytes(values: &[u16]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|value| value.to_be_bytes())
+        .collect()
+}
+
+/// A class file under construction. Constants are appended as they are asked for, and
+/// `build` lays out the whole file.
+pub(crate) struct ClassBuilder {
+    pub(crate) major_version: u16,
+    pub(crate) access_flags: u16,
+    pub(crate) this_class: u16,
+    pub(crate) super_class: u16,
+    constant_pool: Vec<u8>,
+    constant_pool_count: u16,
+    utf8: HashMap<String, u16>,
+    interfaces: Vec<u16>,
+    fields: Vec<Vec<u8>>,
+    methods: Vec<Vec<u8>>,
+    attributes: Vec<Vec<u8>>,
+}
+
+impl ClassBuilder {
+    /// A Java 17 `public class` named `name` extending `java/lang/Object`.
+    pub(crate) fn new(name: &str) -> ClassBuilder {
+        let mut builder = ClassBuilder {
+            major_version: 61,
+            access_flags: 0x0021,
+            this_class: 0,
+            super_class: 0,
+            constant_pool: vec![],
+            constant_pool_count: 1,
+            utf8: HashMap::new(),
+            interfaces: vec![],
+            fields: vec![],
+            methods: vec![],
+            attributes: vec![],
+        };
+        builder.this_class = builder.class(name);
+        builder.super_class = builder.class("java/lang/Object");
+        builder
+    }
+
+    /// Appends a raw entry taking `slots` slots and returns its index.
+    pub(crate) fn constant(&mut self, bytes: &[u8], slots: u16) -> u16 {
+        let index = self.constant_pool_count;
+        self.constant_pool.extend_from_slice(bytes);
+        self.constant_pool_count += slots;
+        index
+    }
+
+    pub(crate) fn utf8(&mut self, value: &str) -> u16 {
+        if let Some(&index) = self.utf8.get(value) {
+            return index;
+        }
+        let encoded = crate::classfile::mutf8::encode(value);
+        let mut bytes = vec![CONSTANT_POOL_UTF8];
+        bytes.extend(u16_bytes(&[encoded.len() as u16]));
+        bytes.extend(encoded);
+        let index = self.constant(&bytes, 1);
+        self.utf8.insert(value.to_string(), index);
+        index
+    }
+
+    pub(crate) fn class(&mut self, name: &str) -> u16 {
+        let name_index = self.utf8(name);
+        let mut bytes = vec![CONSTANT_POOL_CLASS];
+        bytes.extend(u16_bytes(&[name_index]));
+        self.constant(&bytes, 1)
+    }
+
+    pub(crate) fn string(&mut self, value: &str) -> u16 {
+        let string_index = self.utf8(value);
+        let mut bytes = vec![CONSTANT_POOL_STRING];
+        bytes.extend(u16_bytes(&[string_index]));
+        self.constant(&bytes, 1)
+    }
+
+    pub(crate) fn integer(&mut self, value: i32) -> u16 {
+        let mut bytes = vec![CONSTANT_POOL_INTEGER];
+        bytes.extend(value.to_be_bytes());
+        self.constant(&bytes, 1)
+    }
+
+    pub(crate) fn long(&mut self, value: i64) -> u16 {
+        let mut bytes = vec![CONSTANT_POOL_LONG];
+        bytes.extend(value.to_be_bytes());
+        self.constant(&bytes, 2)
+    }
+
+    pub(crate) fn double(&mut self, value: f64) -> u16 {
+        let mut bytes = vec![CONSTANT_POOL_DOUBLE];
+        bytes.extend(value.to_bits().to_be_bytes());
+        self.constant(&bytes, 2)
+    }
+
+    pub(crate) fn name_and_type(&mut self, name: &str, descriptor: &str) -> u16 {
+        let name_index = self.utf8(name);
+        let descriptor_index = self.utf8(descriptor);
+        let mut bytes = vec![CONSTANT_POOL_NAME_AND_TYPE];
+        bytes.extend(u16_bytes(&[name_index, descriptor_index]));
+        self.constant(&bytes, 1)
+    }
+
+    pub(crate) fn method_ref(&mut self, owner: &str, name: &str, descriptor: &str) -> u16 {
+        let class_index = self.class(owner);
+        let name_and_type_index = self.name_and_type(name, descriptor);
+        let mut bytes = vec![CONSTANT_POOL_METHODREF];
+        bytes.extend(u16_bytes(&[class_index, name_and_type_index]));
+        self.constant(&bytes, 1)
+    }
+
+    /// An attribute named `name` with `body` as its contents, ready to be added to a
+    /// class, field, method or Code attribute.
+    pub(crate) fn attribute(&mut self, name: &str, body: &[u8]) -> Vec<u8> {
+        let mut bytes = u16_bytes(&[self.utf8(name)]);
+        bytes.extend((body.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(body);
+        bytes
+    }
+
+    /// A Code attribute. Each exception table entry is `[start_pc, end_pc, handler_pc,
+    /// catch_type]`.
+    pub(crate) fn code(
+        &mut self,
+        max_stack: u16,
+        max_locals: u16,
+        code: &[u8],
+        exception_table: &[[u16; 4]],
+        attributes: &[Vec<u8>],
+    ) -> Vec<u8> {
+        let mut body = u16_bytes(&[max_stack, max_locals]);
+        body.extend((code.len() as u32).to_be_bytes());
+        body.extend_from_slice(code);
+        body.extend(u16_bytes(&[exception_table.len() as u16]));
+        for entry in exception_table {
+            body.extend(u16_bytes(entry));
+        }
+        body.extend(table(attributes));
+        self.attribute("Code", &body)
+    }
+
+    pub(crate) fn interface(&mut self, name: &str) {
+        let index = self.class(name);
+        self.interfaces.push(index);
+    }
+
+    pub(crate) fn field(
+        &mut self,
+        access_flags: u16,
+        name: &str,
+        descriptor: &str,
+        attributes: &[Vec<u8>],
+    ) {
+        let member = self.member(access_flags, name, descriptor, attributes);
+        self.fields.push(member);
+    }
+
+    pub(crate) fn method(
+        &mut self,
+        access_flags: u16,
+        name: &str,
+        descriptor: &str,
+        attributes: &[Vec<u8>],
+    ) {
+        let member = self.member(access_flags, name, descriptor, attributes);
+        self.methods.push(member);
+    }
+
+    fn member(
+        &mut self,
+        access_flags: u16,
+        name: &str,
+        descriptor: &str,
+        attributes: &[Vec<u8>],
+    ) -> Vec<u8> {
+        let mut bytes = u16_bytes(&[access_flags, self.utf8(name), self.utf8(descriptor)]);
+        bytes.extend(table(attributes));
+        bytes
+    }
+
+    pub(crate) fn class_attribute(&mut self, attribute: Vec<u8>) {
+        self.attributes.push(attribute);
+    }
+
+    pub(crate) fn build(&self) -> Vec<u8> {
+        let mut bytes = 0xCAFEBABEu32.to_be_bytes().to_vec();
+        bytes.extend(u16_bytes(&[
+            0,
+            self.major_version,
+            self.constant_pool_count,
+        ]));
+        bytes.extend_from_slice(&self.constant_pool);
+        bytes.extend(u16_bytes(&[
+            self.access_flags,
+            self.this_class,
+            self.super_class,
+        ]));
+        bytes.extend(u16_bytes(&[self.interfaces.len() as u16]));
+        bytes.extend(u16_bytes(&self.interfaces));
+        bytes.extend(table(&self.fields));
+        bytes.extend(table(&self.methods));
+        bytes.extend(table(&self.attributes));
+        bytes
+    }
+
+    /// The offset `build` will place the access flags at, i.e. just past the constant pool.
+    pub(crate) fn constant_pool_end(&self) -> usize {
+        10 + self.constant_pool.len()
+    }
+}
+
+/// A `u2` count followed by the entries.
+fn table(entries: &[Vec<u8>]) -> Vec<u8> {
+    let mut bytes = u16_bytes(&[entries.len() as u16]);
+    for entry in entries {
+        bytes.extend_from_slice(entry);
+    }
+    bytes
+}
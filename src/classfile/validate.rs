@@ -0,0 +1,1536 @@
+//! Format checking (JVMS §4.8). Parsing only establishes that a class file has the right
+//! shape; [`ClassFile::validate`] checks the rules a JVM enforces on top of that, such as
+//! constant pool references pointing at entries of the right kind, access flags forming
+//! legal combinations and attributes appearing only where they are allowed.
+
+use std::{collections::HashSet, fmt};
+
+use crate::classfile::{
+    access_flags::{
+        ACC_ABSTRACT, ACC_ANNOTATION, ACC_BRIDGE, ACC_ENUM, ACC_FINAL, ACC_MODULE, ACC_NATIVE,
+        ACC_PRIVATE, ACC_PROTECTED, ACC_PUBLIC, ACC_STATIC, ACC_STRICT, ACC_SUPER,
+        ACC_SYNCHRONIZED, ACC_SYNTHETIC, ACC_VARARGS, ACC_VISIBILITY, ACC_VOLATILE,
+    },
+    annotations::{Annotation, ElementValue, ElementValueEnum},
+    attributes::*,
+    constant_pool::*,
+    descriptor::{is_class_name, is_unqualified_name, FieldType, MethodDescriptor},
+    fields::FieldInfo,
+    methods::Method,
+    stack_map::{StackMapFrameKind, VerificationTypeInfo},
+    version::{self, ClassVersion, FIRST_MAJOR_VERSION, PREVIEW_MINOR_VERSION},
+    ClassFile,
+};
+
+const JAVA_LANG_OBJECT: &str = "java/lang/Object";
+const MODULE_INFO: &str = "module-info";
+
+/// A broken format rule. `path` names the offending structure the same way parse errors
+/// do, e.g. `methods[2].attributes[0].Code.exception_table[1]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+impl ClassFile {
+    /// Checks the format rules of JVMS §4.8 and returns every violation, in class file
+    /// order. An empty result means the class file is well formed.
+    pub fn validate(&self) -> Vec<Violation> {
+        let mut validator = Validator {
+            class_file: self,
+            violations: vec![],
+        };
+        validator.check_class();
+        validator.violations
+    }
+}
+
+/// Where an attribute appears, for the placement rules of JVMS Table 4.7-C.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Location {
+    Class,
+    Field,
+    Method,
+    Code,
+    RecordComponent,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let structure = match self {
+            Location::Class => "a ClassFile structure",
+            Location::Field => "a field_info structure",
+            Location::Method => "a method_info structure",
+            Location::Code => "a Code attribute",
+            Location::RecordComponent => "a record_component_info structure",
+        };
+        f.write_str(structure)
+    }
+}
+
+/// Where each predefined attribute may appear (JVMS Table 4.7-C). `None` for attributes
+/// the JVMS does not define.
+fn attribute_locations(name: &str) -> Option<&'static [Location]> {
+    use Location::*;
+    let locations: &'static [Location] = match name {
+        "ConstantValue" => &[Field],
+        "Code"
+        | "Exceptions"
+        | "RuntimeVisibleParameterAnnotations"
+        | "RuntimeInvisibleParameterAnnotations"
+        | "AnnotationDefault"
+        | "MethodParameters" => &[Method],
+        "StackMapTable" | "LineNumberTable" | "LocalVariableTable" | "LocalVariableTypeTable" => {
+            &[Code]
+        }
+        "InnerClasses"
+        | "EnclosingMethod"
+        | "SourceFile"
+        | "SourceDebugExtension"
+        | "BootstrapMethods"
+        | "Module"
+        | "ModulePackages"
+        | "ModuleMainClass"
+        | "NestHost"
+        | "NestMembers"
+        | "Record"
+        | "PermittedSubclasses" => &[Class],
+        "Synthetic" | "Deprecated" => &[Class, Field, Method],
+        "Signature" | "RuntimeVisibleAnnotations" | "RuntimeInvisibleAnnotations" => {
+            &[Class, Field, Method, RecordComponent]
+        }
+        "RuntimeVisibleTypeAnnotations" | "RuntimeInvisibleTypeAnnotations" => {
+            &[Class, Field, Method, Code, RecordComponent]
+        }
+        _ => return None,
+    };
+    Some(locations)
+}
+
+/// Attributes that may legitimately appear more than once in the same structure.
+fn may_repeat(name: &str) -> bool {
+    matches!(
+        name,
+        "LineNumberTable"
+            | "LocalVariableTable"
+            | "LocalVariableTypeTable"
+            | "Synthetic"
+            | "Deprecated"
+    )
+}
+
+/// The kinds of constant a reference may be required to point at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expected {
+    Utf8,
+    Class,
+    NameAndType,
+    FieldRef,
+    MethodRef,
+    InterfaceMethodRef,
+    AnyMethodRef,
+    MethodHandle,
+    Module,
+    Package,
+    ConstantValue,
+    Loadable,
+}
+
+impl Expected {
+    fn describe(self) -> &'static str {
+        match self {
+            Expected::Utf8 => "CONSTANT_Utf8",
+            Expected::Class => "CONSTANT_Class",
+            Expected::NameAndType => "CONSTANT_NameAndType",
+            Expected::FieldRef => "CONSTANT_Fieldref",
+            Expected::MethodRef => "CONSTANT_Methodref",
+            Expected::InterfaceMethodRef => "CONSTANT_InterfaceMethodref",
+            Expected::AnyMethodRef => "CONSTANT_Methodref or CONSTANT_InterfaceMethodref",
+            Expected::MethodHandle => "CONSTANT_MethodHandle",
+            Expected::Module => "CONSTANT_Module",
+            Expected::Package => "CONSTANT_Package",
+            Expected::ConstantValue => {
+                "CONSTANT_Integer, CONSTANT_Float, CONSTANT_Long, CONSTANT_Double or CONSTANT_String"
+            }
+            Expected::Loadable => "loadable constant",
+        }
+    }
+
+    fn matches(self, constant: &Constant) -> bool {
+        match self {
+            Expected::Utf8 => matches!(constant, Constant::Utf8Info(_)),
+            Expected::Class => matches!(constant, Constant::Class(_)),
+            Expected::NameAndType => matches!(constant, Constant::NameAndType(_)),
+            Expected::FieldRef => matches!(constant, Constant::FieldRef(_)),
+            Expected::MethodRef => matches!(constant, Constant::MethodRef(_)),
+            Expected::InterfaceMethodRef => matches!(constant, Constant::InterfaceMethodRef(_)),
+            Expected::AnyMethodRef => matches!(
+                constant,
+                Constant::MethodRef(_) | Constant::InterfaceMethodRef(_)
+            ),
+            Expected::MethodHandle => matches!(constant, Constant::MethodHandle(_)),
+            Expected::Module => matches!(constant, Constant::Module(_)),
+            Expected::Package => matches!(constant, Constant::Package(_)),
+            Expected::ConstantValue => matches!(
+                constant,
+                Constant::Integer(_)
+                    | Constant::Float(_)
+                    | Constant::Long(_)
+                    | Constant::Double(_)
+                    | Constant::String(_)
+            ),
+            Expected::Loadable => matches!(
+                constant,
+                Constant::Integer(_)
+                    | Constant::Float(_)
+                    | Constant::Long(_)
+                    | Constant::Double(_)
+                    | Constant::Class(_)
+                    | Constant::String(_)
+                    | Constant::MethodHandle(_)
+                    | Constant::MethodType(_)
+                    | Constant::Dynamic(_)
+            ),
+        }
+    }
+}
+
+fn member_ref_indexes(constant: &Constant) -> Option<(u16, u16)> {
+    match constant {
+        Constant::FieldRef(field_ref) => {
+            Some((field_ref.class_index, field_ref.name_and_type_index))
+        }
+        Constant::MethodRef(method_ref) => {
+            Some((method_ref.class_index, method_ref.name_and_type_index))
+        }
+        Constant::InterfaceMethodRef(method_ref) => {
+            Some((method_ref.class_index, method_ref.name_and_type_index))
+        }
+        _ => None,
+    }
+}
+
+fn child(path: &str, segment: &str) -> String {
+    if path.is_empty() {
+        segment.to_string()
+    } else {
+        format!("{path}.{segment}")
+    }
+}
+
+fn is_method_name(name: &str) -> bool {
+    name == "<init>"
+        || name == "<clinit>"
+        || is_unqualified_name(name) && !name.contains(['<', '>'])
+}
+
+/// A module name (JVMS §4.2.3). Backslash escapes are not checked.
+fn is_module_name(name: &str) -> bool {
+    !name.is_empty() && !name.chars().any(|c| c <= '\u{1F}')
+}
+
+fn is_field_descriptor(descriptor: &str) -> bool {
+    FieldType::parse(descriptor).is_ok()
+}
+
+fn is_method_descriptor(descriptor: &str) -> bool {
+    MethodDescriptor::parse(descriptor).is_ok()
+}
+
+struct Validator<'c> {
+    class_file: &'c ClassFile,
+    violations: Vec<Violation>,
+}
+
+impl<'c> Validator<'c> {
+    fn report(&mut self, path: &str, message: impl Into<String>) {
+        self.violations.push(Violation {
+            path: path.to_string(),
+            message: message.into(),
+        });
+    }
+
+    fn major_version(&self) -> u16 {
+        self.class_file.major_version
+    }
+
+    fn is_interface(&self) -> bool {
+        self.class_file.access_flags.is_interface()
+    }
+
+    fn is_module(&self) -> bool {
+        self.class_file.access_flags.is_module()
+    }
+
+    /// The constant at `index`, or `None` if no usable entry has that index, including
+    /// the slot after a `CONSTANT_Long` or `CONSTANT_Double`.
+    fn constant(&self, index: u16) -> Option<&'c Constant> {
+        self.class_file.constant_pool.entry(index).ok()
+    }
+
+    fn utf8(&self, index: u16) -> Option<&'c str> {
+        self.class_file.constant_pool.utf8(index).ok()
+    }
+
+    fn class_name(&self, index: u16) -> Option<&'c str> {
+        self.class_file.constant_pool.class_name(index).ok()
+    }
+
+    fn name_and_type(&self, index: u16) -> Option<(&'c str, &'c str)> {
+        self.class_file.constant_pool.name_and_type(index).ok()
+    }
+
+    /// Reports `field` of the structure at `path` unless `index` refers to a constant of
+    /// the expected kind.
+    fn expect(
+        &mut self,
+        path: &str,
+        field: &str,
+        index: u16,
+        expected: Expected,
+    ) -> Option<&'c Constant> {
+        match self.constant(index) {
+            Some(constant) if expected.matches(constant) => Some(constant),
+            Some(constant) => {
+                let message = format!(
+                    "{field} #{index} should refer to a {}, not a {}",
+                    expected.describe(),
+                    constant.kind()
+                );
+                self.report(path, message);
+                None
+            }
+            None => {
+                self.report(
+                    path,
+                    format!("{field} #{index} is not a valid constant pool index"),
+                );
+                None
+            }
+        }
+    }
+
+    /// Like [`Validator::expect`], but 0 is accepted as "absent".
+    fn expect_optional(&mut self, path: &str, field: &str, index: u16, expected: Expected) {
+        if index != 0 {
+            self.expect(path, field, index, expected);
+        }
+    }
+
+    fn expect_utf8(&mut self, path: &str, field: &str, index: u16) -> Option<&'c str> {
+        self.expect(path, field, index, Expected::Utf8)?;
+        self.utf8(index)
+    }
+
+    /// Checks a name through `is_valid`, reporting it as an invalid `what`.
+    fn expect_name(
+        &mut self,
+        path: &str,
+        field: &str,
+        index: u16,
+        what: &str,
+        is_valid: fn(&str) -> bool,
+    ) -> Option<&'c str> {
+        let name = self.expect_utf8(path, field, index)?;
+        if !is_valid(name) {
+            self.report(
+                path,
+                format!("{field} #{index} {name:?} is not a valid {what}"),
+            );
+        }
+        Some(name)
+    }
+
+    fn check_class(&mut self) {
+        let class_file = self.class_file;
+        self.check_version();
+        self.check_constant_pool();
+        self.check_class_flags();
+
+        self.expect(
+            "this_class",
+            "this_class",
+            class_file.this_class,
+            Expected::Class,
+        );
+        let this_name = self.class_name(class_file.this_class);
+        if class_file.super_class == 0 {
+            if this_name != Some(JAVA_LANG_OBJECT) && !self.is_module() {
+                self.report(
+                    "super_class",
+                    "super_class is 0, but only java/lang/Object has no superclass",
+                );
+            }
+        } else if self
+            .expect(
+                "super_class",
+                "super_class",
+                class_file.super_class,
+                Expected::Class,
+            )
+            .is_some()
+        {
+            let super_name = self.class_name(class_file.super_class).unwrap_or_default();
+            if super_name.starts_with('[') {
+                self.report("super_class", "the superclass cannot be an array type");
+            } else if self.is_interface() && super_name != JAVA_LANG_OBJECT {
+                self.report(
+                    "super_class",
+                    format!("an interface must extend java/lang/Object, not {super_name}"),
+                );
+            }
+        }
+        for (index, &interface) in class_file.interfaces.iter().enumerate() {
+            let path = format!("interfaces[{index}]");
+            self.expect(&path, "interface", interface, Expected::Class);
+        }
+
+        let mut field_keys: HashSet<(&str, &str)> = HashSet::new();
+        for (index, field) in class_file.fields.iter().enumerate() {
+            let path = format!("fields[{index}]");
+            if let Some(key) = self.check_field(&path, field) {
+                if !field_keys.insert(key) {
+                    self.report(&path, format!("duplicate field {} {}", key.0, key.1));
+                }
+            }
+        }
+        let mut method_keys: HashSet<(&str, &str)> = HashSet::new();
+        for (index, method) in class_file.methods.iter().enumerate() {
+            let path = format!("methods[{index}]");
+            if let Some(key) = self.check_method(&path, method) {
+                if !method_keys.insert(key) {
+                    self.report(&path, format!("duplicate method {}{}", key.0, key.1));
+                }
+            }
+        }
+        self.check_attributes("", &class_file.attributes, Location::Class);
+
+        let needs_bootstrap_methods = class_file.constant_pool.entries().iter().any(|constant| {
+            matches!(
+                constant,
+                Some(Constant::Dynamic(_) | Constant::InvokeDynamic(_))
+            )
+        });
+        if needs_bootstrap_methods && self.bootstrap_methods().is_none() {
+            self.report(
+                "attributes",
+                "the constant pool has dynamically-computed constants but there is no BootstrapMethods attribute",
+            );
+        }
+    }
+
+    fn check_version(&mut self) {
+        let ClassVersion {
+            major: major_version,
+            minor: minor_version,
+        } = self.class_file.version();
+        if major_version < FIRST_MAJOR_VERSION {
+            self.report(
+                "major_version",
+                format!(
+                    "major_version {major_version} predates the first class file version, {FIRST_MAJOR_VERSION}"
+                ),
+            );
+        }
+        if major_version >= 56 && minor_version != 0 && minor_version != PREVIEW_MINOR_VERSION {
+            self.report(
+                "minor_version",
+                format!("minor_version must be 0 or 65535 from version 56, not {minor_version}"),
+            );
+        }
+    }
+
+    fn bootstrap_methods(&self) -> Option<&'c AttributeBootstrapMethods> {
+        self.class_file
+            .attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                AttributeType::BootstrapMethods(bootstrap_methods) => Some(bootstrap_methods),
+                _ => None,
+            })
+    }
+
+    fn check_constant_pool(&mut self) {
+        let class_file = self.class_file;
+        let bootstrap_method_count = self
+            .bootstrap_methods()
+            .map_or(0, |attribute| attribute.bootstrap_methods.len());
+        for (index, constant) in class_file.constant_pool.entries().iter().enumerate() {
+            let Some(constant) = constant else {
+                continue;
+            };
+            let path = format!("constant_pool[{index}]");
+            if let Some(since) = constant.tag().and_then(version::constant_since) {
+                if self.major_version() < since {
+                    self.report(
+                        &path,
+                        format!(
+                            "{} requires class file version {since} or later",
+                            constant.kind()
+                        ),
+                    );
+                }
+            }
+            match constant {
+                Constant::Class(class) => {
+                    if let Some(name) = self.expect_utf8(&path, "name_index", class.name_index) {
+                        let is_array = name.starts_with('[') && is_field_descriptor(name);
+                        if !is_array && !is_class_name(name) {
+                            self.report(&path, format!("{name:?} is not a valid class name"));
+                        }
+                    }
+                }
+                Constant::FieldRef(_)
+                | Constant::MethodRef(_)
+                | Constant::InterfaceMethodRef(_) => self.check_member_ref(&path, constant),
+                Constant::String(string) => {
+                    self.expect(&path, "string_index", string.string_index, Expected::Utf8);
+                }
+                Constant::NameAndType(name_and_type) => {
+                    self.expect_utf8(&path, "name_index", name_and_type.name_index);
+                    if let Some(descriptor) =
+                        self.expect_utf8(&path, "descriptor_index", name_and_type.descriptor_index)
+                    {
+                        if !is_field_descriptor(descriptor) && !is_method_descriptor(descriptor) {
+                            self.report(
+                                &path,
+                                format!("{descriptor:?} is not a valid field or method descriptor"),
+                            );
+                        }
+                    }
+                }
+                Constant::MethodHandle(method_handle) => {
+                    self.check_method_handle(&path, method_handle)
+                }
+                Constant::MethodType(method_type) => {
+                    if let Some(descriptor) =
+                        self.expect_utf8(&path, "descriptor_index", method_type.descriptor_index)
+                    {
+                        if !is_method_descriptor(descriptor) {
+                            self.report(
+                                &path,
+                                format!("{descriptor:?} is not a valid method descriptor"),
+                            );
+                        }
+                    }
+                }
+                Constant::Dynamic(ConstantDynamic {
+                    bootstrap_method_attr_index,
+                    name_and_type_index,
+                    ..
+                })
+                | Constant::InvokeDynamic(ConstantInvokeDynamic {
+                    bootstrap_method_attr_index,
+                    name_and_type_index,
+                    ..
+                }) => {
+                    let is_dynamic = matches!(constant, Constant::Dynamic(_));
+                    if *bootstrap_method_attr_index as usize >= bootstrap_method_count {
+                        self.report(
+                            &path,
+                            format!(
+                                "bootstrap_method_attr_index {bootstrap_method_attr_index} is out of range for {bootstrap_method_count} bootstrap methods"
+                            ),
+                        );
+                    }
+                    self.expect(
+                        &path,
+                        "name_and_type_index",
+                        *name_and_type_index,
+                        Expected::NameAndType,
+                    );
+                    if let Some((_, descriptor)) = self.name_and_type(*name_and_type_index) {
+                        if is_dynamic && !is_field_descriptor(descriptor) {
+                            self.report(
+                                &path,
+                                format!(
+                                    "CONSTANT_Dynamic needs a field descriptor, not {descriptor:?}"
+                                ),
+                            );
+                        } else if !is_dynamic && !is_method_descriptor(descriptor) {
+                            self.report(
+                                &path,
+                                format!(
+                                    "CONSTANT_InvokeDynamic needs a method descriptor, not {descriptor:?}"
+                                ),
+                            );
+                        }
+                    }
+                }
+                Constant::Module(module) => {
+                    self.expect_name(
+                        &path,
+                        "name_index",
+                        module.name_index,
+                        "module name",
+                        is_module_name,
+                    );
+                    self.check_module_only(&path, constant);
+                }
+                Constant::Package(package) => {
+                    self.expect_name(
+                        &path,
+                        "name_index",
+                        package.name_index,
+                        "package name",
+                        is_class_name,
+                    );
+                    self.check_module_only(&path, constant);
+                }
+                Constant::Integer(_)
+                | Constant::Float(_)
+                | Constant::Long(_)
+                | Constant::Double(_)
+                | Constant::Utf8Info(_)
+                | Constant::Unusable => {}
+            }
+        }
+    }
+
+    fn check_module_only(&mut self, path: &str, constant: &Constant) {
+        if !self.is_module() {
+            self.report(
+                path,
+                format!("{} is only allowed in a module-info class", constant.kind()),
+            );
+        }
+    }
+
+    fn check_member_ref(&mut self, path: &str, constant: &Constant) {
+        let Some((class_index, name_and_type_index)) = member_ref_indexes(constant) else {
+            return;
+        };
+        self.expect(path, "class_index", class_index, Expected::Class);
+        self.expect(
+            path,
+            "name_and_type_index",
+            name_and_type_index,
+            Expected::NameAndType,
+        );
+        let Some((name, descriptor)) = self.name_and_type(name_and_type_index) else {
+            return;
+        };
+        if constant.tag() == Some(CONSTANT_POOL_FIELDREF) {
+            if !is_unqualified_name(name) {
+                self.report(path, format!("{name:?} is not a valid field name"));
+            }
+            if !is_field_descriptor(descriptor) {
+                self.report(
+                    path,
+                    format!("{descriptor:?} is not a valid field descriptor"),
+                );
+            }
+        } else {
+            if !is_method_name(name) || name == "<clinit>" {
+                self.report(path, format!("{name:?} cannot be referenced as a method"));
+            }
+            if !is_method_descriptor(descriptor) {
+                self.report(
+                    path,
+                    format!("{descriptor:?} is not a valid method descriptor"),
+                );
+            } else if name == "<init>" && !descriptor.ends_with(")V") {
+                self.report(path, "<init> must return void");
+            }
+        }
+    }
+
+    fn check_method_handle(&mut self, path: &str, method_handle: &ConstantMethodHandle) {
+        let index = method_handle.reference_index;
+        let expected = match method_handle.reference_kind {
+            1..=4 => Expected::FieldRef,
+            5 | 8 => Expected::MethodRef,
+            6 | 7 if self.major_version() < 52 => Expected::MethodRef,
+            6 | 7 => Expected::AnyMethodRef,
+            9 => Expected::InterfaceMethodRef,
+            reference_kind => {
+                self.report(
+                    path,
+                    format!("reference_kind {reference_kind} is not between 1 and 9"),
+                );
+                return;
+            }
+        };
+        let Some(reference) = self.expect(path, "reference_index", index, expected) else {
+            return;
+        };
+        let name = member_ref_indexes(reference)
+            .and_then(|(_, name_and_type_index)| self.name_and_type(name_and_type_index))
+            .map(|(name, _)| name);
+        match (method_handle.reference_kind, name) {
+            (8, Some(name)) if name != "<init>" => {
+                self.report(path, "REF_newInvokeSpecial must refer to an <init> method")
+            }
+            (5..=7 | 9, Some(name @ ("<init>" | "<clinit>"))) => self.report(
+                path,
+                format!("only REF_newInvokeSpecial may refer to {name}"),
+            ),
+            _ => {}
+        }
+    }
+
+    fn check_class_flags(&mut self) {
+        let flags = self.class_file.access_flags.bits();
+        let path = "access_flags";
+        if self.is_module() {
+            if flags != ACC_MODULE {
+                self.report(path, "ACC_MODULE must not be combined with other flags");
+            }
+            if self.major_version() < 53 {
+                self.report(path, "ACC_MODULE requires class file version 53 or later");
+            }
+            let class_file = self.class_file;
+            if self.class_name(class_file.this_class) != Some(MODULE_INFO) {
+                self.report("this_class", "a module's this_class must be module-info");
+            }
+            if class_file.super_class != 0 {
+                self.report("super_class", "a module-info class must have no superclass");
+            }
+            if !class_file.interfaces.is_empty()
+                || !class_file.fields.is_empty()
+                || !class_file.methods.is_empty()
+            {
+                self.report(
+                    path,
+                    "a module-info class must not declare interfaces, fields or methods",
+                );
+            }
+            return;
+        }
+        if self.is_interface() {
+            if flags & ACC_ABSTRACT == 0 {
+                self.report(path, "an interface must also be ACC_ABSTRACT");
+            }
+            if flags & (ACC_FINAL | ACC_SUPER | ACC_ENUM) != 0 {
+                self.report(
+                    path,
+                    "an interface cannot be ACC_FINAL, ACC_SUPER or ACC_ENUM",
+                );
+            }
+        } else {
+            if flags & ACC_ANNOTATION != 0 {
+                self.report(path, "ACC_ANNOTATION requires ACC_INTERFACE");
+            }
+            if flags & ACC_FINAL != 0 && flags & ACC_ABSTRACT != 0 {
+                self.report(path, "a class cannot be both ACC_FINAL and ACC_ABSTRACT");
+            }
+        }
+    }
+
+    /// Checks a field and returns its name and descriptor for duplicate detection.
+    fn check_field(&mut self, path: &str, field: &'c FieldInfo) -> Option<(&'c str, &'c str)> {
+        let flags = field.access_flags.bits();
+        if (flags & ACC_VISIBILITY).count_ones() > 1 {
+            self.report(
+                path,
+                "at most one of ACC_PUBLIC, ACC_PRIVATE and ACC_PROTECTED may be set",
+            );
+        }
+        if flags & ACC_FINAL != 0 && flags & ACC_VOLATILE != 0 {
+            self.report(path, "a field cannot be both ACC_FINAL and ACC_VOLATILE");
+        }
+        if self.is_interface() {
+            let required = ACC_PUBLIC | ACC_STATIC | ACC_FINAL;
+            if flags & required != required || flags & !(required | ACC_SYNTHETIC) != 0 {
+                self.report(
+                    path,
+                    "an interface field must be ACC_PUBLIC, ACC_STATIC and ACC_FINAL, and may only add ACC_SYNTHETIC",
+                );
+            }
+        }
+        let name = self.expect_name(
+            path,
+            "name_index",
+            field.name_index,
+            "field name",
+            is_unqualified_name,
+        );
+        let descriptor = self.expect_name(
+            path,
+            "descriptor_index",
+            field.descriptor_index,
+            "field descriptor",
+            is_field_descriptor,
+        );
+        self.check_attributes(path, &field.attributes, Location::Field);
+
+        for (index, attribute) in field.attributes.iter().enumerate() {
+            let (AttributeType::ConstantValue(constant_value), Some(descriptor)) =
+                (attribute, descriptor)
+            else {
+                continue;
+            };
+            // The JVM ignores ConstantValue on a field that is not static (JVMS 4.7.2).
+            if flags & ACC_STATIC == 0 {
+                continue;
+            }
+            let Some(constant) = self.constant(constant_value.constantvalue_index) else {
+                continue;
+            };
+            let fits = match descriptor {
+                "J" => matches!(constant, Constant::Long(_)),
+                "F" => matches!(constant, Constant::Float(_)),
+                "D" => matches!(constant, Constant::Double(_)),
+                "I" | "S" | "C" | "B" | "Z" => matches!(constant, Constant::Integer(_)),
+                "Ljava/lang/String;" => matches!(constant, Constant::String(_)),
+                _ => false,
+            };
+            if !fits {
+                self.report(
+                    &child(path, &format!("attributes[{index}].ConstantValue")),
+                    format!(
+                        "a {} cannot initialise a field of type {descriptor}",
+                        constant.kind()
+                    ),
+                );
+            }
+        }
+        Some((name?, descriptor?))
+    }
+
+    /// Checks a method and returns its name and descriptor for duplicate detection.
+    fn check_method(&mut self, path: &str, method: &'c Method) -> Option<(&'c str, &'c str)> {
+        let name = self.expect_name(
+            path,
+            "name_index",
+            method.name_index,
+            "method name",
+            is_method_name,
+        );
+        let descriptor = self.expect_name(
+            path,
+            "descriptor_index",
+            method.descriptor_index,
+            "method descriptor",
+            is_method_descriptor,
+        );
+        if let (Some(name @ ("<init>" | "<clinit>")), Some(descriptor)) = (name, descriptor) {
+            if !descriptor.ends_with(")V") {
+                self.report(path, format!("{name} must return void"));
+            }
+            if name == "<clinit>" && self.major_version() >= 51 && descriptor != "()V" {
+                self.report(path, "<clinit> must take no arguments");
+            }
+        }
+        self.check_method_flags(path, method.access_flags.bits(), name);
+
+        self.check_attributes(path, &method.attributes, Location::Method);
+        let has_code = method
+            .attributes
+            .iter()
+            .any(|attribute| matches!(attribute, AttributeType::Code(_)));
+        if method.access_flags.intersects(ACC_NATIVE | ACC_ABSTRACT) {
+            if has_code {
+                self.report(
+                    path,
+                    "a native or abstract method must not have a Code attribute",
+                );
+            }
+        } else if !has_code {
+            self.report(
+                path,
+                "a method that is neither native nor abstract needs a Code attribute",
+            );
+        }
+        Some((name?, descriptor?))
+    }
+
+    fn check_method_flags(&mut self, path: &str, flags: u16, name: Option<&str>) {
+        if name == Some("<clinit>") {
+            // Other flags on a class initialiser are ignored (JVMS §4.6).
+            if self.major_version() >= 51 && flags & ACC_STATIC == 0 {
+                self.report(path, "<clinit> must be ACC_STATIC");
+            }
+            return;
+        }
+        if (flags & ACC_VISIBILITY).count_ones() > 1 {
+            self.report(
+                path,
+                "at most one of ACC_PUBLIC, ACC_PRIVATE and ACC_PROTECTED may be set",
+            );
+        }
+        if self.is_interface() {
+            if self.major_version() < 52 {
+                let allowed = ACC_PUBLIC | ACC_ABSTRACT | ACC_BRIDGE | ACC_VARARGS | ACC_SYNTHETIC;
+                if flags & (ACC_PUBLIC | ACC_ABSTRACT) != ACC_PUBLIC | ACC_ABSTRACT
+                    || flags & !allowed != 0
+                {
+                    self.report(
+                        path,
+                        "before version 52 an interface method must be ACC_PUBLIC and ACC_ABSTRACT",
+                    );
+                }
+            } else {
+                if flags & (ACC_PROTECTED | ACC_FINAL | ACC_SYNCHRONIZED | ACC_NATIVE) != 0 {
+                    self.report(
+                        path,
+                        "an interface method cannot be ACC_PROTECTED, ACC_FINAL, ACC_SYNCHRONIZED or ACC_NATIVE",
+                    );
+                }
+                if (flags & (ACC_PUBLIC | ACC_PRIVATE)).count_ones() != 1 {
+                    self.report(
+                        path,
+                        "an interface method must be exactly one of ACC_PUBLIC and ACC_PRIVATE",
+                    );
+                }
+            }
+        }
+        if flags & ACC_ABSTRACT != 0 {
+            let mut forbidden =
+                ACC_PRIVATE | ACC_STATIC | ACC_FINAL | ACC_SYNCHRONIZED | ACC_NATIVE;
+            if (46..=60).contains(&self.major_version()) {
+                forbidden |= ACC_STRICT;
+            }
+            if flags & forbidden != 0 {
+                self.report(
+                    path,
+                    "an abstract method cannot also be ACC_PRIVATE, ACC_STATIC, ACC_FINAL, ACC_SYNCHRONIZED, ACC_NATIVE or ACC_STRICT",
+                );
+            }
+        }
+        if name == Some("<init>") {
+            let allowed = ACC_VISIBILITY | ACC_VARARGS | ACC_STRICT | ACC_SYNTHETIC;
+            if flags & !allowed != 0 {
+                self.report(
+                    path,
+                    "<init> may only be ACC_PUBLIC, ACC_PRIVATE, ACC_PROTECTED, ACC_VARARGS, ACC_STRICT or ACC_SYNTHETIC",
+                );
+            }
+        }
+    }
+
+    fn check_attributes(
+        &mut self,
+        path: &str,
+        attributes: &'c [AttributeType],
+        location: Location,
+    ) {
+        let mut seen: HashSet<&str> = HashSet::new();
+        for (index, attribute) in attributes.iter().enumerate() {
+            let Some(name) = attribute.name() else {
+                continue;
+            };
+            let attribute_path = child(path, &format!("attributes[{index}]"));
+            if let Some(locations) = attribute_locations(name) {
+                if !locations.contains(&location) {
+                    self.report(
+                        &attribute_path,
+                        format!("{name} is not allowed in {location}"),
+                    );
+                }
+                if !may_repeat(name) && !seen.insert(name) {
+                    self.report(&attribute_path, format!("duplicate {name} attribute"));
+                }
+            }
+            if let Some(since) = version::attribute_since(name) {
+                if self.major_version() < since {
+                    self.report(
+                        &attribute_path,
+                        format!("{name} requires class file version {since} or later"),
+                    );
+                }
+            }
+            self.check_attribute(&child(&attribute_path, name), attribute);
+        }
+    }
+
+    fn check_attribute(&mut self, path: &str, attribute: &'c AttributeType) {
+        match attribute {
+            AttributeType::ConstantValue(constant_value) => {
+                self.expect(
+                    path,
+                    "constantvalue_index",
+                    constant_value.constantvalue_index,
+                    Expected::ConstantValue,
+                );
+            }
+            AttributeType::Code(code) => self.check_code(path, code),
+            AttributeType::StackMapTable(stack_map_table) => {
+                for (index, frame) in stack_map_table.entries.iter().enumerate() {
+                    let types: Vec<&VerificationTypeInfo> = match &frame.kind {
+                        StackMapFrameKind::SameLocals1StackItem { stack }
+                        | StackMapFrameKind::SameLocals1StackItemExtended { stack } => vec![stack],
+                        StackMapFrameKind::Append { locals } => locals.iter().collect(),
+                        StackMapFrameKind::Full { locals, stack, .. } => {
+                            locals.iter().chain(stack).collect()
+                        }
+                        _ => vec![],
+                    };
+                    let frame_path = child(path, &format!("entries[{index}]"));
+                    for verification_type in types {
+                        if let VerificationTypeInfo::Object { cpool_index } = verification_type {
+                            self.expect(&frame_path, "cpool_index", *cpool_index, Expected::Class);
+                        }
+                    }
+                }
+            }
+            AttributeType::Exceptions(exceptions) => {
+                for &exception in &exceptions.exception_index_table {
+                    self.expect(path, "exception_index_table", exception, Expected::Class);
+                }
+            }
+            AttributeType::InnerClasses(inner_classes) => {
+                for (index, inner_class) in inner_classes.classes.iter().enumerate() {
+                    let path = child(path, &format!("classes[{index}]"));
+                    self.expect(
+                        &path,
+                        "inner_class_info_index",
+                        inner_class.inner_class_info_index,
+                        Expected::Class,
+                    );
+                    self.expect_optional(
+                        &path,
+                        "outer_class_info_index",
+                        inner_class.outer_class_info_index,
+                        Expected::Class,
+                    );
+                    self.expect_optional(
+                        &path,
+                        "inner_name_index",
+                        inner_class.inner_name_index,
+                        Expected::Utf8,
+                    );
+                }
+            }
+            AttributeType::EnclosingMethods(enclosing_method) => {
+                self.expect(
+                    path,
+                    "class_index",
+                    enclosing_method.class_index,
+                    Expected::Class,
+                );
+                self.expect_optional(
+                    path,
+                    "method_index",
+                    enclosing_method.method_index,
+                    Expected::NameAndType,
+                );
+            }
+            AttributeType::Signature(signature) => {
+                self.expect(
+                    path,
+                    "signature_index",
+                    signature.signature_index,
+                    Expected::Utf8,
+                );
+            }
+            AttributeType::SourceFile(source_file) => {
+                self.expect(
+                    path,
+                    "sourcefile_index",
+                    source_file.sourcefile_index,
+                    Expected::Utf8,
+                );
+            }
+            AttributeType::LocalVariableTable(local_variables) => {
+                for (index, variable) in local_variables.local_variable_table.iter().enumerate() {
+                    let path = child(path, &format!("local_variable_table[{index}]"));
+                    self.expect_name(
+                        &path,
+                        "name_index",
+                        variable.name_index,
+                        "variable name",
+                        is_unqualified_name,
+                    );
+                    self.expect_name(
+                        &path,
+                        "descriptor_index",
+                        variable.descriptor_index,
+                        "field descriptor",
+                        is_field_descriptor,
+                    );
+                }
+            }
+            AttributeType::LocalVariableTypeTable(local_variable_types) => {
+                for (index, variable) in local_variable_types
+                    .local_variable_type_table
+                    .iter()
+                    .enumerate()
+                {
+                    let path = child(path, &format!("local_variable_type_table[{index}]"));
+                    self.expect_name(
+                        &path,
+                        "name_index",
+                        variable.name_index,
+                        "variable name",
+                        is_unqualified_name,
+                    );
+                    self.expect(
+                        &path,
+                        "signature_index",
+                        variable.signature_index,
+                        Expected::Utf8,
+                    );
+                }
+            }
+            AttributeType::RuntimeVisibleAnnotations(AttributeRuntimeVisibleAnnotations {
+                annotations,
+                ..
+            })
+            | AttributeType::RuntimeInvisibleAnnotations(AttributeRuntimeInvisibleAnnotations {
+                annotations,
+                ..
+            }) => {
+                for (index, annotation) in annotations.iter().enumerate() {
+                    self.check_annotation(
+                        &child(path, &format!("annotations[{index}]")),
+                        annotation,
+                    );
+                }
+            }
+            AttributeType::RuntimeVisibleParameterAnnotations(
+                AttributeRuntimeVisibleParameterAnnotations {
+                    parameter_annotations,
+                    ..
+                },
+            )
+            | AttributeType::RuntimeInvisibleParameterAnnotations(
+                AttributeRuntimeInvisibleParameterAnnotations {
+                    parameter_annotations,
+                    ..
+                },
+            ) => {
+                for (parameter_index, parameter) in parameter_annotations.iter().enumerate() {
+                    for (index, annotation) in parameter.annotations.iter().enumerate() {
+                        let path = child(
+                            path,
+                            &format!(
+                                "parameter_annotations[{parameter_index}].annotations[{index}]"
+                            ),
+                        );
+                        self.check_annotation(&path, annotation);
+                    }
+                }
+            }
+            AttributeType::RuntimeVisibleTypeAnnotations(
+                AttributeRuntimeVisibleTypeAnnotations { annotations, .. },
+            )
+            | AttributeType::RuntimeInvisibleTypeAnnotations(
+                AttributeRuntimeInvisibleTypeAnnotations { annotations, .. },
+            ) => {
+                for (index, type_annotation) in annotations.iter().enumerate() {
+                    let path = child(path, &format!("annotations[{index}]"));
+                    self.check_annotation(&path, &type_annotation.annotation);
+                }
+            }
+            AttributeType::AnnotationDefault(annotation_default) => {
+                self.check_element_value(
+                    &child(path, "default_value"),
+                    &annotation_default.default_value,
+                );
+            }
+            AttributeType::BootstrapMethods(bootstrap_methods) => {
+                for (index, bootstrap_method) in
+                    bootstrap_methods.bootstrap_methods.iter().enumerate()
+                {
+                    let path = child(path, &format!("bootstrap_methods[{index}]"));
+                    self.expect(
+                        &path,
+                        "bootstrap_method_ref",
+                        bootstrap_method.bootstrap_method_ref,
+                        Expected::MethodHandle,
+                    );
+                    for &argument in &bootstrap_method.bootstrap_arguments {
+                        self.expect(&path, "bootstrap_arguments", argument, Expected::Loadable);
+                    }
+                }
+            }
+            AttributeType::MethodParameters(method_parameters) => {
+                for (index, parameter) in method_parameters.parameters.iter().enumerate() {
+                    let path = child(path, &format!("parameters[{index}]"));
+                    self.expect_optional(&path, "name_index", parameter.name_index, Expected::Utf8);
+                }
+            }
+            AttributeType::Module(module) => self.check_module(path, module),
+            AttributeType::ModulePackages(module_packages) => {
+                for &package in &module_packages.package_index {
+                    self.expect(path, "package_index", package, Expected::Package);
+                }
+            }
+            AttributeType::ModuleMainClass(module_main_class) => {
+                self.expect(
+                    path,
+                    "main_class_index",
+                    module_main_class.main_class_index,
+                    Expected::Class,
+                );
+            }
+            AttributeType::NestHost(nest_host) => {
+                self.expect(
+                    path,
+                    "host_class_index",
+                    nest_host.host_class_index,
+                    Expected::Class,
+                );
+            }
+            AttributeType::NestMembers(AttributeNestMembers { classes, .. })
+            | AttributeType::PermittedSubclasses(AttributePermittedSubclasses {
+                classes, ..
+            }) => {
+                for &class in classes {
+                    self.expect(path, "classes", class, Expected::Class);
+                }
+            }
+            AttributeType::Record(record) => {
+                for (index, component) in record.components.iter().enumerate() {
+                    let path = child(path, &format!("components[{index}]"));
+                    self.expect_name(
+                        &path,
+                        "name_index",
+                        component.name_index,
+                        "component name",
+                        is_unqualified_name,
+                    );
+                    self.expect_name(
+                        &path,
+                        "descriptor_index",
+                        component.descriptor_index,
+                        "field descriptor",
+                        is_field_descriptor,
+                    );
+                    self.check_attributes(&path, &component.attributes, Location::RecordComponent);
+                }
+            }
+            AttributeType::LineNumberTable(_)
+            | AttributeType::Synthetic(_)
+            | AttributeType::Deprecated(_)
+            | AttributeType::SourceDebugExtension(_)
+            | AttributeType::Unknown { .. }
+            | AttributeType::Invalid { .. }
+            | AttributeType::Custom { .. } => {}
+        }
+    }
+
+    fn check_code(&mut self, path: &str, code: &'c AttributeCode) {
+        if code.code_length == 0 || code.code_length >= 65536 {
+            self.report(
+                path,
+                format!(
+                    "code_length must be between 1 and 65535, not {}",
+                    code.code_length
+                ),
+            );
+        }
+        for (index, entry) in code.exception_table.iter().enumerate() {
+            let path = child(path, &format!("exception_table[{index}]"));
+            let code_length = code.code_length;
+            if entry.start_pc >= entry.end_pc || entry.end_pc as u32 > code_length {
+                self.report(
+                    &path,
+                    format!(
+                        "the range {}..{} is not within code of length {code_length}",
+                        entry.start_pc, entry.end_pc
+                    ),
+                );
+            }
+            if entry.handler_pc as u32 >= code_length {
+                self.report(
+                    &path,
+                    format!(
+                        "handler_pc {} is past the end of the code",
+                        entry.handler_pc
+                    ),
+                );
+            }
+            self.expect_optional(&path, "catch_type", entry.catch_type, Expected::Class);
+        }
+        self.check_attributes(path, &code.attribute_info, Location::Code);
+    }
+
+    fn check_module(&mut self, path: &str, module: &'c AttributeModule) {
+        self.expect(
+            path,
+            "module_name_index",
+            module.module_name_index,
+            Expected::Module,
+        );
+        self.expect_optional(
+            path,
+            "module_version_index",
+            module.module_version_index,
+            Expected::Utf8,
+        );
+        for (index, requires) in module.requires.iter().enumerate() {
+            let path = child(path, &format!("requires[{index}]"));
+            self.expect(
+                &path,
+                "requires_index",
+                requires.requires_index,
+                Expected::Module,
+            );
+            self.expect_optional(
+                &path,
+                "requires_version_index",
+                requires.requires_version_index,
+                Expected::Utf8,
+            );
+        }
+        for (index, exports) in module.exports.iter().enumerate() {
+            let path = child(path, &format!("exports[{index}]"));
+            self.expect(
+                &path,
+                "exports_index",
+                exports.exports_index,
+                Expected::Package,
+            );
+            for &target in &exports.exports_to_index {
+                self.expect(&path, "exports_to_index", target, Expected::Module);
+            }
+        }
+        for (index, opens) in module.opens.iter().enumerate() {
+            let path = child(path, &format!("opens[{index}]"));
+            self.expect(&path, "opens_index", opens.opens_index, Expected::Package);
+            for &target in &opens.opens_to_index {
+                self.expect(&path, "opens_to_index", target, Expected::Module);
+            }
+        }
+        for &service in &module.uses_index {
+            self.expect(&child(path, "uses"), "uses_index", service, Expected::Class);
+        }
+        for (index, provides) in module.provides.iter().enumerate() {
+            let path = child(path, &format!("provides[{index}]"));
+            self.expect(
+                &path,
+                "provides_index",
+                provides.provides_index,
+                Expected::Class,
+            );
+            for &implementation in &provides.provides_with_index {
+                self.expect(
+                    &path,
+                    "provides_with_index",
+                    implementation,
+                    Expected::Class,
+                );
+            }
+        }
+    }
+
+    fn check_annotation(&mut self, path: &str, annotation: &'c Annotation) {
+        self.expect_name(
+            path,
+            "type_index",
+            annotation.type_index,
+            "field descriptor",
+            is_field_descriptor,
+        );
+        for (index, pair) in annotation.element_value_pairs.iter().enumerate() {
+            let path = child(path, &format!("element_value_pairs[{index}]"));
+            self.expect(
+                &path,
+                "element_name_index",
+                pair.element_name_index,
+                Expected::Utf8,
+            );
+            self.check_element_value(&path, &pair.value);
+        }
+    }
+
+    fn check_element_value(&mut self, path: &str, element_value: &'c ElementValue) {
+        match &element_value.value {
+            ElementValueEnum::ConstantValueIndex(constant_value) => {
+                let index = constant_value.const_value_index;
+                let Some(constant) = self.constant(index) else {
+                    self.report(
+                        path,
+                        format!("const_value_index #{index} is not a valid constant pool index"),
+                    );
+                    return;
+                };
+                let (fits, expected) = match element_value.tag {
+                    b'B' | b'C' | b'I' | b'S' | b'Z' => {
+                        (matches!(constant, Constant::Integer(_)), "CONSTANT_Integer")
+                    }
+                    b'D' => (matches!(constant, Constant::Double(_)), "CONSTANT_Double"),
+                    b'F' => (matches!(constant, Constant::Float(_)), "CONSTANT_Float"),
+                    b'J' => (matches!(constant, Constant::Long(_)), "CONSTANT_Long"),
+                    _ => (matches!(constant, Constant::Utf8Info(_)), "CONSTANT_Utf8"),
+                };
+                if !fits {
+                    self.report(
+                        path,
+                        format!(
+                            "const_value_index #{index} should refer to a {expected}, not a {}",
+                            constant.kind()
+                        ),
+                    );
+                }
+            }
+            ElementValueEnum::EnumConstValue(enum_value) => {
+                self.expect(
+                    path,
+                    "type_name_index",
+                    enum_value.type_name_index,
+                    Expected::Utf8,
+                );
+                self.expect(
+                    path,
+                    "const_name_index",
+                    enum_value.const_name_index,
+                    Expected::Utf8,
+                );
+            }
+            ElementValueEnum::ClassInfoIndex(class_info) => {
+                self.expect(
+                    path,
+                    "class_info_index",
+                    class_info.class_info_index,
+                    Expected::Utf8,
+                );
+            }
+            ElementValueEnum::AnnotationValue(annotation) => {
+                self.check_annotation(&child(path, "annotation"), annotation);
+            }
+            ElementValueEnum::ArrayValue(array) => {
+                for (index, value) in array.values.iter().enumerate() {
+                    self.check_element_value(&child(path, &format!("values[{index}]")), value);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classfile::testing::{u16_bytes, ClassBuilder};
+
+    fn violations(builder: &ClassBuilder) -> Vec<Violation> {
+        ClassFile::from_bytes(&builder.build()).unwrap().validate()
+    }
+
+    fn messages(builder: &ClassBuilder) -> Vec<String> {
+        violations(builder)
+            .iter()
+            .map(Violation::to_string)
+            .collect()
+    }
+
+    fn valid_class() -> ClassBuilder {
+        let mut builder = ClassBuilder::new("Sample");
+        let value = builder.integer(1);
+        let constant_value = builder.attribute("ConstantValue", &u16_bytes(&[value]));
+        builder.field(ACC_STATIC | ACC_FINAL, "ONE", "I", &[constant_value]);
+        let code = builder.code(1, 1, &[0x2A, 0xB0], &[], &[]);
+        builder.method(ACC_PUBLIC, "self", "()LSample;", &[code]);
+        builder
+    }
+
+    #[test]
+    fn a_well_formed_class_has_no_violations() {
+        assert_eq!(violations(&valid_class()), []);
+    }
+
+    #[test]
+    fn classes_compiled_by_javac_have_no_violations() {
+        let classes: [&[u8]; 4] = [
+            include_bytes!("../../ClassFileCheck.class"),
+            include_bytes!("../../testdata/Members.class"),
+            include_bytes!("../../testdata/Shapes$Polygon.class"),
+            include_bytes!("../../testdata/module/module-info.class"),
+        ];
+        for bytes in classes {
+            let class_file = ClassFile::from_bytes(bytes).unwrap();
+            assert_eq!(class_file.validate(), []);
+        }
+    }
+
+    #[test]
+    fn reports_illegal_flag_combinations() {
+        let mut builder = valid_class();
+        builder.access_flags = ACC_PUBLIC | ACC_FINAL | ACC_ABSTRACT;
+        builder.field(ACC_FINAL | ACC_VOLATILE, "flag", "Z", &[]);
+        builder.method(ACC_PUBLIC | ACC_PRIVATE | ACC_NATIVE, "both", "()V", &[]);
+        assert_eq!(
+            messages(&builder),
+            [
+                "access_flags: a class cannot be both ACC_FINAL and ACC_ABSTRACT",
+                "fields[1]: a field cannot be both ACC_FINAL and ACC_VOLATILE",
+                "methods[1]: at most one of ACC_PUBLIC, ACC_PRIVATE and ACC_PROTECTED may be set",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_attributes_in_the_wrong_place() {
+        let mut builder = valid_class();
+        let value = builder.integer(2);
+        let constant_value = builder.attribute("ConstantValue", &u16_bytes(&[value]));
+        let code = builder.code(1, 1, &[0xB1], &[], &[]);
+        builder.method(ACC_STATIC, "run", "()V", &[code, constant_value]);
+        let source_file = builder.utf8("Sample.java");
+        let source_file = builder.attribute("SourceFile", &u16_bytes(&[source_file]));
+        builder.class_attribute(source_file.clone());
+        builder.class_attribute(source_file);
+        assert_eq!(
+            messages(&builder),
+            [
+                "methods[1].attributes[1]: ConstantValue is not allowed in a method_info structure",
+                "attributes[1]: duplicate SourceFile attribute",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_attributes_newer_than_the_class() {
+        let mut builder = valid_class();
+        builder.major_version = 50;
+        let nest_members = builder.attribute("NestMembers", &u16_bytes(&[0]));
+        builder.class_attribute(nest_members);
+        assert_eq!(
+            messages(&builder),
+            ["attributes[0]: NestMembers requires class file version 55 or later"]
+        );
+    }
+
+    #[test]
+    fn reports_exception_table_entries_outside_the_code() {
+        let mut builder = valid_class();
+        let code = builder.code(1, 1, &[0x00, 0xB1], &[[1, 1, 0, 0], [0, 3, 2, 0]], &[]);
+        builder.method(ACC_STATIC, "run", "()V", &[code]);
+        assert_eq!(
+            messages(&builder),
+            [
+                "methods[1].attributes[0].Code.exception_table[0]: the range 1..1 is not within code of length 2",
+                "methods[1].attributes[0].Code.exception_table[1]: the range 0..3 is not within code of length 2",
+                "methods[1].attributes[0].Code.exception_table[1]: handler_pc 2 is past the end of the code",
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_constant_value_types_only_on_static_fields() {
+        let mut builder = valid_class();
+        let text = builder.string("1");
+        let constant_value = builder.attribute("ConstantValue", &u16_bytes(&[text]));
+        builder.field(ACC_STATIC | ACC_FINAL, "TWO", "I", &[constant_value]);
+        let constant_value = builder.attribute("ConstantValue", &u16_bytes(&[text]));
+        builder.field(ACC_FINAL, "three", "I", &[constant_value]);
+        assert_eq!(
+            messages(&builder),
+            ["fields[1].attributes[0].ConstantValue: a CONSTANT_String cannot initialise a field of type I"]
+        );
+    }
+
+    #[test]
+    fn references_to_the_slot_after_a_long_are_invalid_indexes() {
+        let mut builder = ClassBuilder::new("Sample");
+        let long = builder.long(1);
+        let constant_value = builder.attribute("ConstantValue", &u16_bytes(&[long + 1]));
+        builder.field(ACC_STATIC | ACC_FINAL, "ONE", "J", &[constant_value]);
+        assert_eq!(
+            messages(&builder),
+            [format!(
+                "fields[0].attributes[0].ConstantValue: constantvalue_index #{} is not a valid constant pool index",
+                long + 1
+            )]
+        );
+    }
+}
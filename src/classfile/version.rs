@@ -0,0 +1,222 @@
+use std::fmt;
+
+pub const FIRST_MAJOR_VERSION: u16 = 45;
+/// The newest major version this crate was written against (Java 23). Later versions are
+/// still parsed and map to releases by the same rule.
+pub const LATEST_MAJOR_VERSION: u16 = 67;
+/// The minor version marking a class that depends on preview features (JVMS §4.1).
+pub const PREVIEW_MINOR_VERSION: u16 = 0xFFFF;
+
+/// A class file's `major_version.minor_version`. Ordering follows the JVMS: by major
+/// version, then by minor version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ClassVersion {
+    pub major: u16,
+    pub minor: u16,
+}
+
+impl ClassVersion {
+    pub const fn new(major: u16, minor: u16) -> ClassVersion {
+        ClassVersion { major, minor }
+    }
+
+    /// The Java SE release that introduced this version: 8 for 52, 17 for 61. Releases
+    /// before Java 5 were named 1.x and come out as their x, so 46 gives 2 for Java 1.2
+    /// and 45 gives 1 for both JDK 1.0.2 and 1.1.
+    pub fn java_release(&self) -> Option<u16> {
+        self.major
+            .checked_sub(FIRST_MAJOR_VERSION - 1)
+            .filter(|&release| release > 0)
+    }
+
+    /// True for a class compiled with `--enable-preview`, which only runs on the exact
+    /// release it was compiled for.
+    pub fn is_preview(&self) -> bool {
+        self.major >= 56 && self.minor == PREVIEW_MINOR_VERSION
+    }
+
+    /// True if the major version is between 45 and [`LATEST_MAJOR_VERSION`].
+    pub fn is_known(&self) -> bool {
+        (FIRST_MAJOR_VERSION..=LATEST_MAJOR_VERSION).contains(&self.major)
+    }
+
+    pub fn at_least(&self, major: u16) -> bool {
+        self.major >= major
+    }
+}
+
+impl fmt::Display for ClassVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)?;
+        match self.java_release() {
+            Some(1) if self.minor < 3 => write!(f, " (Java 1.0.2)"),
+            Some(release @ 1..=4) => write!(f, " (Java 1.{release})"),
+            Some(release) if self.is_preview() => write!(f, " (Java {release}, preview)"),
+            Some(release) => write!(f, " (Java {release})"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The first major version that allows a constant pool entry with this tag (JVMS
+/// Table 4.4-C).
+pub fn constant_since(tag: u8) -> Option<u16> {
+    match tag {
+        15 | 16 | 18 => Some(51),
+        19 | 20 => Some(53),
+        17 => Some(55),
+        _ => None,
+    }
+}
+
+/// The first major version that defines each predefined attribute (JVMS Table 4.7-B).
+/// Older class files may contain an attribute with the same name, but the JVM does not
+/// recognise it there.
+pub fn attribute_since(name: &str) -> Option<u16> {
+    let since = match name {
+        "ConstantValue" | "Code" | "Exceptions" | "InnerClasses" | "Synthetic" | "SourceFile"
+        | "LineNumberTable" | "LocalVariableTable" | "Deprecated" => 45,
+        "EnclosingMethod"
+        | "Signature"
+        | "SourceDebugExtension"
+        | "LocalVariableTypeTable"
+        | "RuntimeVisibleAnnotations"
+        | "RuntimeInvisibleAnnotations"
+        | "RuntimeVisibleParameterAnnotations"
+        | "RuntimeInvisibleParameterAnnotations"
+        | "AnnotationDefault" => 49,
+        "StackMapTable" => 50,
+        "BootstrapMethods" => 51,
+        "RuntimeVisibleTypeAnnotations"
+        | "RuntimeInvisibleTypeAnnotations"
+        | "MethodParameters" => 52,
+        "Module" | "ModulePackages" | "ModuleMainClass" => 53,
+        "NestHost" | "NestMembers" => 55,
+        "Record" => 60,
+        "PermittedSubclasses" => 61,
+        _ => return None,
+    };
+    Some(since)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classfile::{
+        attributes::AttributeType,
+        constant_pool::CONSTANT_POOL_DYNAMIC,
+        error::ParseErrorKind,
+        options::{ParseOptions, VersionPolicy},
+        testing::{u16_bytes, ClassBuilder},
+        ClassFile,
+    };
+
+    #[test]
+    fn maps_versions_to_releases() {
+        assert_eq!(ClassVersion::new(45, 3).to_string(), "45.3 (Java 1.1)");
+        assert_eq!(ClassVersion::new(45, 0).to_string(), "45.0 (Java 1.0.2)");
+        assert_eq!(ClassVersion::new(48, 0).to_string(), "48.0 (Java 1.4)");
+        assert_eq!(ClassVersion::new(52, 0).to_string(), "52.0 (Java 8)");
+        assert_eq!(
+            ClassVersion::new(65, PREVIEW_MINOR_VERSION).to_string(),
+            "65.65535 (Java 21, preview)"
+        );
+        assert_eq!(ClassVersion::new(44, 0).java_release(), None);
+        assert!(!ClassVersion::new(44, 0).is_known());
+        assert!(!ClassVersion::new(52, PREVIEW_MINOR_VERSION).is_preview());
+        assert!(ClassVersion::new(52, 1) > ClassVersion::new(52, 0));
+        assert!(ClassVersion::new(53, 0) > ClassVersion::new(52, 65535));
+    }
+
+    /// A version 52 class with a NestHost attribute, which needs version 55.
+    fn class_with_nest_host() -> Vec<u8> {
+        let mut builder = ClassBuilder::new("Sample$Inner");
+        builder.major_version = 52;
+        let host = builder.class("Sample");
+        let nest_host = builder.attribute("NestHost", &u16_bytes(&[host]));
+        builder.class_attribute(nest_host);
+        builder.build()
+    }
+
+    fn parse(bytes: &[u8], version_policy: VersionPolicy) -> Result<(ClassFile, usize), String> {
+        let options = ParseOptions {
+            version_policy,
+            ..ParseOptions::default()
+        };
+        ClassFile::from_reader_with_diagnostics(bytes, &options)
+            .map(|(class_file, diagnostics)| (class_file, diagnostics.len()))
+            .map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn attributes_newer_than_the_class_are_gated() {
+        let bytes = class_with_nest_host();
+
+        let (strict, diagnostics) = parse(&bytes, VersionPolicy::Strict).unwrap();
+        assert!(matches!(
+            &strict.attributes[..],
+            [AttributeType::Unknown { name, .. }] if name == "NestHost"
+        ));
+        assert_eq!(diagnostics, 0);
+
+        let (warned, diagnostics) = parse(&bytes, VersionPolicy::Warn).unwrap();
+        assert!(matches!(
+            warned.attributes[..],
+            [AttributeType::NestHost(_)]
+        ));
+        assert_eq!(diagnostics, 1);
+
+        let (ignored, diagnostics) = parse(&bytes, VersionPolicy::Ignore).unwrap();
+        assert!(matches!(
+            ignored.attributes[..],
+            [AttributeType::NestHost(_)]
+        ));
+        assert_eq!(diagnostics, 0);
+    }
+
+    #[test]
+    fn constants_newer_than_the_class_are_gated() {
+        let mut builder = ClassBuilder::new("Sample");
+        builder.major_version = 52;
+        let name_and_type = builder.name_and_type("value", "I");
+        let dynamic = builder.constant(
+            &[
+                &[CONSTANT_POOL_DYNAMIC][..],
+                &u16_bytes(&[0, name_and_type]),
+            ]
+            .concat(),
+            1,
+        );
+        let bytes = builder.build();
+
+        let err = ClassFile::from_bytes(&bytes).unwrap_err();
+        assert!(matches!(
+            err.kind,
+            ParseErrorKind::UnsupportedInVersion { since: 55, version, .. }
+                if version == ClassVersion::new(52, 0)
+        ));
+        assert_eq!(err.path, format!("constant_pool[{dynamic}]"));
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "CONSTANT_Dynamic requires class file version 55 or later, but this class is version 52.0 (Java 8) at offset 0x{:04X} (constant_pool[{dynamic}])",
+                builder.constant_pool_end() - 5
+            )
+        );
+
+        let (_, diagnostics) = parse(&bytes, VersionPolicy::Warn).unwrap();
+        assert_eq!(diagnostics, 1);
+        assert!(parse(&bytes, VersionPolicy::Ignore).is_ok());
+    }
+
+    #[test]
+    fn attribute_and_constant_tables() {
+        assert_eq!(attribute_since("Code"), Some(45));
+        assert_eq!(attribute_since("StackMapTable"), Some(50));
+        assert_eq!(attribute_since("PermittedSubclasses"), Some(61));
+        assert_eq!(attribute_since("Kotlin"), None);
+        assert_eq!(constant_since(CONSTANT_POOL_DYNAMIC), Some(55));
+        assert_eq!(constant_since(1), None);
+    }
+}
@@ -0,0 +1,1205 @@
+//! A read-only view over a parsed [`ClassFile`] that resolves constant pool indexes to
+//! names and parses descriptors and signatures on request.
+//!
+//! [`ClassFile`] mirrors the class file format, index for index. [`ClassView`] and the
+//! member views below answer the questions most tools ask instead: what is this method
+//! called, what does it take and return, where is its bytecode. Lookups that go through
+//! the constant pool return a [`ViewError`] when the class file is malformed.
+
+use std::fmt;
+
+use crate::classfile::{
+    access_flags::{
+        ClassAccessFlags, FieldAccessFlags, InnerClassAccessFlags, MethodAccessFlags,
+        ParameterFlags,
+    },
+    annotations::{
+        Annotation, ElementValue, ElementValueEnum, ParameterAnnotations, TypeAnnotation,
+    },
+    attributes::{AttributeCode, AttributeEnclosingMethod, AttributeType},
+    constant_pool::{Constant, ConstantPool, ConstantPoolError},
+    descriptor::{DescriptorError, FieldType, MethodDescriptor},
+    fields::FieldInfo,
+    methods::Method,
+    signature::{ClassSignature, FieldSignature, MethodSignature, SignatureError},
+    version::ClassVersion,
+    ClassFile,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ViewError {
+    ConstantPool(ConstantPoolError),
+    Descriptor(DescriptorError),
+    Signature(SignatureError),
+}
+
+impl fmt::Display for ViewError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ViewError::ConstantPool(err) => err.fmt(f),
+            ViewError::Descriptor(err) => err.fmt(f),
+            ViewError::Signature(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ViewError {}
+
+impl From<ConstantPoolError> for ViewError {
+    fn from(err: ConstantPoolError) -> ViewError {
+        ViewError::ConstantPool(err)
+    }
+}
+
+impl From<DescriptorError> for ViewError {
+    fn from(err: DescriptorError) -> ViewError {
+        ViewError::Descriptor(err)
+    }
+}
+
+impl From<SignatureError> for ViewError {
+    fn from(err: SignatureError) -> ViewError {
+        ViewError::Signature(err)
+    }
+}
+
+impl ClassFile {
+    pub fn view(&self) -> ClassView<'_> {
+        ClassView::new(self)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ClassView<'c> {
+    class_file: &'c ClassFile,
+}
+
+impl<'c> ClassView<'c> {
+    pub fn new(class_file: &'c ClassFile) -> ClassView<'c> {
+        ClassView { class_file }
+    }
+
+    pub fn class_file(&self) -> &'c ClassFile {
+        self.class_file
+    }
+
+    pub fn constant_pool(&self) -> &'c ConstantPool {
+        &self.class_file.constant_pool
+    }
+
+    pub fn version(&self) -> ClassVersion {
+        self.class_file.version()
+    }
+
+    pub fn access_flags(&self) -> ClassAccessFlags {
+        self.class_file.access_flags
+    }
+
+    /// Internal name of the class, e.g. `java/util/HashMap`.
+    pub fn name(&self) -> Result<&'c str, ViewError> {
+        Ok(self
+            .constant_pool()
+            .class_name(self.class_file.this_class)?)
+    }
+
+    /// Internal name of the superclass; `None` for `java/lang/Object` and modules.
+    pub fn super_class(&self) -> Result<Option<&'c str>, ViewError> {
+        optional_class_name(self.constant_pool(), self.class_file.super_class)
+    }
+
+    pub fn interfaces(&self) -> Result<Vec<&'c str>, ViewError> {
+        self.class_file
+            .interfaces
+            .iter()
+            .map(|&index| Ok(self.constant_pool().class_name(index)?))
+            .collect()
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = FieldView<'c>> + 'c {
+        let constant_pool = self.constant_pool();
+        self.class_file.fields.iter().map(move |field| FieldView {
+            constant_pool,
+            field,
+        })
+    }
+
+    pub fn methods(&self) -> impl Iterator<Item = MethodView<'c>> + 'c {
+        let constant_pool = self.constant_pool();
+        self.class_file
+            .methods
+            .iter()
+            .map(move |method| MethodView {
+                constant_pool,
+                method,
+            })
+    }
+
+    /// The first field called `name`. Fields are unique by name and descriptor, so
+    /// only hand-written class files can have more than one.
+    pub fn field(&self, name: &str) -> Option<FieldView<'c>> {
+        self.fields().find(|field| field.name().ok() == Some(name))
+    }
+
+    /// The method with this name and descriptor, e.g. `("main", "([Ljava/lang/String;)V")`.
+    pub fn method(&self, name: &str, descriptor: &str) -> Option<MethodView<'c>> {
+        self.methods().find(|method| {
+            method.name().ok() == Some(name) && method.descriptor_str().ok() == Some(descriptor)
+        })
+    }
+
+    pub fn attributes(&self) -> AttributesView<'c> {
+        AttributesView {
+            constant_pool: self.constant_pool(),
+            attributes: &self.class_file.attributes,
+        }
+    }
+
+    /// The SourceFile attribute, e.g. `HashMap.java`.
+    pub fn source_file(&self) -> Option<Result<&'c str, ViewError>> {
+        self.attributes().find(|attribute| match attribute {
+            AttributeType::SourceFile(source_file) => Some(
+                self.constant_pool()
+                    .utf8(source_file.sourcefile_index)
+                    .map_err(ViewError::from),
+            ),
+            _ => None,
+        })
+    }
+
+    pub fn signature(&self) -> Option<Result<ClassSignature, ViewError>> {
+        let signature = self.attributes().signature()?;
+        Some(signature.and_then(|signature| Ok(ClassSignature::parse(signature)?)))
+    }
+
+    /// The nested classes this class refers to, from the InnerClasses attribute.
+    pub fn inner_classes(&self) -> Result<Vec<InnerClassView<'c>>, ViewError> {
+        let constant_pool = self.constant_pool();
+        let mut inner_classes: Vec<InnerClassView<'c>> = vec![];
+        for attribute in &self.class_file.attributes {
+            let AttributeType::InnerClasses(attribute) = attribute else {
+                continue;
+            };
+            for inner_class in &attribute.classes {
+                inner_classes.push(InnerClassView {
+                    inner_class: constant_pool.class_name(inner_class.inner_class_info_index)?,
+                    outer_class: optional_class_name(
+                        constant_pool,
+                        inner_class.outer_class_info_index,
+                    )?,
+                    inner_name: optional_utf8(constant_pool, inner_class.inner_name_index)?,
+                    access_flags: inner_class.inner_class_access_flags,
+                });
+            }
+        }
+        Ok(inner_classes)
+    }
+
+    /// For a local or anonymous class, the class and (if any) method that encloses it.
+    pub fn enclosing_method(&self) -> Option<Result<EnclosingMethodView<'c>, ViewError>> {
+        self.attributes().find(|attribute| match attribute {
+            AttributeType::EnclosingMethods(enclosing) => {
+                Some(resolve_enclosing_method(self.constant_pool(), enclosing))
+            }
+            _ => None,
+        })
+    }
+
+    pub fn nest_host(&self) -> Option<Result<&'c str, ViewError>> {
+        self.attributes().find(|attribute| match attribute {
+            AttributeType::NestHost(nest_host) => Some(
+                self.constant_pool()
+                    .class_name(nest_host.host_class_index)
+                    .map_err(ViewError::from),
+            ),
+            _ => None,
+        })
+    }
+
+    pub fn nest_members(&self) -> Option<Result<Vec<&'c str>, ViewError>> {
+        self.attributes().find(|attribute| match attribute {
+            AttributeType::NestMembers(nest_members) => {
+                Some(class_names(self.constant_pool(), &nest_members.classes))
+            }
+            _ => None,
+        })
+    }
+
+    pub fn permitted_subclasses(&self) -> Option<Result<Vec<&'c str>, ViewError>> {
+        self.attributes().find(|attribute| match attribute {
+            AttributeType::PermittedSubclasses(permitted) => {
+                Some(class_names(self.constant_pool(), &permitted.classes))
+            }
+            _ => None,
+        })
+    }
+
+    /// The components of a record, in declaration order; `None` if this is not a record.
+    pub fn record_components(&self) -> Option<Result<Vec<RecordComponentView<'c>>, ViewError>> {
+        let constant_pool = self.constant_pool();
+        self.attributes().find(|attribute| match attribute {
+            AttributeType::Record(record) => Some(
+                record
+                    .components
+                    .iter()
+                    .map(|component| {
+                        Ok(RecordComponentView {
+                            name: constant_pool.utf8(component.name_index)?,
+                            descriptor: constant_pool.utf8(component.descriptor_index)?,
+                            attributes: AttributesView {
+                                constant_pool,
+                                attributes: &component.attributes,
+                            },
+                        })
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FieldView<'c> {
+    constant_pool: &'c ConstantPool,
+    field: &'c FieldInfo,
+}
+
+impl<'c> FieldView<'c> {
+    pub fn field_info(&self) -> &'c FieldInfo {
+        self.field
+    }
+
+    pub fn access_flags(&self) -> FieldAccessFlags {
+        self.field.access_flags
+    }
+
+    pub fn name(&self) -> Result<&'c str, ViewError> {
+        Ok(self.constant_pool.utf8(self.field.name_index)?)
+    }
+
+    pub fn descriptor_str(&self) -> Result<&'c str, ViewError> {
+        Ok(self.constant_pool.utf8(self.field.descriptor_index)?)
+    }
+
+    pub fn descriptor(&self) -> Result<FieldType, ViewError> {
+        Ok(FieldType::parse(self.descriptor_str()?)?)
+    }
+
+    pub fn signature(&self) -> Option<Result<FieldSignature, ViewError>> {
+        let signature = self.attributes().signature()?;
+        Some(signature.and_then(|signature| Ok(FieldSignature::parse(signature)?)))
+    }
+
+    /// The initial value of a `static final` field from its ConstantValue attribute.
+    pub fn constant_value(&self) -> Option<Result<ConstantValue<'c>, ViewError>> {
+        self.attributes().find(|attribute| match attribute {
+            AttributeType::ConstantValue(constant_value) => Some(resolve_constant_value(
+                self.constant_pool,
+                constant_value.constantvalue_index,
+            )),
+            _ => None,
+        })
+    }
+
+    pub fn attributes(&self) -> AttributesView<'c> {
+        AttributesView {
+            constant_pool: self.constant_pool,
+            attributes: &self.field.attributes,
+        }
+    }
+
+    /// The field as declared in Java source, e.g. `private static final long serialVersionUID`,
+    /// using the generic type when there is a valid Signature attribute.
+    pub fn declaration(&self) -> Result<String, ViewError> {
+        let field_type = match self.signature() {
+            Some(Ok(signature)) => signature.to_java(),
+            _ => self.descriptor()?.simple_name(),
+        };
+        Ok(join_declaration(
+            &self.access_flags().to_string(),
+            &format!("{field_type} {}", self.name()?),
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MethodView<'c> {
+    constant_pool: &'c ConstantPool,
+    method: &'c Method,
+}
+
+impl<'c> MethodView<'c> {
+    pub fn method(&self) -> &'c Method {
+        self.method
+    }
+
+    pub fn access_flags(&self) -> MethodAccessFlags {
+        self.method.access_flags
+    }
+
+    pub fn name(&self) -> Result<&'c str, ViewError> {
+        Ok(self.constant_pool.utf8(self.method.name_index)?)
+    }
+
+    pub fn descriptor_str(&self) -> Result<&'c str, ViewError> {
+        Ok(self.constant_pool.utf8(self.method.descriptor_index)?)
+    }
+
+    pub fn descriptor(&self) -> Result<MethodDescriptor, ViewError> {
+        Ok(MethodDescriptor::parse(self.descriptor_str()?)?)
+    }
+
+    pub fn signature(&self) -> Option<Result<MethodSignature, ViewError>> {
+        let signature = self.attributes().signature()?;
+        Some(signature.and_then(|signature| Ok(MethodSignature::parse(signature)?)))
+    }
+
+    /// The method body; `None` for abstract and native methods.
+    pub fn code(&self) -> Option<CodeView<'c>> {
+        self.attributes().find(|attribute| match attribute {
+            AttributeType::Code(code) => Some(CodeView {
+                constant_pool: self.constant_pool,
+                code,
+            }),
+            _ => None,
+        })
+    }
+
+    /// Internal names of the checked exceptions from the Exceptions attribute.
+    pub fn exceptions(&self) -> Result<Vec<&'c str>, ViewError> {
+        let exceptions = self.attributes().find(|attribute| match attribute {
+            AttributeType::Exceptions(exceptions) => Some(class_names(
+                self.constant_pool,
+                &exceptions.exception_index_table,
+            )),
+            _ => None,
+        });
+        exceptions.unwrap_or_else(|| Ok(vec![]))
+    }
+
+    /// Parameter names and flags from the MethodParameters attribute, which javac only
+    /// writes with `-parameters`.
+    pub fn parameters(&self) -> Option<Result<Vec<ParameterView<'c>>, ViewError>> {
+        self.attributes().find(|attribute| match attribute {
+            AttributeType::MethodParameters(parameters) => Some(
+                parameters
+                    .parameters
+                    .iter()
+                    .map(|parameter| {
+                        Ok(ParameterView {
+                            name: optional_utf8(self.constant_pool, parameter.name_index)?,
+                            access_flags: parameter.access_flags,
+                        })
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        })
+    }
+
+    /// Annotations on each parameter, from the RuntimeVisibleParameterAnnotations or
+    /// RuntimeInvisibleParameterAnnotations attribute. The list can be shorter than the
+    /// descriptor's parameters, as javac leaves out synthetic ones.
+    pub fn parameter_annotations(
+        &self,
+        visible: bool,
+    ) -> Result<Vec<Vec<AnnotationView<'c>>>, ViewError> {
+        let parameters = self.attributes().find(|attribute| match attribute {
+            AttributeType::RuntimeVisibleParameterAnnotations(attribute) if visible => {
+                Some(&attribute.parameter_annotations)
+            }
+            AttributeType::RuntimeInvisibleParameterAnnotations(attribute) if !visible => {
+                Some(&attribute.parameter_annotations)
+            }
+            _ => None,
+        });
+        parameters
+            .map_or(&[] as &[ParameterAnnotations], Vec::as_slice)
+            .iter()
+            .map(|parameter| resolve_annotations(self.constant_pool, &parameter.annotations))
+            .collect()
+    }
+
+    /// The default value of an annotation interface element.
+    pub fn annotation_default(&self) -> Option<Result<ElementValueView<'c>, ViewError>> {
+        self.attributes().find(|attribute| match attribute {
+            AttributeType::AnnotationDefault(default) => Some(resolve_element_value(
+                self.constant_pool,
+                &default.default_value,
+            )),
+            _ => None,
+        })
+    }
+
+    pub fn attributes(&self) -> AttributesView<'c> {
+        AttributesView {
+            constant_pool: self.constant_pool,
+            attributes: &self.method.attributes,
+        }
+    }
+
+    /// The method as declared in Java source with simple names, e.g.
+    /// `public static void main(String[])`, using the generic signature when there is a
+    /// valid Signature attribute. Constructors and class initialisers keep their
+    /// `<init>` and `<clinit>` names.
+    pub fn declaration(&self) -> Result<String, ViewError> {
+        let name = self.name()?;
+        let mut declaration = match self.signature() {
+            Some(Ok(signature)) => signature.to_java(name),
+            _ => self.descriptor()?.to_java(name),
+        };
+        let exceptions = self.exceptions()?;
+        if !declaration.contains(" throws ") && !exceptions.is_empty() {
+            let exceptions: Vec<String> = exceptions
+                .iter()
+                .map(|&exception| FieldType::Object(exception.to_string()).simple_name())
+                .collect();
+            declaration.push_str(" throws ");
+            declaration.push_str(&exceptions.join(", "));
+        }
+        Ok(join_declaration(
+            &self.access_flags().to_string(),
+            &declaration,
+        ))
+    }
+}
+
+/// A method's Code attribute with its handlers, line numbers and local variables
+/// resolved.
+#[derive(Debug, Clone, Copy)]
+pub struct CodeView<'c> {
+    constant_pool: &'c ConstantPool,
+    code: &'c AttributeCode,
+}
+
+impl<'c> CodeView<'c> {
+    pub fn attribute(&self) -> &'c AttributeCode {
+        self.code
+    }
+
+    pub fn max_stack(&self) -> u16 {
+        self.code.max_stack
+    }
+
+    pub fn max_locals(&self) -> u16 {
+        self.code.max_locals
+    }
+
+    pub fn bytecode(&self) -> &'c [u8] {
+        &self.code.code
+    }
+
+    pub fn exception_handlers(&self) -> Result<Vec<ExceptionHandler<'c>>, ViewError> {
+        self.code
+            .exception_table
+            .iter()
+            .map(|entry| {
+                Ok(ExceptionHandler {
+                    start_pc: entry.start_pc,
+                    end_pc: entry.end_pc,
+                    handler_pc: entry.handler_pc,
+                    catch_type: optional_class_name(self.constant_pool, entry.catch_type)?,
+                })
+            })
+            .collect()
+    }
+
+    pub fn attributes(&self) -> AttributesView<'c> {
+        AttributesView {
+            constant_pool: self.constant_pool,
+            attributes: &self.code.attribute_info,
+        }
+    }
+
+    /// Entries of every LineNumberTable attribute, ordered by `start_pc`.
+    pub fn line_numbers(&self) -> Vec<LineNumber> {
+        let mut line_numbers: Vec<LineNumber> = self
+            .code
+            .attribute_info
+            .iter()
+            .filter_map(|attribute| match attribute {
+                AttributeType::LineNumberTable(table) => Some(&table.line_number_table),
+                _ => None,
+            })
+            .flatten()
+            .map(|entry| LineNumber {
+                start_pc: entry.start_pc,
+                line_number: entry.line_number,
+            })
+            .collect();
+        line_numbers.sort_by_key(|line_number| line_number.start_pc);
+        line_numbers
+    }
+
+    /// The source line of the instruction at `pc`: that of the last entry starting at
+    /// or before it.
+    pub fn line_at(&self, pc: u16) -> Option<u16> {
+        self.line_numbers()
+            .iter()
+            .rev()
+            .find(|line_number| line_number.start_pc <= pc)
+            .map(|line_number| line_number.line_number)
+    }
+
+    /// Entries of every LocalVariableTable attribute, each with the generic signature
+    /// from the LocalVariableTypeTable entry for the same variable, if there is one.
+    pub fn local_variables(&self) -> Result<Vec<LocalVariable<'c>>, ViewError> {
+        let constant_pool = self.constant_pool;
+        let mut signatures: Vec<(u16, u16, u16, &'c str)> = vec![];
+        for attribute in &self.code.attribute_info {
+            if let AttributeType::LocalVariableTypeTable(table) = attribute {
+                for entry in &table.local_variable_type_table {
+                    let signature = constant_pool.utf8(entry.signature_index)?;
+                    signatures.push((entry.start_pc, entry.length, entry.index, signature));
+                }
+            }
+        }
+        let mut local_variables: Vec<LocalVariable<'c>> = vec![];
+        for attribute in &self.code.attribute_info {
+            let AttributeType::LocalVariableTable(table) = attribute else {
+                continue;
+            };
+            for entry in &table.local_variable_table {
+                let signature = signatures
+                    .iter()
+                    .find(|&&(start_pc, length, index, _)| {
+                        start_pc == entry.start_pc && length == entry.length && index == entry.index
+                    })
+                    .map(|&(_, _, _, signature)| signature);
+                local_variables.push(LocalVariable {
+                    start_pc: entry.start_pc,
+                    length: entry.length,
+                    index: entry.index,
+                    name: constant_pool.utf8(entry.name_index)?,
+                    descriptor: constant_pool.utf8(entry.descriptor_index)?,
+                    signature,
+                });
+            }
+        }
+        Ok(local_variables)
+    }
+}
+
+/// The attributes of a class, member, record component or Code attribute, with lookups
+/// for those that can appear in more than one of them.
+#[derive(Debug, Clone, Copy)]
+pub struct AttributesView<'c> {
+    constant_pool: &'c ConstantPool,
+    attributes: &'c [AttributeType],
+}
+
+impl<'c> AttributesView<'c> {
+    pub fn all(&self) -> &'c [AttributeType] {
+        self.attributes
+    }
+
+    /// The first attribute with this name, e.g. `"Code"`.
+    pub fn by_name(&self, name: &str) -> Option<&'c AttributeType> {
+        self.attributes
+            .iter()
+            .find(|attribute| attribute.name() == Some(name))
+    }
+
+    /// The first attribute `select` picks out.
+    pub fn find<T>(&self, select: impl FnMut(&'c AttributeType) -> Option<T>) -> Option<T> {
+        self.attributes.iter().find_map(select)
+    }
+
+    /// The raw Signature attribute text; members parse it with their own grammar.
+    pub fn signature(&self) -> Option<Result<&'c str, ViewError>> {
+        self.find(|attribute| match attribute {
+            AttributeType::Signature(signature) => Some(
+                signature
+                    .signature(self.constant_pool)
+                    .map_err(ViewError::from),
+            ),
+            _ => None,
+        })
+    }
+
+    pub fn is_deprecated(&self) -> bool {
+        self.attributes
+            .iter()
+            .any(|attribute| matches!(attribute, AttributeType::Deprecated(_)))
+    }
+
+    pub fn is_synthetic(&self) -> bool {
+        self.attributes
+            .iter()
+            .any(|attribute| matches!(attribute, AttributeType::Synthetic(_)))
+    }
+
+    /// Annotations from RuntimeVisibleAnnotations, or RuntimeInvisibleAnnotations
+    /// (retention `CLASS`) when `visible` is false.
+    pub fn annotations(&self, visible: bool) -> Result<Vec<AnnotationView<'c>>, ViewError> {
+        let annotations = self.find(|attribute| match attribute {
+            AttributeType::RuntimeVisibleAnnotations(attribute) if visible => {
+                Some(&attribute.annotations)
+            }
+            AttributeType::RuntimeInvisibleAnnotations(attribute) if !visible => {
+                Some(&attribute.annotations)
+            }
+            _ => None,
+        });
+        match annotations {
+            Some(annotations) => resolve_annotations(self.constant_pool, annotations),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Type annotations from RuntimeVisibleTypeAnnotations, or
+    /// RuntimeInvisibleTypeAnnotations when `visible` is false. Where they apply is left
+    /// as parsed.
+    pub fn type_annotations(&self, visible: bool) -> &'c [TypeAnnotation] {
+        self.find(|attribute| match attribute {
+            AttributeType::RuntimeVisibleTypeAnnotations(attribute) if visible => {
+                Some(attribute.annotations.as_slice())
+            }
+            AttributeType::RuntimeInvisibleTypeAnnotations(attribute) if !visible => {
+                Some(attribute.annotations.as_slice())
+            }
+            _ => None,
+        })
+        .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InnerClassView<'c> {
+    pub inner_class: &'c str,
+    /// `None` for local and anonymous classes.
+    pub outer_class: Option<&'c str>,
+    /// The simple name; `None` for anonymous classes.
+    pub inner_name: Option<&'c str>,
+    pub access_flags: InnerClassAccessFlags,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnclosingMethodView<'c> {
+    pub class: &'c str,
+    /// Name and descriptor; `None` when the class is in an initialiser.
+    pub method: Option<(&'c str, &'c str)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RecordComponentView<'c> {
+    pub name: &'c str,
+    pub descriptor: &'c str,
+    pub attributes: AttributesView<'c>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParameterView<'c> {
+    /// `None` for a parameter the compiler did not name.
+    pub name: Option<&'c str>,
+    pub access_flags: ParameterFlags,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExceptionHandler<'c> {
+    pub start_pc: u16,
+    pub end_pc: u16,
+    pub handler_pc: u16,
+    /// `None` for a handler that catches everything, as used for `finally`.
+    pub catch_type: Option<&'c str>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineNumber {
+    pub start_pc: u16,
+    pub line_number: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalVariable<'c> {
+    pub start_pc: u16,
+    pub length: u16,
+    pub index: u16,
+    pub name: &'c str,
+    pub descriptor: &'c str,
+    pub signature: Option<&'c str>,
+}
+
+/// A value a ConstantValue attribute can give a field.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConstantValue<'c> {
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    String(&'c str),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnnotationView<'c> {
+    /// The annotation interface as a field descriptor, e.g. `Ljava/lang/Deprecated;`.
+    pub type_descriptor: &'c str,
+    pub elements: Vec<(&'c str, ElementValueView<'c>)>,
+}
+
+impl<'c> AnnotationView<'c> {
+    /// Internal name of the annotation interface, e.g. `java/lang/Deprecated`.
+    pub fn type_name(&self) -> Option<&'c str> {
+        self.type_descriptor.strip_prefix('L')?.strip_suffix(';')
+    }
+
+    pub fn element(&self, name: &str) -> Option<&ElementValueView<'c>> {
+        self.elements
+            .iter()
+            .find(|(element, _)| *element == name)
+            .map(|(_, value)| value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ElementValueView<'c> {
+    Byte(i8),
+    /// A UTF-16 code unit, which may be half of a surrogate pair.
+    Char(u16),
+    Double(f64),
+    Float(f32),
+    Int(i32),
+    Long(i64),
+    Short(i16),
+    Boolean(bool),
+    String(&'c str),
+    Enum {
+        type_descriptor: &'c str,
+        name: &'c str,
+    },
+    /// A class literal as a return descriptor, e.g. `Ljava/lang/String;` or `V`.
+    Class(&'c str),
+    Annotation(AnnotationView<'c>),
+    Array(Vec<ElementValueView<'c>>),
+}
+
+fn optional_class_name(
+    constant_pool: &ConstantPool,
+    index: u16,
+) -> Result<Option<&str>, ViewError> {
+    match index {
+        0 => Ok(None),
+        index => Ok(Some(constant_pool.class_name(index)?)),
+    }
+}
+
+fn optional_utf8(constant_pool: &ConstantPool, index: u16) -> Result<Option<&str>, ViewError> {
+    match index {
+        0 => Ok(None),
+        index => Ok(Some(constant_pool.utf8(index)?)),
+    }
+}
+
+fn class_names<'c>(
+    constant_pool: &'c ConstantPool,
+    indexes: &[u16],
+) -> Result<Vec<&'c str>, ViewError> {
+    indexes
+        .iter()
+        .map(|&index| Ok(constant_pool.class_name(index)?))
+        .collect()
+}
+
+fn join_declaration(modifiers: &str, declaration: &str) -> String {
+    if modifiers.is_empty() {
+        declaration.to_string()
+    } else {
+        format!("{modifiers} {declaration}")
+    }
+}
+
+fn resolve_enclosing_method<'c>(
+    constant_pool: &'c ConstantPool,
+    enclosing: &AttributeEnclosingMethod,
+) -> Result<EnclosingMethodView<'c>, ViewError> {
+    let method = match enclosing.method_index {
+        0 => None,
+        index => Some(constant_pool.name_and_type(index)?),
+    };
+    Ok(EnclosingMethodView {
+        class: constant_pool.class_name(enclosing.class_index)?,
+        method,
+    })
+}
+
+fn resolve_constant_value(
+    constant_pool: &ConstantPool,
+    index: u16,
+) -> Result<ConstantValue<'_>, ViewError> {
+    let value = match constant_pool.entry(index)? {
+        Constant::Integer(integer) => ConstantValue::Int(integer.value()),
+        Constant::Long(long) => ConstantValue::Long(long.value()),
+        Constant::Float(float) => ConstantValue::Float(float.value()),
+        Constant::Double(double) => ConstantValue::Double(double.value()),
+        Constant::String(_) => ConstantValue::String(constant_pool.string(index)?),
+        constant => {
+            return Err(ViewError::ConstantPool(ConstantPoolError::UnexpectedKind {
+                index,
+                expected: "a constant value",
+                found: constant.kind(),
+            }));
+        }
+    };
+    Ok(value)
+}
+
+fn resolve_annotations<'c>(
+    constant_pool: &'c ConstantPool,
+    annotations: &'c [Annotation],
+) -> Result<Vec<AnnotationView<'c>>, ViewError> {
+    annotations
+        .iter()
+        .map(|annotation| resolve_annotation(constant_pool, annotation))
+        .collect()
+}
+
+fn resolve_annotation<'c>(
+    constant_pool: &'c ConstantPool,
+    annotation: &'c Annotation,
+) -> Result<AnnotationView<'c>, ViewError> {
+    let elements = annotation
+        .element_value_pairs
+        .iter()
+        .map(|pair| {
+            Ok((
+                constant_pool.utf8(pair.element_name_index)?,
+                resolve_element_value(constant_pool, &pair.value)?,
+            ))
+        })
+        .collect::<Result<_, ViewError>>()?;
+    Ok(AnnotationView {
+        type_descriptor: constant_pool.utf8(annotation.type_index)?,
+        elements,
+    })
+}
+
+fn resolve_element_value<'c>(
+    constant_pool: &'c ConstantPool,
+    element_value: &'c ElementValue,
+) -> Result<ElementValueView<'c>, ViewError> {
+    let value = match &element_value.value {
+        ElementValueEnum::ConstantValueIndex(constant) => {
+            let index = constant.const_value_index;
+            // Narrow types are stored as CONSTANT_Integer, so the tag decides the type.
+            match element_value.tag {
+                b'B' => ElementValueView::Byte(constant_pool.integer(index)? as i8),
+                b'C' => ElementValueView::Char(constant_pool.integer(index)? as u16),
+                b'D' => ElementValueView::Double(constant_pool.double(index)?),
+                b'F' => ElementValueView::Float(constant_pool.float(index)?),
+                b'I' => ElementValueView::Int(constant_pool.integer(index)?),
+                b'J' => ElementValueView::Long(constant_pool.long(index)?),
+                b'S' => ElementValueView::Short(constant_pool.integer(index)? as i16),
+                b'Z' => ElementValueView::Boolean(constant_pool.integer(index)? != 0),
+                _ => ElementValueView::String(constant_pool.utf8(index)?),
+            }
+        }
+        ElementValueEnum::EnumConstValue(enum_value) => ElementValueView::Enum {
+            type_descriptor: constant_pool.utf8(enum_value.type_name_index)?,
+            name: constant_pool.utf8(enum_value.const_name_index)?,
+        },
+        ElementValueEnum::ClassInfoIndex(class) => {
+            ElementValueView::Class(constant_pool.utf8(class.class_info_index)?)
+        }
+        ElementValueEnum::AnnotationValue(annotation) => {
+            ElementValueView::Annotation(resolve_annotation(constant_pool, annotation)?)
+        }
+        ElementValueEnum::ArrayValue(array) => ElementValueView::Array(
+            array
+                .values
+                .iter()
+                .map(|value| resolve_element_value(constant_pool, value))
+                .collect::<Result<_, ViewError>>()?,
+        ),
+    };
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classfile::{
+        access_flags::{ACC_FINAL, ACC_MANDATED, ACC_SYNTHETIC},
+        descriptor::{BaseType, ReturnType},
+        testing::{u16_bytes, ClassBuilder},
+    };
+
+    const MEMBERS: &[u8] = include_bytes!("../../testdata/Members.class");
+
+    fn members() -> ClassFile {
+        ClassFile::from_bytes(MEMBERS).unwrap()
+    }
+
+    #[test]
+    fn resolves_the_class_and_its_relations() {
+        let class_file = members();
+        let view = class_file.view();
+        assert_eq!(view.name(), Ok("Members"));
+        assert_eq!(view.super_class(), Ok(Some("java/lang/Object")));
+        assert_eq!(view.interfaces(), Ok(vec!["java/lang/Runnable"]));
+        assert!(view.access_flags().is_public());
+        assert!(view.access_flags().is_abstract());
+        assert!(!view.access_flags().is_interface());
+        assert_eq!(view.source_file(), Some(Ok("Members.java")));
+
+        let inner_classes = view.inner_classes().unwrap();
+        let anonymous = inner_classes
+            .iter()
+            .find(|inner| inner.inner_class == "Members$1")
+            .unwrap();
+        assert_eq!(anonymous.outer_class, None);
+        assert_eq!(anonymous.inner_name, None);
+        let local = inner_classes
+            .iter()
+            .find(|inner| inner.inner_class == "Members$1Counter")
+            .unwrap();
+        assert_eq!(local.inner_name, Some("Counter"));
+    }
+
+    #[test]
+    fn resolves_member_names_descriptors_and_flags() {
+        let class_file = members();
+        let view = class_file.view();
+        let methods: Vec<(&str, &str)> = view
+            .methods()
+            .map(|method| (method.name().unwrap(), method.descriptor_str().unwrap()))
+            .collect();
+        assert_eq!(
+            methods,
+            [
+                ("<init>", "()V"),
+                ("names", "(ILjava/lang/String;)Ljava/util/List;"),
+                ("parse", "(Ljava/lang/String;)I"),
+                ("first", "(Ljava/util/List;)Ljava/lang/Comparable;"),
+                ("<clinit>", "()V"),
+            ]
+        );
+
+        let index = view.field("index").unwrap();
+        assert_eq!(
+            index.descriptor(),
+            Ok(FieldType::Object("java/util/Map".to_string()))
+        );
+        assert!(index.access_flags().is_protected());
+        assert!(index.access_flags().is_transient());
+        assert!(!index.access_flags().is_static());
+        let initialiser = view.field("INITIALISER").unwrap();
+        assert_eq!(initialiser.access_flags().to_string(), "static final");
+
+        let names = view
+            .method("names", "(ILjava/lang/String;)Ljava/util/List;")
+            .unwrap();
+        assert_eq!(
+            names.descriptor(),
+            Ok(MethodDescriptor {
+                parameters: vec![
+                    FieldType::Base(BaseType::Int),
+                    FieldType::Object("java/lang/String".to_string()),
+                ],
+                return_type: ReturnType::Value(FieldType::Object("java/util/List".to_string())),
+            })
+        );
+        assert!(names.access_flags().is_final());
+        assert!(!names.access_flags().is_abstract());
+        assert_eq!(
+            names.exceptions(),
+            Ok(vec![
+                "java/io/IOException",
+                "java/lang/InterruptedException"
+            ])
+        );
+        let parameters = names.parameters().unwrap().unwrap();
+        assert_eq!(parameters[0].name, Some("count"));
+        assert!(parameters[0].access_flags.is_final());
+        assert_eq!(parameters[1].name, Some("prefix"));
+        assert!(view.method("names", "()V").is_none());
+    }
+
+    #[test]
+    fn declarations_use_the_generic_signatures() {
+        let class_file = members();
+        let view = class_file.view();
+        assert_eq!(
+            view.signature().unwrap().unwrap().to_java("Members"),
+            "Members<K extends Comparable<? super K>> implements Runnable"
+        );
+        assert_eq!(
+            view.field("index").unwrap().declaration().unwrap(),
+            "protected transient Map<K, List<String>> index"
+        );
+        let declarations: Vec<String> = view
+            .methods()
+            .map(|method| method.declaration().unwrap())
+            .collect();
+        assert_eq!(
+            declarations[1..4],
+            [
+                "public final List<String> names(int, String) throws IOException, InterruptedException",
+                "static int parse(String)",
+                "public abstract <T extends K> T first(List<? extends T>)",
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_exception_handlers_and_line_numbers() {
+        let class_file = members();
+        let view = class_file.view();
+        let code = view
+            .method("parse", "(Ljava/lang/String;)I")
+            .unwrap()
+            .code()
+            .unwrap();
+        assert_eq!(
+            code.exception_handlers().unwrap(),
+            [
+                ExceptionHandler {
+                    start_pc: 0,
+                    end_pc: 5,
+                    handler_pc: 14,
+                    catch_type: Some("java/lang/NumberFormatException"),
+                },
+                ExceptionHandler {
+                    start_pc: 0,
+                    end_pc: 5,
+                    handler_pc: 26,
+                    catch_type: None,
+                },
+                ExceptionHandler {
+                    start_pc: 14,
+                    end_pc: 17,
+                    handler_pc: 26,
+                    catch_type: None,
+                },
+            ]
+        );
+        // The finally block is copied after the return, so line 28 comes back at 12.
+        let lines: Vec<(u16, u16)> = code.line_numbers()[..3]
+            .iter()
+            .map(|line| (line.start_pc, line.line_number))
+            .collect();
+        assert_eq!(lines, [(0, 28), (5, 32), (12, 28)]);
+        assert_eq!(code.line_at(13), Some(28));
+        assert_eq!(code.line_at(34), Some(33));
+
+        let abstract_method = view
+            .method("first", "(Ljava/util/List;)Ljava/lang/Comparable;")
+            .unwrap();
+        assert!(abstract_method.code().is_none());
+    }
+
+    #[test]
+    fn pairs_local_variables_with_their_generic_signatures() {
+        let class_file = members();
+        let view = class_file.view();
+        let code = view
+            .method("names", "(ILjava/lang/String;)Ljava/util/List;")
+            .unwrap()
+            .code()
+            .unwrap();
+        assert_eq!((code.max_stack(), code.max_locals()), (3, 5));
+        let local_variables: Vec<(&str, u16, u16, Option<&str>)> = code
+            .local_variables()
+            .unwrap()
+            .iter()
+            .map(|variable| {
+                (
+                    variable.name,
+                    variable.start_pc,
+                    variable.length,
+                    variable.signature,
+                )
+            })
+            .collect();
+        assert_eq!(
+            local_variables,
+            [
+                ("i", 11, 27, None),
+                ("this", 0, 57, Some("LMembers<TK;>;")),
+                ("count", 0, 57, None),
+                ("prefix", 0, 57, None),
+                ("names", 8, 49, Some("Ljava/util/List<Ljava/lang/String;>;")),
+            ]
+        );
+    }
+
+    #[test]
+    fn local_variables_take_the_signature_of_the_same_range() {
+        let mut builder = ClassBuilder::new("Sample");
+        let list = builder.utf8("list");
+        let descriptor = builder.utf8("Ljava/util/List;");
+        let signature = builder.utf8("Ljava/util/List<Ljava/lang/String;>;");
+        let other = builder.utf8("Ljava/util/List<Ljava/lang/Integer;>;");
+        // Slot 1 holds `list` twice, over 0..4 and over 4..6; only the first has a
+        // LocalVariableTypeTable entry, and one with another length must not match.
+        let local_variables = [
+            &u16_bytes(&[2])[..],
+            &u16_bytes(&[0, 4, list, descriptor, 1]),
+            &u16_bytes(&[4, 2, list, descriptor, 1]),
+        ]
+        .concat();
+        let local_variable_types = [
+            &u16_bytes(&[2])[..],
+            &u16_bytes(&[0, 4, list, signature, 1]),
+            &u16_bytes(&[4, 1, list, other, 1]),
+        ]
+        .concat();
+        let attributes = [
+            builder.attribute("LocalVariableTable", &local_variables),
+            builder.attribute("LocalVariableTypeTable", &local_variable_types),
+        ];
+        let code = builder.code(1, 2, &[0; 6], &[], &attributes);
+        builder.method(0x0009, "run", "()V", &[code]);
+        let class_file = ClassFile::from_bytes(&builder.build()).unwrap();
+
+        let view = class_file.view();
+        let code = view.method("run", "()V").unwrap().code().unwrap();
+        let local_variables = code.local_variables().unwrap();
+        assert_eq!(
+            local_variables[0].signature,
+            Some("Ljava/util/List<Ljava/lang/String;>;")
+        );
+        assert_eq!(local_variables[1].signature, None);
+        assert_eq!(local_variables[1].start_pc, 4);
+    }
+
+    #[test]
+    fn resolves_parameters_and_constant_values() {
+        let mut builder = ClassBuilder::new("Sample");
+        let greeting = builder.string("hello");
+        let constant_value = builder.attribute("ConstantValue", &u16_bytes(&[greeting]));
+        builder.field(0x0019, "GREETING", "Ljava/lang/String;", &[constant_value]);
+        let answer = builder.double(4.2);
+        let constant_value = builder.attribute("ConstantValue", &u16_bytes(&[answer]));
+        builder.field(0x0019, "ANSWER", "D", &[constant_value]);
+        let name = builder.utf8("name");
+        let parameters = [
+            &[3][..],
+            &u16_bytes(&[0, ACC_MANDATED]),
+            &u16_bytes(&[name, ACC_FINAL]),
+            &u16_bytes(&[0, ACC_SYNTHETIC]),
+        ]
+        .concat();
+        let parameters = builder.attribute("MethodParameters", &parameters);
+        builder.method(0x0401, "set", "(ILjava/lang/String;J)V", &[parameters]);
+        let class_file = ClassFile::from_bytes(&builder.build()).unwrap();
+
+        let view = class_file.view();
+        let field = view.field("GREETING").unwrap();
+        assert_eq!(
+            field.constant_value().unwrap().unwrap(),
+            ConstantValue::String("hello")
+        );
+        let field = view.field("ANSWER").unwrap();
+        assert_eq!(
+            field.constant_value().unwrap().unwrap(),
+            ConstantValue::Double(4.2)
+        );
+        let method = view.method("set", "(ILjava/lang/String;J)V").unwrap();
+        let parameters = method.parameters().unwrap().unwrap();
+        assert_eq!(parameters.len(), 3);
+        assert!(parameters[0].access_flags.is_mandated());
+        assert_eq!(parameters[1].name, Some("name"));
+        assert_eq!(parameters[1].access_flags.to_string(), "final");
+        assert!(parameters[2].access_flags.is_synthetic());
+    }
+}
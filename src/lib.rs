@@ -0,0 +1,23 @@
+//! A parser for JVM class files.
+//!
+//! [`ClassFile`] is the model of a class file as laid out in the JVMS, with every
+//! constant pool index kept as read; [`ClassFile::view`] resolves it into names, types
+//! and attributes. For indexing many classes,
+//! [`ClassSummary`](classfile::scan::ClassSummary) and
+//! [`ClassFileRef`](classfile::borrowed::ClassFileRef) read less.
+//!
+//! # Features
+//!
+//! - `cli` (default): the `rust-java` binary.
+//! - `archives` (default): reading jars, memory-mapped input and parallel parsing in
+//!   `classfile::archive` and `classfile::bulk`.
+//! - `serde`: `Serialize` for the class file model.
+
+pub mod classfile;
+
+pub use classfile::{
+    error::{ClassParseError, ParseErrorKind},
+    options::ParseOptions,
+    view::{ClassView, ViewError},
+    ClassFile,
+};
@@ -1,8 +1,65 @@
-mod classfile;
+use std::{env, fs, process};
 
-use crate::classfile::{fields::*, ClassFile};
+use rust_java::classfile::{dump::hex_dump, ClassFile};
+
+const USAGE: &str = "usage: rust-java [--dump | --lenient | --validate] <class file>";
 
 fn main() {
-    let class_file = ClassFile::new("/home/rob/Documents/Projects/rust-java/ClassFileCheck.class");
-    println!("class file \n: {:?}", class_file);
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["--dump", path] => match fs::read(path) {
+            Ok(bytes) => print!("{}", hex_dump(&bytes)),
+            Err(err) => {
+                eprintln!("could not read {path}: {err}");
+                process::exit(1);
+            }
+        },
+        ["--lenient", path] => {
+            let parsed = fs::read(path)
+                .map_err(|err| format!("could not read {path}: {err}"))
+                .and_then(|bytes| {
+                    ClassFile::from_bytes_lenient(&bytes)
+                        .map_err(|err| format!("could not parse class file: {err}"))
+                });
+            match parsed {
+                Ok((class_file, diagnostics)) => {
+                    for diagnostic in diagnostics {
+                        eprintln!("warning: {diagnostic}");
+                    }
+                    println!("class file \n: {:?}", class_file);
+                }
+                Err(message) => {
+                    eprintln!("{message}");
+                    process::exit(1);
+                }
+            }
+        }
+        ["--validate", path] => match ClassFile::parse(path) {
+            Ok(class_file) => {
+                let violations = class_file.validate();
+                for violation in &violations {
+                    println!("{violation}");
+                }
+                if !violations.is_empty() {
+                    process::exit(1);
+                }
+            }
+            Err(err) => {
+                eprintln!("could not parse class file: {err}");
+                process::exit(1);
+            }
+        },
+        [path] => match ClassFile::parse(path) {
+            Ok(class_file) => println!("class file \n: {:?}", class_file),
+            Err(err) => {
+                eprintln!("could not parse class file: {err}");
+                process::exit(1);
+            }
+        },
+        _ => {
+            eprintln!("{USAGE}");
+            process::exit(2);
+        }
+    }
 }
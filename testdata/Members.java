@@ -0,0 +1,37 @@
+import java.io.IOException;
+import java.util.ArrayList;
+import java.util.List;
+import java.util.Map;
+
+public abstract class Members<K extends Comparable<? super K>> implements Runnable {
+    static final Runnable INITIALISER = new Runnable() {
+        public void run() {}
+    };
+
+    protected transient Map<K, List<String>> index;
+
+    public final List<String> names(final int count, String prefix)
+            throws IOException, InterruptedException {
+        List<String> names = new ArrayList<String>();
+        for (int i = 0; i < count; i++) {
+            names.add(prefix + i);
+        }
+        class Counter {
+            int seen;
+        }
+        new Counter().seen = names.size();
+        return names;
+    }
+
+    static int parse(String text) {
+        try {
+            return Integer.parseInt(text);
+        } catch (NumberFormatException e) {
+            return -1;
+        } finally {
+            System.out.println(text);
+        }
+    }
+
+    public abstract <T extends K> T first(List<? extends T> items);
+}
@@ -0,0 +1,17 @@
+import java.lang.annotation.ElementType;
+import java.lang.annotation.Retention;
+import java.lang.annotation.RetentionPolicy;
+import java.lang.annotation.Target;
+import java.util.List;
+
+public sealed interface Shapes permits Shapes.Circle, Shapes.Polygon {
+    @Retention(RetentionPolicy.RUNTIME)
+    @Target(ElementType.RECORD_COMPONENT)
+    @interface Unit {
+        String value();
+    }
+
+    record Circle(@Unit("cm") double radius) implements Shapes {}
+
+    record Polygon<P>(List<P> points, int sides) implements Shapes {}
+}
@@ -0,0 +1,28 @@
+import java.util.ArrayList;
+import java.util.Collections;
+import java.util.List;
+import java.util.Map;
+
+public class TypeAnnotations<@Tag("parameter") T extends @Tag("bound") Number>
+        implements @Tag("interface") Comparable<T> {
+    Map<String, @Tag("value") List<@Tag("element") String @Tag("array") []>> table;
+
+    public @Tag("return") String convert(@Tag("formal") T value)
+            throws @Tag("throws") IllegalStateException {
+        @Tag("local") List<String> items = new @Tag("new") ArrayList<>();
+        if (value instanceof @Tag("instanceof") Integer) {
+            items.add("integer");
+        }
+        Object text = value.toString();
+        try {
+            items.add((@Tag("cast") String) text);
+        } catch (@Tag("catch") ClassCastException e) {
+            return null;
+        }
+        return Collections.<@Tag("argument") String>emptyList().toString() + items;
+    }
+
+    public int compareTo(T other) {
+        return 0;
+    }
+}
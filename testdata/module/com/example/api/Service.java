@@ -0,0 +1,5 @@
+package com.example.api;
+
+public interface Service {
+    static void main(String[] args) {}
+}
@@ -0,0 +1,3 @@
+package com.example.internal;
+
+public class ServiceImpl implements com.example.api.Service {}
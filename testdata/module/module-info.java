@@ -0,0 +1,7 @@
+module com.example {
+    requires transitive java.logging;
+    exports com.example.api;
+    opens com.example.internal to java.logging;
+    uses com.example.api.Service;
+    provides com.example.api.Service with com.example.internal.ServiceImpl;
+}